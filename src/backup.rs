@@ -0,0 +1,243 @@
+//! All of the user's files bundled into one, to move them to another machine or to keep
+//! them in a dotfiles repository. The bundle is a JSON document mapping a name for each
+//! file to its contents, so it diffs well under version control. Binary files, like
+//! compressed dictionaries, are stored base64 encoded.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::{paths::Paths, store};
+
+/// Version 2 added binary files.
+const VERSION: u32 = 2;
+
+/// The prefix of the names of imported dictionaries.
+const DICTIONARIES: &str = "dictionaries/";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    version: u32,
+    /// The contents of every file that exists, by name.
+    files: BTreeMap<String, Contents>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Contents {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl Contents {
+    fn new(contents: Vec<u8>) -> Self {
+        match String::from_utf8(contents) {
+            Ok(text) => Self::Text(text),
+            Err(e) => Self::Binary {
+                base64: STANDARD.encode(e.into_bytes()),
+            },
+        }
+    }
+
+    fn bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Text(text) => Ok(text.clone().into_bytes()),
+            Self::Binary { base64 } => STANDARD
+                .decode(base64)
+                .map_err(|e| anyhow!("invalid base64: {}", e)),
+        }
+    }
+}
+
+impl Backup {
+    /// Reads all of the user's files. Files which don't exist are left out.
+    pub fn create(paths: &Paths) -> anyhow::Result<Self> {
+        let mut files = BTreeMap::new();
+        for (name, path) in user_files(paths)? {
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(anyhow!("failed to read {}: {}", path.display(), e)),
+            };
+            files.insert(name, Contents::new(contents));
+        }
+
+        Ok(Self {
+            version: VERSION,
+            files,
+        })
+    }
+
+    /// The names of the bundled files.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// Writes the bundled files to where the given paths point. Existing files are
+    /// replaced, keeping their previous contents next to them as backups. Files missing
+    /// from the bundle are left alone. Returns the paths written.
+    pub fn restore(&self, paths: &Paths) -> anyhow::Result<Vec<PathBuf>> {
+        // Check all names first, so that a bad bundle doesn't restore only some files.
+        let targets = self
+            .files
+            .iter()
+            .map(|(name, contents)| {
+                let contents = contents
+                    .bytes()
+                    .map_err(|e| anyhow!("invalid file \"{}\" in backup: {}", name, e))?;
+                Ok((target(paths, name)?, contents))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut written = vec![];
+        for (path, contents) in targets {
+            store::write_with_backup(&path, &contents)
+                .map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+        let backup: Self = serde_json::from_str(&text)
+            .map_err(|e| anyhow!("{} is not a backup: {}", path.display(), e))?;
+        if backup.version > VERSION {
+            return Err(anyhow!(
+                "{} was made by a newer version of the program",
+                path.display()
+            ));
+        }
+        Ok(backup)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        store::write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+}
+
+/// The user's files with their names in a bundle.
+fn user_files(paths: &Paths) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files: Vec<_> = [
+        ("config", paths.config_file()),
+        ("stopwords", paths.stopwords()),
+        ("known words", paths.known_words()),
+        ("vocab", paths.vocab()),
+        ("history", paths.history()),
+        ("input history", paths.input_history()),
+        ("lookups", paths.lookups()),
+        ("pronunciations", paths.pronunciations()),
+    ]
+    .into_iter()
+    .map(|(name, path)| (name.to_string(), path))
+    .collect();
+
+    match fs::read_dir(paths.dictionaries_dir()) {
+        Ok(entries) => {
+            for entry in entries {
+                let entry = entry?;
+                if !entry.file_type()?.is_file() {
+                    continue;
+                }
+                let name = entry.file_name();
+                let Some(name) = name.to_str() else {
+                    continue;
+                };
+                // Left behind by the store, not dictionaries.
+                if name.ends_with(".bak") || name.ends_with(".tmp") || name.contains(".corrupt-") {
+                    continue;
+                }
+                files.push((format!("{}{}", DICTIONARIES, name), entry.path()));
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    Ok(files)
+}
+
+/// Where the bundled file with the given name is restored to.
+fn target(paths: &Paths, name: &str) -> anyhow::Result<PathBuf> {
+    if let Some(file) = name.strip_prefix(DICTIONARIES) {
+        // The name must not lead out of the directory.
+        let mut components = Path::new(file).components();
+        return match (components.next(), components.next()) {
+            (Some(std::path::Component::Normal(file)), None) => {
+                Ok(paths.dictionaries_dir().join(file))
+            }
+            _ => Err(anyhow!("invalid dictionary name \"{}\" in backup", file)),
+        };
+    }
+
+    user_files(paths)?
+        .into_iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, path)| path)
+        .ok_or_else(|| anyhow!("unknown file \"{}\" in backup", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Backup;
+    use crate::paths::Paths;
+
+    #[test]
+    fn create_and_restore() {
+        let root = std::env::temp_dir().join(format!("backup-test-{}", std::process::id()));
+        let (old, new) = (
+            Paths::in_dir(&root.join("old")),
+            Paths::in_dir(&root.join("new")),
+        );
+
+        fs::create_dir_all(old.dictionaries_dir()).unwrap();
+        fs::create_dir_all(old.config_dir()).unwrap();
+        fs::write(old.config_file(), "[ranking]\nfrequency = 1\n").unwrap();
+        fs::write(old.dictionaries_dir().join("mine.txt"), "Hund {m}\tdog\n").unwrap();
+        let compressed = [0x28, 0xb5, 0x2f, 0xfd, 0x00, 0xff];
+        fs::write(old.dictionaries_dir().join("mine.txt.zst"), compressed).unwrap();
+
+        let file = root.join("backup.json");
+        Backup::create(&old).unwrap().save(&file).unwrap();
+        let backup = Backup::load(&file).unwrap();
+        assert_eq!(
+            backup.names().collect::<Vec<_>>(),
+            [
+                "config",
+                "dictionaries/mine.txt",
+                "dictionaries/mine.txt.zst"
+            ]
+        );
+        assert!(fs::read_to_string(&file).unwrap().contains("\"base64\""));
+
+        backup.restore(&new).unwrap();
+        assert_eq!(
+            fs::read_to_string(new.dictionaries_dir().join("mine.txt")).unwrap(),
+            "Hund {m}\tdog\n"
+        );
+        assert_eq!(
+            fs::read(new.dictionaries_dir().join("mine.txt.zst")).unwrap(),
+            compressed
+        );
+        assert_eq!(Backup::create(&new).unwrap(), backup);
+
+        let evil: Backup = serde_json::from_str(
+            r#"{"version": 1, "files": {"dictionaries/../config.toml": "", "vocab": "{}"}}"#,
+        )
+        .unwrap();
+        assert!(evil.restore(&new).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
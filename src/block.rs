@@ -0,0 +1,193 @@
+//! Rules hiding entries by their tags, configured in the `[block]` table of the config
+//! file, e.g. `vulgar = "[vulg.] and not [idiom]"`. A rule is lifted for a single query
+//! with `--show <name>`.
+
+use anyhow::anyhow;
+
+use crate::{entry::Entry, util};
+
+/// A named rule hiding the entries its condition matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub condition: Condition,
+}
+
+/// A combination of tags with `and`, `or`, `not` and parentheses. Tags are written with
+/// or without brackets, as in "[vulg.]" or "vulg".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Tag(String),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Rule {
+    pub fn blocks(&self, entry: &Entry) -> bool {
+        self.condition.matches(&tags(entry))
+    }
+}
+
+impl Condition {
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let condition = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(condition),
+            Some(token) => Err(anyhow!("unexpected \"{}\" in rule", token)),
+        }
+    }
+
+    fn matches(&self, tags: &[&str]) -> bool {
+        match self {
+            Self::Tag(tag) => tags.iter().any(|t| tag_eq(t, tag)),
+            Self::Not(c) => !c.matches(tags),
+            Self::And(a, b) => a.matches(tags) && b.matches(tags),
+            Self::Or(a, b) => a.matches(tags) || b.matches(tags),
+        }
+    }
+}
+
+/// The subjects, word classes and annotations of an entry.
+fn tags(entry: &Entry) -> Vec<&str> {
+    entry
+        .subjects
+        .iter()
+        .map(|s| s.as_str())
+        .chain(entry.word_classes.iter().map(|c| c.as_str()))
+        .chain(
+            [&entry.german, &entry.english]
+                .into_iter()
+                .flat_map(|t| t.annotations())
+                .map(|a| a.value),
+        )
+        .collect()
+}
+
+/// Compares tags ignoring case, brackets and the abbreviation dot.
+fn tag_eq(a: &str, b: &str) -> bool {
+    let normalize = |v: &str| {
+        v.trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_string()
+    };
+    let (a, b) = (normalize(a), normalize(b));
+    util::case_fold_eq(&a, &b)
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<String>> {
+    let mut tokens = vec![];
+    let mut rest = input.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '(' | ')' => 1,
+            '[' => rest
+                .find(']')
+                .map(|end| end + 1)
+                .ok_or_else(|| anyhow!("unclosed \"[\" in rule"))?,
+            _ => rest
+                .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                .unwrap_or(rest.len()),
+        };
+        tokens.push(rest[..len].to_string());
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser, with `not` binding tighter than `and`, and `and` tighter
+/// than `or`.
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn or(&mut self) -> anyhow::Result<Condition> {
+        let mut condition = self.and()?;
+        while self.peek() == Some("or") {
+            self.pos += 1;
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Condition> {
+        let mut condition = self.not()?;
+        while self.peek() == Some("and") {
+            self.pos += 1;
+            condition = Condition::And(Box::new(condition), Box::new(self.not()?));
+        }
+        Ok(condition)
+    }
+
+    fn not(&mut self) -> anyhow::Result<Condition> {
+        let token = self
+            .peek()
+            .ok_or_else(|| anyhow!("unexpected end of rule"))?
+            .to_string();
+        self.pos += 1;
+
+        match token.as_str() {
+            "not" => Ok(Condition::Not(Box::new(self.not()?))),
+            "(" => {
+                let condition = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err(anyhow!("missing \")\" in rule"));
+                }
+                self.pos += 1;
+                Ok(condition)
+            }
+            ")" | "and" | "or" => Err(anyhow!("unexpected \"{}\" in rule", token)),
+            tag => Ok(Condition::Tag(tag.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Condition, Rule};
+    use crate::Dictionary;
+
+    #[test]
+    fn rules() {
+        let dict = Dictionary::load(
+            "Scheiße {f} [vulg.]\tshit [vulg.]\tnoun\n\
+             Scheiß drauf! [vulg.]\tScrew it!\t\t[idiom]\n\
+             Weib {n} [veraltet]\twife [archaic]\tnoun\n\
+             Hund {m}\tdog\tnoun\t[zool.]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let rule = |condition| Rule {
+            name: String::new(),
+            condition: Condition::parse(condition).unwrap(),
+        };
+        let blocked =
+            |rule: Rule| -> Vec<bool> { dict.entries().iter().map(|e| rule.blocks(e)).collect() };
+
+        assert_eq!(
+            blocked(rule("[vulg.] and not [idiom]")),
+            [true, false, false, false]
+        );
+        assert_eq!(
+            blocked(rule("VULG or (archaic and noun)")),
+            [true, true, true, false]
+        );
+        assert_eq!(blocked(rule("not not zool")), [false, false, false, true]);
+
+        assert!(Condition::parse("vulg and").is_err());
+        assert!(Condition::parse("(vulg or obs.").is_err());
+        assert!(Condition::parse("[vulg.").is_err());
+        assert!(Condition::parse("vulg obs").is_err());
+    }
+}
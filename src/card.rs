@@ -0,0 +1,173 @@
+//! The dictionary page of a German word: a card per sense with its article, plural,
+//! translations, subjects and the entries it refers to.
+
+use crate::{
+    entry::{is_placeholder, AnnotationKind, Entry, Gender, WordClass},
+    rank, util, Dictionary,
+};
+
+/// The most entries a card lists under "see also".
+const SEE_ALSO_LIMIT: usize = 8;
+
+/// Prefixes of annotations which refer to other entries, as in "[vgl. Schulschwänzer]".
+const REFERENCE_PREFIXES: &[&str] = &["vgl.", "siehe", "cf.", "see:"];
+
+/// One sense of a word: its entries with the same word class and gender.
+#[derive(Debug)]
+pub struct Card<'a> {
+    /// Spelled like the dictionary does.
+    pub headword: &'a str,
+    pub word_class: Option<&'a WordClass>,
+    /// None for words which aren't nouns, and for nouns which only exist in the plural.
+    pub gender: Option<Gender>,
+    /// Whether the noun only exists in the plural.
+    pub plural_only: bool,
+    /// The translations grouped by the register the entries are marked with, the
+    /// unmarked ones first under `None`.
+    pub registers: Vec<(Option<&'a str>, Vec<&'a Entry>)>,
+    /// The subjects of all entries, in the order they first appear.
+    pub subjects: Vec<&'a str>,
+    /// The words the annotations of the entries refer to.
+    pub references: Vec<&'a str>,
+    /// Entries of the same word class containing the word, like compounds and idioms.
+    pub see_also: Vec<&'a Entry>,
+}
+
+impl Card<'_> {
+    /// The entries of the card, in dictionary order within each register.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.registers
+            .iter()
+            .flat_map(|(_, entries)| entries)
+            .copied()
+    }
+}
+
+/// Whether the entry is the word alone, except for placeholders, as "jdm. helfen" is
+/// for "helfen".
+fn is_entry_of(entry: &Entry, word: &str) -> bool {
+    let mut keywords = entry.german.keywords().filter(|k| !is_placeholder(k));
+    matches!(
+        (keywords.next(), keywords.next()),
+        (Some(k), None) if util::case_fold_eq(k, word)
+    )
+}
+
+/// The cards of the German word, in the order their senses first appear in the
+/// dictionary. Empty if the dictionary has no entry for the word alone.
+pub fn cards<'a>(dict: &'a Dictionary, word: &str) -> Vec<Card<'a>> {
+    let mut cards: Vec<Card> = vec![];
+
+    for entry in dict.entries().iter().filter(|e| is_entry_of(e, word)) {
+        let word_class = entry.word_classes.first();
+        let gender = entry.german.gender();
+        let card = match cards
+            .iter_mut()
+            .find(|c| c.word_class == word_class && c.gender == gender)
+        {
+            Some(card) => card,
+            None => {
+                cards.push(Card {
+                    headword: entry.headword().unwrap_or_default(),
+                    word_class,
+                    gender,
+                    plural_only: gender.is_none() && entry.german.is_plural(),
+                    registers: vec![(None, vec![])],
+                    subjects: vec![],
+                    references: vec![],
+                    see_also: vec![],
+                });
+                cards.last_mut().unwrap()
+            }
+        };
+
+        let register = rank::register(&entry.german).or_else(|| rank::register(&entry.english));
+        match card.registers.iter_mut().find(|(r, _)| *r == register) {
+            Some((_, entries)) => entries.push(entry),
+            None => card.registers.push((register, vec![entry])),
+        }
+        for subject in &entry.subjects {
+            insert(&mut card.subjects, subject.as_str());
+        }
+        for annotation in entry.german.annotations() {
+            if annotation.kind == AnnotationKind::Explanation {
+                if let Some(reference) = reference(annotation.value) {
+                    insert(&mut card.references, reference);
+                }
+            }
+        }
+    }
+
+    for card in &mut cards {
+        card.registers.retain(|(_, entries)| !entries.is_empty());
+        card.see_also = dict
+            .entries()
+            .iter()
+            .filter(|e| e.word_classes.first() == card.word_class && !is_entry_of(e, word))
+            .filter(|e| e.german.keywords().any(|k| util::case_fold_eq(k, word)))
+            .take(SEE_ALSO_LIMIT)
+            .collect();
+    }
+
+    cards
+}
+
+/// The word an annotation like "[vgl. Schulschwänzer]" refers to.
+fn reference(annotation: &str) -> Option<&str> {
+    REFERENCE_PREFIXES
+        .iter()
+        .find_map(|p| annotation.strip_prefix(p))
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+}
+
+fn insert<'a>(values: &mut Vec<&'a str>, value: &'a str) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cards;
+    use crate::{
+        entry::{Gender, WordClass},
+        Dictionary,
+    };
+
+    #[test]
+    fn cards_by_sense() {
+        let dict = Dictionary::load(
+            "See {m}\tlake\tnoun\t[geogr.]\n\
+             See {f}\tsea\tnoun\t\n\
+             See {f} [vgl. Meer]\tocean\tnoun\t[geogr.]\n\
+             See {m} [ugs.]\tpond [coll.]\tnoun\t\n\
+             Toter See {m}\tDead Sea\tnoun\t[geogr.]\n\
+             sehen\tto see\tverb\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let cards = cards(&dict, "see");
+        assert_eq!(cards.len(), 2);
+
+        let lake = &cards[0];
+        assert_eq!(lake.headword, "See");
+        assert_eq!(lake.word_class, Some(&WordClass::Noun));
+        assert_eq!(lake.gender, Some(Gender::Masculine));
+        let registers: Vec<_> = lake
+            .registers
+            .iter()
+            .map(|(r, entries)| (*r, entries.len()))
+            .collect();
+        assert_eq!(registers, [(None, 1), (Some("ugs."), 1)]);
+        assert_eq!(lake.subjects, ["geogr."]);
+        assert_eq!(lake.see_also.len(), 1);
+        assert_eq!(lake.see_also[0].english.to_string(), "Dead Sea");
+
+        let sea = &cards[1];
+        assert_eq!(sea.gender, Some(Gender::Feminine));
+        assert_eq!(sea.entries().count(), 2);
+        assert_eq!(sea.references, ["Meer"]);
+    }
+}
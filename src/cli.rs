@@ -0,0 +1,751 @@
+//! The query specifiers and flags the program takes, described for shell completions and
+//! `explain`. [`Query::try_from`](crate::query::Query) does the parsing.
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+/// What an argument takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    None,
+    /// German words, completed from the saved vocabulary.
+    Word,
+    /// One of the given values.
+    Choice(&'static [&'static str]),
+    File,
+    /// Anything, like English words or numbers, which can't be completed.
+    Any,
+}
+
+#[derive(Debug)]
+pub struct Command {
+    /// The specifier and its aliases.
+    pub names: &'static [&'static str],
+    pub usage: &'static str,
+    pub about: &'static str,
+    /// Whether the specifier is a command on its own. Other specifiers given without
+    /// arguments are looked up as words.
+    pub standalone: bool,
+    /// The actions completing the first argument, as in "v list".
+    pub actions: &'static [&'static str],
+    /// The arguments, after the action if there is one.
+    pub args: Value,
+}
+
+#[derive(Debug)]
+pub struct Flag {
+    pub name: &'static str,
+    pub value: Value,
+    pub about: &'static str,
+}
+
+const fn command(
+    names: &'static [&'static str],
+    usage: &'static str,
+    about: &'static str,
+    args: Value,
+) -> Command {
+    Command {
+        names,
+        usage,
+        about,
+        standalone: false,
+        actions: &[],
+        args,
+    }
+}
+
+const fn standalone(
+    names: &'static [&'static str],
+    usage: &'static str,
+    about: &'static str,
+    args: Value,
+) -> Command {
+    Command {
+        standalone: true,
+        ..command(names, usage, about, args)
+    }
+}
+
+const fn actions(
+    names: &'static [&'static str],
+    usage: &'static str,
+    about: &'static str,
+    actions: &'static [&'static str],
+    args: Value,
+) -> Command {
+    Command {
+        actions,
+        ..command(names, usage, about, args)
+    }
+}
+
+pub const COMMANDS: &[Command] = &[
+    command(&["d"], "WORDS", "look up German words", Value::Word),
+    command(
+        &["dv"],
+        "WORDS",
+        "look up German words, with details",
+        Value::Word,
+    ),
+    command(&["e"], "WORDS", "look up English words", Value::Any),
+    command(
+        &["ev"],
+        "WORDS",
+        "look up English words, with details",
+        Value::Any,
+    ),
+    command(
+        &["df"],
+        "WORD",
+        "look up a German word, allowing for typos",
+        Value::Word,
+    ),
+    command(
+        &["ef"],
+        "WORD",
+        "look up an English word, allowing for typos",
+        Value::Any,
+    ),
+    command(
+        &["g", "gender"],
+        "WORD | --batch FILE",
+        "the genders and plurals of a German noun",
+        Value::Word,
+    ),
+    command(
+        &["ge"],
+        "WORD",
+        "the German nouns translating an English noun, with their genders",
+        Value::Any,
+    ),
+    command(
+        &["show"],
+        "ID|WORD",
+        "an entry by its ID, or a card per sense of a German word",
+        Value::Word,
+    ),
+    command(
+        &["p"],
+        "WORDS",
+        "the German phrases and idioms containing the words",
+        Value::Word,
+    ),
+    command(
+        &["r"],
+        "WORD [--count N]",
+        "the German headwords containing the word",
+        Value::Word,
+    ),
+    command(
+        &["near"],
+        "WORD",
+        "the German headwords sorting around the word",
+        Value::Word,
+    ),
+    command(
+        &["compare"],
+        "WORD1 WORD2",
+        "the translations of two German words side by side",
+        Value::Word,
+    ),
+    command(
+        &["list"],
+        "[--gender m|f|n] [--prefix P] [--suffix S] [--class CLASS]",
+        "German headwords matching the conditions",
+        Value::Any,
+    ),
+    standalone(&["i"], "", "interactive lookups", Value::None),
+    standalone(&["t"], "", "browse the dictionary full screen", Value::None),
+    standalone(
+        &["b"],
+        "[FILE]",
+        "look up every line of the file, or of stdin",
+        Value::File,
+    ),
+    actions(
+        &["v"],
+        "list | clear | export --out FILE [--per-sense]",
+        "the saved vocabulary",
+        &["list", "clear", "export"],
+        Value::File,
+    ),
+    actions(
+        &["known"],
+        "list | add WORDS | remove WORDS",
+        "the words marked as known",
+        &["list", "add", "remove"],
+        Value::Word,
+    ),
+    standalone(
+        &["q"],
+        "",
+        "a flashcard quiz over the cards due for review",
+        Value::None,
+    ),
+    actions(
+        &["review"],
+        "due [--count|--notify] | stats",
+        "the cards due for review and the progress in quizzes",
+        &["due", "stats"],
+        Value::Any,
+    ),
+    actions(
+        &["quiz"],
+        "recent [--days N]",
+        "a quiz over the words looked up lately",
+        &["recent"],
+        Value::Any,
+    ),
+    command(
+        &["session"],
+        "--text FILE [--minutes N]",
+        "read a text with glosses of the unknown words",
+        Value::File,
+    ),
+    standalone(
+        &["stats"],
+        "[--linguistics]",
+        "figures about the dictionary",
+        Value::Any,
+    ),
+    command(
+        &["export"],
+        "[--filter FILTER]... --out FILE",
+        "write the matching entries to a file",
+        Value::File,
+    ),
+    command(
+        &["merge"],
+        "FILE... --out FILE",
+        "merge dictionaries into one file",
+        Value::File,
+    ),
+    command(
+        &["update"],
+        "FILE|URL",
+        "install a dict.cc export in place of the embedded dictionary",
+        Value::File,
+    ),
+    actions(
+        &["index"],
+        "build",
+        "build the keyword index of the dictionary",
+        &["build"],
+        Value::None,
+    ),
+    actions(
+        &["backup"],
+        "create|restore FILE",
+        "bundle the user files into one, or put them back",
+        &["create", "restore"],
+        Value::File,
+    ),
+    Command {
+        actions: &["stop"],
+        ..standalone(
+            &["daemon"],
+            "[stop]",
+            "keep the dictionary in memory for other invocations",
+            Value::None,
+        )
+    },
+    standalone(
+        &["serve"],
+        "[ADDRESS]",
+        "answer lookups over HTTP",
+        Value::Any,
+    ),
+    standalone(
+        &["paths"],
+        "",
+        "where the files of the program are",
+        Value::None,
+    ),
+    actions(
+        &["completions"],
+        "bash|zsh|fish",
+        "the completion script for the shell",
+        &["bash", "zsh", "fish"],
+        Value::None,
+    ),
+    command(
+        &["explain"],
+        "ARGS...",
+        "how the arguments are interpreted",
+        Value::Any,
+    ),
+];
+
+pub const FLAGS: &[Flag] = &[
+    Flag {
+        name: "--json",
+        value: Value::None,
+        about: "print results as JSON",
+    },
+    Flag {
+        name: "--ndjson",
+        value: Value::None,
+        about: "stream results as newline delimited JSON",
+    },
+    Flag {
+        name: "--format",
+        value: Value::Choice(&["text", "json", "ndjson"]),
+        about: "how results are printed",
+    },
+    Flag {
+        name: "--lang",
+        value: Value::Choice(&["auto", "de", "en"]),
+        about: "the language of lookups without a specifier",
+    },
+    Flag {
+        name: "--limit",
+        value: Value::Any,
+        about: "show at most this many results",
+    },
+    Flag {
+        name: "--verbosity",
+        value: Value::Choice(&["auto", "card", "list", "summary"]),
+        about: "how much lookups show",
+    },
+    Flag {
+        name: "--placeholders",
+        value: Value::Choice(&["compact", "expanded"]),
+        about: "whether placeholders are written out",
+    },
+    Flag {
+        name: "--cases",
+        value: Value::Choice(&["letter", "short", "full"]),
+        about: "how the cases of placeholders are written",
+    },
+    Flag {
+        name: "--variants",
+        value: Value::Choice(&["joined", "split"]),
+        about: "whether the variants of a term are split",
+    },
+    Flag {
+        name: "--subject",
+        value: Value::Any,
+        about: "only entries with the subject",
+    },
+    Flag {
+        name: "--source",
+        value: Value::Choice(&["main", "custom"]),
+        about: "only entries of the dictionary",
+    },
+    Flag {
+        name: "--show",
+        value: Value::Any,
+        about: "turn off the blocking rule",
+    },
+    Flag {
+        name: "--dict",
+        value: Value::File,
+        about: "a dictionary file to use instead of the embedded one",
+    },
+    Flag {
+        name: "--timeout",
+        value: Value::Any,
+        about: "stop searching after this long, e.g. 2s",
+    },
+    Flag {
+        name: "--color",
+        value: Value::None,
+        about: "color the output",
+    },
+    Flag {
+        name: "--no-color",
+        value: Value::None,
+        about: "don't color the output",
+    },
+    Flag {
+        name: "--page",
+        value: Value::None,
+        about: "show the results a page at a time",
+    },
+    Flag {
+        name: "--ignore-diacritics",
+        value: Value::None,
+        about: "match words regardless of accents",
+    },
+    Flag {
+        name: "--match-case",
+        value: Value::None,
+        about: "match the case of the words",
+    },
+    Flag {
+        name: "--pron",
+        value: Value::None,
+        about: "pronunciation hints for English results",
+    },
+    Flag {
+        name: "--explain",
+        value: Value::None,
+        about: "show how the score of each result is made up",
+    },
+    Flag {
+        name: "--debug-refs",
+        value: Value::None,
+        about: "show where the entries come from",
+    },
+];
+
+/// The command of the specifier, which is case insensitive.
+pub fn command_of(specifier: &str) -> Option<&'static Command> {
+    let specifier = specifier.to_lowercase();
+    COMMANDS
+        .iter()
+        .find(|c| c.names.contains(&specifier.as_str()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl TryFrom<&str> for Shell {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            _ => Err(anyhow!(
+                "unknown shell \"{}\", expected bash, zsh or fish",
+                value
+            )),
+        }
+    }
+}
+
+/// The completion script for the program called `bin`. German words are completed
+/// from the saved vocabulary, which the script asks `bin complete words` for.
+pub fn script(shell: Shell, bin: &str) -> String {
+    match shell {
+        Shell::Bash => bash(bin),
+        Shell::Zsh => zsh(bin),
+        Shell::Fish => fish(bin),
+    }
+}
+
+/// The specifiers of the commands whose arguments take the value.
+fn taking(value: Value) -> Vec<&'static str> {
+    COMMANDS
+        .iter()
+        .filter(|c| c.args == value)
+        .flat_map(|c| c.names)
+        .copied()
+        .collect()
+}
+
+fn flags_taking_values() -> String {
+    FLAGS
+        .iter()
+        .filter(|f| f.value != Value::None)
+        .map(|f| f.name)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Quotes the text for POSIX shells.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn function_name(bin: &str) -> String {
+    format!(
+        "_{}",
+        bin.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    )
+}
+
+fn bash(bin: &str) -> String {
+    let function = function_name(bin);
+    let mut out = String::new();
+
+    let _ = writeln!(out, "{}() {{", function);
+    out.push_str(
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n",
+    );
+    out.push_str("    case \"$prev\" in\n");
+    for flag in FLAGS {
+        match flag.value {
+            Value::Choice(values) => {
+                let _ = writeln!(
+                    out,
+                    "        {}) COMPREPLY=($(compgen -W {} -- \"$cur\")); return ;;",
+                    flag.name,
+                    quote(&values.join(" "))
+                );
+            }
+            Value::File => {
+                let _ = writeln!(
+                    out,
+                    "        {}) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;",
+                    flag.name
+                );
+            }
+            Value::Word | Value::Any => {
+                let _ = writeln!(out, "        {}) return ;;", flag.name);
+            }
+            Value::None => {}
+        }
+    }
+    out.push_str("    esac\n");
+
+    let flags: Vec<_> = FLAGS.iter().map(|f| f.name).collect();
+    let _ = writeln!(
+        out,
+        "    if [[ \"$cur\" == -* ]]; then\n        COMPREPLY=($(compgen -W {} -- \"$cur\"))\n        return\n    fi",
+        quote(&flags.join(" "))
+    );
+
+    // The specifier is the first argument which isn't a flag or the value of one.
+    let _ = writeln!(
+        out,
+        "    local i specifier=\"\" position=0\n    \
+         for ((i = 1; i < COMP_CWORD; i++)); do\n        \
+         case \"${{COMP_WORDS[i]}}\" in\n            \
+         {}) ((i++)) ;;\n            \
+         -*) ;;\n            \
+         *) if [[ -z \"$specifier\" ]]; then specifier=\"${{COMP_WORDS[i]}}\"; else ((position++)); fi ;;\n        \
+         esac\n    \
+         done",
+        flags_taking_values()
+    );
+
+    let all: Vec<_> = COMMANDS.iter().flat_map(|c| c.names).copied().collect();
+    let _ = writeln!(
+        out,
+        "    if [[ -z \"$specifier\" ]]; then\n        COMPREPLY=($(compgen -W {} -- \"$cur\"))\n        return\n    fi",
+        quote(&all.join(" "))
+    );
+
+    out.push_str("    case \"$specifier\" in\n");
+    for command in COMMANDS.iter().filter(|c| !c.actions.is_empty()) {
+        let _ = writeln!(
+            out,
+            "        {}) if ((position == 0)); then COMPREPLY=($(compgen -W {} -- \"$cur\")); return; fi ;;",
+            command.names.join("|"),
+            quote(&command.actions.join(" "))
+        );
+    }
+    out.push_str("    esac\n");
+
+    out.push_str("    case \"$specifier\" in\n");
+    let _ = writeln!(
+        out,
+        "        {}) COMPREPLY=($(compgen -W \"$({} complete words 2>/dev/null)\" -- \"$cur\")) ;;",
+        taking(Value::Word).join("|"),
+        bin
+    );
+    let _ = writeln!(
+        out,
+        "        {}) COMPREPLY=($(compgen -f -- \"$cur\")) ;;",
+        taking(Value::File).join("|")
+    );
+    out.push_str("    esac\n}\n");
+    let _ = writeln!(out, "complete -F {} {}", function, bin);
+
+    out
+}
+
+fn zsh(bin: &str) -> String {
+    let function = function_name(bin);
+    // Colons separate the names from the descriptions.
+    let describe = |name: &str, about: &str| quote(&format!("{}:{}", name, about));
+    let mut out = String::new();
+
+    let _ = writeln!(out, "#compdef {}\n", bin);
+    let _ = writeln!(out, "{}() {{", function);
+    out.push_str("    local -a commands flags\n    commands=(\n");
+    for command in COMMANDS {
+        for name in command.names {
+            let _ = writeln!(out, "        {}", describe(name, command.about));
+        }
+    }
+    out.push_str("    )\n    flags=(\n");
+    for flag in FLAGS {
+        let _ = writeln!(out, "        {}", describe(flag.name, flag.about));
+    }
+    out.push_str("    )\n\n");
+
+    out.push_str("    case $words[CURRENT-1] in\n");
+    for flag in FLAGS {
+        match flag.value {
+            Value::Choice(values) => {
+                let _ = writeln!(
+                    out,
+                    "        {}) compadd -- {}; return ;;",
+                    flag.name,
+                    values.join(" ")
+                );
+            }
+            Value::File => {
+                let _ = writeln!(out, "        {}) _files; return ;;", flag.name);
+            }
+            Value::Word | Value::Any => {
+                let _ = writeln!(out, "        {}) return ;;", flag.name);
+            }
+            Value::None => {}
+        }
+    }
+    out.push_str("    esac\n");
+    out.push_str("    if [[ $PREFIX == -* ]]; then\n        _describe flag flags\n        return\n    fi\n\n");
+
+    let _ = writeln!(
+        out,
+        "    local i specifier=\"\" position=0\n    \
+         for ((i = 2; i < CURRENT; i++)); do\n        \
+         case $words[i] in\n            \
+         {}) ((i++)) ;;\n            \
+         -*) ;;\n            \
+         *) if [[ -z $specifier ]]; then specifier=$words[i]; else ((position++)); fi ;;\n        \
+         esac\n    \
+         done",
+        flags_taking_values()
+    );
+    out.push_str("    if [[ -z $specifier ]]; then\n        _describe command commands\n        return\n    fi\n\n");
+
+    out.push_str("    case $specifier in\n");
+    for command in COMMANDS.iter().filter(|c| !c.actions.is_empty()) {
+        let _ = writeln!(
+            out,
+            "        {}) if ((position == 0)); then compadd -- {}; return; fi ;;",
+            command.names.join("|"),
+            command.actions.join(" ")
+        );
+    }
+    out.push_str("    esac\n");
+
+    out.push_str("    case $specifier in\n");
+    let _ = writeln!(
+        out,
+        "        {}) compadd -- ${{(f)\"$({} complete words 2>/dev/null)\"}} ;;",
+        taking(Value::Word).join("|"),
+        bin
+    );
+    let _ = writeln!(out, "        {}) _files ;;", taking(Value::File).join("|"));
+    out.push_str("    esac\n}\n\n");
+
+    let _ = writeln!(
+        out,
+        "if [ \"$funcstack[1]\" = \"{0}\" ]; then\n    {0} \"$@\"\nelse\n    compdef {0} {1}\nfi",
+        function, bin
+    );
+
+    out
+}
+
+fn fish(bin: &str) -> String {
+    // Single quotes in fish only take escaped quotes and backslashes.
+    let quote = |text: &str| format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'"));
+    let seen = |names: &[&str]| format!("__fish_seen_subcommand_from {}", names.join(" "));
+    let mut out = String::new();
+
+    let _ = writeln!(out, "complete -c {} -f", bin);
+    for command in COMMANDS {
+        for name in command.names {
+            let _ = writeln!(
+                out,
+                "complete -c {} -n __fish_use_subcommand -a {} -d {}",
+                bin,
+                name,
+                quote(command.about)
+            );
+        }
+    }
+
+    for flag in FLAGS {
+        let name = flag.name.trim_start_matches("--");
+        let about = quote(flag.about);
+        let _ = match flag.value {
+            Value::None => writeln!(out, "complete -c {} -l {} -d {}", bin, name, about),
+            Value::Choice(values) => writeln!(
+                out,
+                "complete -c {} -l {} -x -a {} -d {}",
+                bin,
+                name,
+                quote(&values.join(" ")),
+                about
+            ),
+            Value::File => writeln!(out, "complete -c {} -l {} -r -F -d {}", bin, name, about),
+            Value::Word | Value::Any => {
+                writeln!(out, "complete -c {} -l {} -x -d {}", bin, name, about)
+            }
+        };
+    }
+
+    for command in COMMANDS.iter().filter(|c| !c.actions.is_empty()) {
+        let _ = writeln!(
+            out,
+            "complete -c {} -n {} -a {}",
+            bin,
+            quote(&format!(
+                "{}; and not {}",
+                seen(command.names),
+                seen(command.actions)
+            )),
+            quote(&command.actions.join(" "))
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "complete -c {} -n {} -a {}",
+        bin,
+        quote(&seen(&taking(Value::Word))),
+        quote(&format!("({} complete words 2>/dev/null)", bin))
+    );
+    let _ = writeln!(
+        out,
+        "complete -c {} -n {} -F",
+        bin,
+        quote(&seen(&taking(Value::File)))
+    );
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_of, script, Shell, COMMANDS};
+
+    #[test]
+    fn commands() {
+        let mut names: Vec<_> = COMMANDS.iter().flat_map(|c| c.names).collect();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count, "specifiers are unique");
+
+        assert_eq!(command_of("G").unwrap().names, ["g", "gender"]);
+        assert!(command_of("i").unwrap().standalone);
+        assert!(command_of("hund").is_none());
+    }
+
+    #[test]
+    fn scripts() {
+        let bash = script(Shell::Bash, "dict-cc-lookup");
+        assert!(bash.contains("complete -F _dict_cc_lookup dict-cc-lookup"));
+        assert!(bash.contains(
+            "--format) COMPREPLY=($(compgen -W 'text json ndjson' -- \"$cur\")); return ;;"
+        ));
+        assert!(bash
+            .contains("v) if ((position == 0)); then COMPREPLY=($(compgen -W 'list clear export'"));
+
+        let zsh = script(Shell::Zsh, "dict-cc-lookup");
+        assert!(zsh.starts_with("#compdef dict-cc-lookup\n"));
+        assert!(zsh.contains("'g:the genders and plurals of a German noun'"));
+
+        let fish = script(Shell::Fish, "dict-cc-lookup");
+        assert!(fish.contains(
+            "complete -c dict-cc-lookup -n __fish_use_subcommand -a dv -d 'look up German words, with details'"
+        ));
+        assert!(fish.contains("-a '(dict-cc-lookup complete words 2>/dev/null)'"));
+    }
+}
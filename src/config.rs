@@ -0,0 +1,313 @@
+//! The user's configuration file, `config.toml` in the config directory.
+//!
+//! Only a small subset of TOML is understood: `[table]` headers and `key = value`
+//! lines with numbers, booleans or double quoted strings, plus `#` comments.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+
+use crate::{
+    block::{Condition, Rule},
+    entry::{PlaceholderStyle, VariantStyle},
+    query::{Language, LanguageMode, OutputFormat, Verbosity},
+    rank::Weights,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// The `[ranking]` table, overriding the default weights of the result ranking.
+    pub ranking: Weights,
+    /// The `[block]` table, rules hiding entries by their tags, in the order given.
+    pub block: Vec<Rule>,
+    /// `ignore_diacritics` in the `[matching]` table.
+    pub ignore_diacritics: bool,
+    /// `autostart` in the `[daemon]` table: start the daemon when a lookup finds none.
+    pub daemon_autostart: bool,
+    /// `wait` in the `[index]` table: build a missing index before looking up, instead
+    /// of scanning the dictionary while it is built in the background.
+    pub wait_for_index: bool,
+    /// `grammar` in the `[saved]` table: show the word classes of saved words in the
+    /// session summary and on exported cards.
+    pub saved_grammar: bool,
+    /// `sessions` in the `[history]` table: append the statistics of every interactive
+    /// session to the history file.
+    pub history_sessions: bool,
+    /// `known_words` in the `[history]` table: add the words saved in interactive mode
+    /// and the reviewed cards to the known words.
+    pub history_known_words: bool,
+    /// `format` in the `[defaults]` table: "text", "json" or "ndjson".
+    pub format: Option<OutputFormat>,
+    /// `language` in the `[defaults]` table, for queries without a specifier: "de", "en"
+    /// or "auto".
+    pub language: Option<LanguageMode>,
+    /// `limit` in the `[defaults]` table: how many results to show at most.
+    pub limit: Option<usize>,
+    /// `color` in the `[defaults]` table: whether to color the output even if stdout
+    /// isn't a terminal, or not at all.
+    pub color: Option<bool>,
+    /// `placeholders` in the `[defaults]` table: "compact" for "jdm. etw. leihen",
+    /// "expanded" for "jemandem etwas leihen".
+    pub placeholders: Option<PlaceholderStyle>,
+    /// `verbosity` in the `[defaults]` table: "auto" to pick by the number of results,
+    /// "card", "list" or "summary".
+    pub verbosity: Option<Verbosity>,
+    /// `variants` in the `[defaults]` table: "joined" for "mutwillige/böswillige
+    /// Beschädigung", "split" for "mutwillige Beschädigung / böswillige Beschädigung".
+    pub variants: Option<VariantStyle>,
+    /// `dictionary` in the `[files]` table: a dictionary file to use instead of the
+    /// embedded one.
+    pub dictionary: Option<PathBuf>,
+    /// `vocab` in the `[files]` table: where to keep the saved vocabulary.
+    pub vocab_file: Option<PathBuf>,
+}
+
+/// A value on the right hand side of `key = value`.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl Config {
+    /// Reads the configuration file, falling back to the defaults if it doesn't exist.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| anyhow!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut config = Self::default();
+        let mut table = String::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                table = name.trim().to_string();
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected key = value", i + 1))?;
+            let (key, value) = (key.trim(), parse_value(value.trim()));
+
+            config
+                .set(&table, key, value)
+                .map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+        }
+
+        Ok(config)
+    }
+
+    fn set(&mut self, table: &str, key: &str, value: Value) -> anyhow::Result<()> {
+        match table {
+            "ranking" => {
+                let weight = match key {
+                    "exact" => &mut self.ranking.exact,
+                    "keyword" => &mut self.ranking.keyword,
+                    "substring" => &mut self.ranking.substring,
+                    "length_penalty" => &mut self.ranking.length_penalty,
+                    "register_penalty" => &mut self.ranking.register_penalty,
+                    "frequency" => &mut self.ranking.frequency,
+                    "complexity_penalty" => &mut self.ranking.complexity_penalty,
+                    "word_class" => &mut self.ranking.word_class,
+                    _ => return Err(anyhow!("unknown ranking weight \"{}\"", key)),
+                };
+                *weight = match value {
+                    Value::Number(n) => n as f32,
+                    _ => return Err(anyhow!("\"{}\" must be a number", key)),
+                };
+            }
+            "block" => {
+                let Value::String(condition) = value else {
+                    return Err(anyhow!("rule \"{}\" must be a string", key));
+                };
+                self.block.push(Rule {
+                    name: key.to_string(),
+                    condition: Condition::parse(&condition)?,
+                });
+            }
+            "matching" if key == "ignore_diacritics" => {
+                self.ignore_diacritics = parse_bool(key, value)?;
+            }
+            "daemon" if key == "autostart" => self.daemon_autostart = parse_bool(key, value)?,
+            "index" if key == "wait" => self.wait_for_index = parse_bool(key, value)?,
+            "saved" if key == "grammar" => self.saved_grammar = parse_bool(key, value)?,
+            "history" => match key {
+                "sessions" => self.history_sessions = parse_bool(key, value)?,
+                "known_words" => self.history_known_words = parse_bool(key, value)?,
+                _ => return Err(anyhow!("unknown setting \"{}\" in [{}]", key, table)),
+            },
+            "defaults" => match key {
+                "format" => self.format = Some(parse_string(key, value)?.as_str().try_into()?),
+                "language" => {
+                    self.language = Some(match parse_string(key, value)?.as_str() {
+                        "auto" => LanguageMode::Auto,
+                        "de" => LanguageMode::Fixed(Language::German),
+                        "en" => LanguageMode::Fixed(Language::English),
+                        other => return Err(anyhow!("unknown language \"{}\"", other)),
+                    })
+                }
+                "limit" => {
+                    self.limit = match value {
+                        Value::Number(n) if n >= 1.0 && n.fract() == 0.0 => Some(n as usize),
+                        _ => return Err(anyhow!("\"limit\" must be a positive whole number")),
+                    }
+                }
+                "color" => self.color = Some(parse_bool(key, value)?),
+                "placeholders" => {
+                    self.placeholders = Some(parse_string(key, value)?.as_str().try_into()?)
+                }
+                "verbosity" => {
+                    self.verbosity = Some(parse_string(key, value)?.as_str().try_into()?)
+                }
+                "variants" => self.variants = Some(parse_string(key, value)?.as_str().try_into()?),
+                _ => return Err(anyhow!("unknown setting \"{}\" in [{}]", key, table)),
+            },
+            "files" => match key {
+                "dictionary" => self.dictionary = Some(parse_string(key, value)?.into()),
+                "vocab" => self.vocab_file = Some(parse_string(key, value)?.into()),
+                _ => return Err(anyhow!("unknown setting \"{}\" in [{}]", key, table)),
+            },
+            _ => return Err(anyhow!("unknown setting \"{}\" in [{}]", key, table)),
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_bool(key: &str, value: Value) -> anyhow::Result<bool> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        _ => Err(anyhow!("\"{}\" must be true or false", key)),
+    }
+}
+
+fn parse_string(key: &str, value: Value) -> anyhow::Result<String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(anyhow!("\"{}\" must be a string", key)),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Value {
+    match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(s) => Value::String(s.to_string()),
+            None => match value.replace('_', "").parse() {
+                Ok(n) => Value::Number(n),
+                Err(_) => Value::String(value.to_string()),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Config;
+    use crate::{
+        entry::VariantStyle,
+        query::{Language, LanguageMode, OutputFormat, Verbosity},
+        rank::Weights,
+    };
+
+    #[test]
+    fn parse_ranking() {
+        let config = Config::parse(
+            "# prefer common senses\n\
+             [ranking]\n\
+             register_penalty = 0.5 # push slang down\n\
+             frequency = 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.ranking,
+            Weights {
+                register_penalty: 0.5,
+                frequency: 1.0,
+                ..Default::default()
+            }
+        );
+
+        assert!(Config::parse("[ranking]\nfrequency = \"high\"").is_err());
+        assert!(Config::parse("[ranking]\nboost = 1").is_err());
+    }
+
+    #[test]
+    fn parse_block_rules() {
+        let config = Config::parse(
+            "[block]\n\
+             vulgar = \"[vulg.] and not [idiom]\"\n\
+             archaic = \"obs. or archaic\"\n",
+        )
+        .unwrap();
+
+        let names: Vec<_> = config.block.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["vulgar", "archaic"]);
+
+        assert!(Config::parse("[block]\nvulgar = 1").is_err());
+        assert!(Config::parse("[block]\nvulgar = \"vulg and\"").is_err());
+    }
+
+    #[test]
+    fn parse_defaults() {
+        let config = Config::parse(
+            "[defaults]\n\
+             format = \"json\"\n\
+             language = \"en\"\n\
+             limit = 20\n\
+             verbosity = \"list\"\n\
+             variants = \"split\"\n\
+             [files]\n\
+             vocab = \"/tmp/vocab.json\"\n\
+             [history]\n\
+             sessions = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.format, Some(OutputFormat::Json));
+        assert_eq!(
+            config.language,
+            Some(LanguageMode::Fixed(Language::English))
+        );
+        assert_eq!(config.limit, Some(20));
+        assert_eq!(config.verbosity, Some(Verbosity::List));
+        assert_eq!(config.variants, Some(VariantStyle::Split));
+        assert_eq!(config.vocab_file.unwrap(), Path::new("/tmp/vocab.json"));
+        assert_eq!(config.dictionary, None);
+        assert!(config.history_sessions && !config.history_known_words);
+
+        assert!(Config::parse("[defaults]\nformat = \"yaml\"").is_err());
+        assert!(Config::parse("[defaults]\nlimit = 2.5").is_err());
+        assert!(Config::parse("[files]\ndictionary = true").is_err());
+    }
+}
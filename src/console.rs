@@ -0,0 +1,196 @@
+//! Terminal setup. Unix terminals understand UTF-8 and ANSI escape sequences out of the
+//! box, Windows consoles have to be switched to the UTF-8 code page and to virtual
+//! terminal processing first. Raw mode, needed to read single key presses, is only
+//! implemented for Unix.
+
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static ANSI: AtomicBool = AtomicBool::new(false);
+
+/// Prepares the console for output. Call once at startup, before printing anything.
+pub fn init() {
+    let ansi = io::stdout().is_terminal() && platform::init();
+    ANSI.store(ansi, Ordering::Relaxed);
+}
+
+/// Whether escape sequences for colors and the like can be written to stdout.
+/// Always false before [`init`] and when `NO_COLOR` is set.
+pub fn supports_ansi() -> bool {
+    ANSI.load(Ordering::Relaxed) && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Overrides whether escape sequences are written, for output shown on another terminal
+/// than ours, like the daemon's.
+pub fn set_ansi(ansi: bool) {
+    ANSI.store(ansi, Ordering::Relaxed);
+}
+
+pub use raw::{size, RawMode};
+
+/// Asks whether to go on with output shown a page at a time: Enter or space go on, "q"
+/// and Escape stop. Without a terminal to ask, the output goes on.
+pub fn wait_for_more() -> io::Result<bool> {
+    const PROMPT: &str = "-- more: Enter, stop: q --";
+
+    let Some(_raw) = RawMode::enable() else {
+        return Ok(true);
+    };
+    let mut stderr = io::stderr();
+    write!(stderr, "{}", PROMPT)?;
+    stderr.flush()?;
+
+    let mut key = [0];
+    let more = loop {
+        if io::stdin().read(&mut key)? == 0 {
+            break false;
+        }
+        match key[0] {
+            b'\r' | b'\n' | b' ' => break true,
+            // Ctrl-C arrives as a key in raw mode.
+            b'q' | b'Q' | 0x1b | 0x03 => break false,
+            _ => {}
+        }
+    };
+
+    write!(stderr, "\r{}\r", " ".repeat(PROMPT.len()))?;
+    Ok(more)
+}
+
+#[cfg(unix)]
+mod raw {
+    use std::{
+        io::{self, IsTerminal},
+        mem::MaybeUninit,
+    };
+
+    /// Puts the terminal into raw mode for as long as the value lives.
+    pub struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        /// Returns `None` if stdin isn't a terminal.
+        pub fn enable() -> Option<Self> {
+            if !io::stdin().is_terminal() {
+                return None;
+            }
+
+            // SAFETY: tcgetattr fills the struct on success, which is checked.
+            let original = unsafe {
+                let mut termios = MaybeUninit::uninit();
+                if libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) != 0 {
+                    return None;
+                }
+                termios.assume_init()
+            };
+
+            let mut raw = original;
+            // Keep output processing, so that "\n" still starts a new line.
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+
+            // SAFETY: raw is a valid termios derived from the current settings.
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) } != 0 {
+                return None;
+            }
+
+            Some(Self { original })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            // SAFETY: restores the settings read in enable.
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original);
+            }
+        }
+    }
+
+    /// The number of columns and rows of the terminal stdout is connected to.
+    pub fn size() -> Option<(usize, usize)> {
+        // SAFETY: TIOCGWINSZ fills the struct on success, which is checked.
+        let size = unsafe {
+            let mut size = MaybeUninit::<libc::winsize>::uninit();
+            if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) != 0 {
+                return None;
+            }
+            size.assume_init()
+        };
+
+        (size.ws_col > 0 && size.ws_row > 0).then_some((size.ws_col.into(), size.ws_row.into()))
+    }
+}
+
+#[cfg(not(unix))]
+mod raw {
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> Option<Self> {
+            None
+        }
+    }
+
+    pub fn size() -> Option<(usize, usize)> {
+        None
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn init() -> bool {
+        true
+    }
+}
+
+#[cfg(all(windows, feature = "windows-console"))]
+mod platform {
+    use windows_sys::Win32::{
+        Foundation::INVALID_HANDLE_VALUE,
+        System::Console::{
+            GetConsoleMode, GetStdHandle, SetConsoleCP, SetConsoleMode, SetConsoleOutputCP,
+            ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+        },
+    };
+
+    const CP_UTF8: u32 = 65001;
+
+    pub fn init() -> bool {
+        // SAFETY: plain Win32 calls on the process' own standard handles.
+        unsafe {
+            SetConsoleCP(CP_UTF8);
+            SetConsoleOutputCP(CP_UTF8);
+
+            let stdout = enable_vt(STD_OUTPUT_HANDLE);
+            enable_vt(STD_ERROR_HANDLE);
+            stdout
+        }
+    }
+
+    unsafe fn enable_vt(handle: u32) -> bool {
+        let handle = GetStdHandle(handle);
+        if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+            return false;
+        }
+
+        let mut mode = 0;
+        GetConsoleMode(handle, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+/// Without the console setup, legacy Windows consoles would print escape sequences
+/// verbatim, so none are written.
+#[cfg(all(windows, not(feature = "windows-console")))]
+mod platform {
+    pub fn init() -> bool {
+        false
+    }
+}
@@ -0,0 +1,311 @@
+//! Keeping the parsed dictionary in memory between invocations. The daemon listens on a
+//! Unix socket, clients send it their command line as a line of JSON and get back a
+//! line of JSON with the outcome, followed by the output.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::{
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{dictionary, entry::Entry, query::Language, util, Dictionary};
+
+/// How long a client waits for an answer before doing the lookup itself.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Request {
+    /// Answer a command line.
+    Query {
+        /// Identifies the client's build. A daemon of another build exits instead of
+        /// answering, so that one matching the client can be started.
+        build: String,
+        args: Vec<String>,
+        /// Whether the client's terminal understands escape sequences.
+        ansi: bool,
+    },
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Failed(String),
+    Declined,
+    Outdated,
+}
+
+/// What the daemon made of a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// The output of the command.
+    Answered(Vec<u8>),
+    /// The command failed with the given message, after writing the output.
+    Failed(String, Vec<u8>),
+    /// The daemon doesn't run this kind of command, the client has to.
+    Declined,
+    /// The daemon is of another build and exited.
+    Outdated,
+}
+
+/// Whether the handler ran the command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Handled,
+    Declined,
+}
+
+/// The dictionary with a keyword map per language, like the on-disk
+/// [`Index`](crate::index::Index) has, so that lookups don't go through all entries.
+pub struct Resident {
+    dict: Dictionary,
+    german: HashMap<String, Vec<usize>>,
+    english: HashMap<String, Vec<usize>>,
+}
+
+impl Resident {
+    pub fn new(dict: Dictionary) -> Self {
+        let mut german = HashMap::<String, Vec<usize>>::new();
+        let mut english = HashMap::<String, Vec<usize>>::new();
+
+        for (i, entry) in dict.entries().iter().enumerate() {
+            for (keywords, term) in [(&mut german, &entry.german), (&mut english, &entry.english)] {
+                for keyword in term.keywords() {
+                    let entries = keywords.entry(keyword.to_lowercase()).or_default();
+                    if entries.last() != Some(&i) {
+                        entries.push(i);
+                    }
+                }
+            }
+        }
+
+        Self {
+            dict,
+            german,
+            english,
+        }
+    }
+
+    /// The number of entries whose term in the given language has the keyword.
+    pub fn frequency(&self, keyword: &str, language: Language) -> usize {
+        self.positions(keyword, language).len()
+    }
+
+    /// The entries whose term in the given language has the keyword, ignoring case.
+    pub fn lookup(&self, keyword: &str, language: Language) -> Vec<Entry> {
+        let entries = self.dict.entries();
+        self.positions(keyword, language)
+            .into_iter()
+            .map(|i| entries[i].clone())
+            .collect()
+    }
+
+    /// The entries which may match the word or phrase, looked up through its rarest word
+    /// like [`Index::candidates`](crate::index::Index::candidates) does.
+    pub fn candidates(&self, word: &str, language: Language) -> Vec<Entry> {
+        dictionary::query_components(word)
+            .into_iter()
+            .min_by_key(|c| self.frequency(c, language))
+            .map(|rarest| self.lookup(rarest, language))
+            .unwrap_or_default()
+    }
+
+    fn positions(&self, keyword: &str, language: Language) -> Vec<usize> {
+        let keywords = match language {
+            Language::German => &self.german,
+            Language::English => &self.english,
+        };
+
+        if !util::ignores_diacritics() {
+            return keywords
+                .get(&keyword.to_lowercase())
+                .cloned()
+                .unwrap_or_default();
+        }
+
+        let mut positions: Vec<usize> = keywords
+            .iter()
+            .filter(|(k, _)| util::case_fold_eq(k, keyword))
+            .flat_map(|(_, positions)| positions.iter().copied())
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+/// Makes sure only one daemon serves the socket, by locking a file next to it for as
+/// long as the returned file is open. Returns `None` if another daemon holds the lock.
+pub fn lock(socket: &Path) -> io::Result<Option<fs::File>> {
+    if let Some(dir) = socket.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = fs::File::create(socket.with_extension("lock"))?;
+
+    // SAFETY: the descriptor is valid as long as `file` is alive.
+    let res = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if res == 0 {
+        return Ok(Some(file));
+    }
+    match io::Error::last_os_error() {
+        e if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+        e => Err(e),
+    }
+}
+
+/// Answers requests until asked to stop or until a client of another build connects.
+/// `handle` runs a command line, writing the output to the given buffer. Errors of
+/// single connections are passed to `on_error` and don't stop the daemon.
+pub fn serve(
+    listener: &UnixListener,
+    build: &str,
+    mut handle: impl FnMut(&[String], bool, &mut Vec<u8>) -> anyhow::Result<Answer>,
+    mut on_error: impl FnMut(io::Error),
+) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept()?;
+        if let Err(e) = stream.set_read_timeout(Some(TIMEOUT)) {
+            on_error(e);
+            continue;
+        }
+
+        let mut line = String::new();
+        match BufReader::new(&stream).read_line(&mut line) {
+            // Someone checked whether the daemon runs.
+            Ok(0) => continue,
+            Ok(_) => {}
+            Err(e) => {
+                on_error(e);
+                continue;
+            }
+        }
+        let request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                on_error(io::Error::new(io::ErrorKind::InvalidData, e));
+                continue;
+            }
+        };
+
+        let (status, output, done) = match request {
+            Request::Stop => (Status::Ok, vec![], true),
+            Request::Query { build: b, .. } if b != build => (Status::Outdated, vec![], true),
+            Request::Query { args, ansi, .. } => {
+                let mut output = vec![];
+                let status = match handle(&args, ansi, &mut output) {
+                    Ok(Answer::Handled) => Status::Ok,
+                    Ok(Answer::Declined) => Status::Declined,
+                    Err(e) => Status::Failed(e.to_string()),
+                };
+                (status, output, false)
+            }
+        };
+
+        if let Err(e) = write_reply(&stream, &status, &output) {
+            on_error(e);
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+fn write_reply(mut wr: impl Write, status: &Status, output: &[u8]) -> io::Result<()> {
+    serde_json::to_writer(&mut wr, status)?;
+    wr.write_all(b"\n")?;
+    wr.write_all(output)?;
+    wr.flush()
+}
+
+/// Sends the request to the daemon listening on the socket. Fails if there is none.
+pub fn forward(socket: &Path, request: &Request) -> io::Result<Reply> {
+    let stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    let mut wr = &stream;
+    serde_json::to_writer(&mut wr, request)?;
+    wr.write_all(b"\n")?;
+    wr.flush()?;
+
+    let mut rd = BufReader::new(&stream);
+    let mut line = String::new();
+    rd.read_line(&mut line)?;
+    let status: Status =
+        serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut output = vec![];
+    rd.read_to_end(&mut output)?;
+
+    Ok(match status {
+        Status::Ok => Reply::Answered(output),
+        Status::Failed(message) => Reply::Failed(message, output),
+        Status::Declined => Reply::Declined,
+        Status::Outdated => Reply::Outdated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::net::UnixListener, thread};
+
+    use super::{forward, serve, Answer, Reply, Request, Resident};
+    use crate::{query::Language, Dictionary};
+
+    #[test]
+    fn answers_clients() {
+        let socket = std::env::temp_dir().join(format!("daemon-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let daemon = thread::spawn(move || {
+            serve(
+                &listener,
+                "1",
+                |args, _, out| match args {
+                    [word] if word == "fail" => Err(anyhow::anyhow!("failed")),
+                    [word] => {
+                        out.extend_from_slice(word.as_bytes());
+                        Ok(Answer::Handled)
+                    }
+                    _ => Ok(Answer::Declined),
+                },
+                |e| panic!("{}", e),
+            )
+        });
+
+        let query = |build: &str, args: &[&str]| {
+            let request = Request::Query {
+                build: build.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+                ansi: false,
+            };
+            forward(&socket, &request).unwrap()
+        };
+        assert_eq!(query("1", &["Hund"]), Reply::Answered(b"Hund".to_vec()));
+        assert_eq!(query("1", &[]), Reply::Declined);
+        assert_eq!(
+            query("1", &["fail"]),
+            Reply::Failed("failed".into(), vec![])
+        );
+        assert_eq!(query("2", &["Hund"]), Reply::Outdated);
+
+        daemon.join().unwrap().unwrap();
+        assert!(forward(&socket, &Request::Stop).is_err());
+        std::fs::remove_file(&socket).unwrap();
+
+        let resident = Resident::new(
+            Dictionary::load("Hund {m}\tdog\nHundehütte {f}\tdog house\n".as_bytes()).unwrap(),
+        );
+        assert_eq!(resident.frequency("dog", Language::English), 2);
+        assert_eq!(resident.lookup("HUND", Language::German).len(), 1);
+    }
+}
@@ -0,0 +1,1355 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    io::{self, BufRead},
+    ops::Range,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    entry::{is_placeholder, Entry, Gender, Origin, SourceRef, Term},
+    index::{Index, IndexedLine},
+    loader::DictionarySource,
+    query::{Language, Options},
+    rank::{self, Score, Weights},
+    util,
+};
+
+/// Stops scans of the dictionary once a timeout is over, so that expensive queries
+/// return what they found so far instead of hanging. Clones share whether a scan was
+/// cut short. The default never passes.
+#[derive(Debug, Clone, Default)]
+pub struct Deadline {
+    at: Option<Instant>,
+    hit: Rc<Cell<bool>>,
+}
+
+impl Deadline {
+    pub fn after(timeout: Option<Duration>) -> Self {
+        Self {
+            at: timeout.map(|t| Instant::now() + t),
+            hit: Rc::default(),
+        }
+    }
+
+    /// Whether the time is over. Scans check this before every entry.
+    pub fn passed(&self) -> bool {
+        if !self.hit.get() && self.at.is_some_and(|at| Instant::now() >= at) {
+            self.hit.set(true);
+        }
+        self.hit.get()
+    }
+
+    /// Whether a scan was cut short, i.e. the results are incomplete.
+    pub fn hit(&self) -> bool {
+        self.hit.get()
+    }
+}
+
+/// Reads the dictionary line by line, keeping track of where each line starts.
+/// It can also replay lines found through the index.
+pub struct LineReader<R> {
+    rd: R,
+    indexed: Option<std::vec::IntoIter<IndexedLine>>,
+    source: SourceRef,
+    next_offset: usize,
+    deadline: Deadline,
+}
+
+impl LineReader<io::Empty> {
+    pub fn indexed(lines: Vec<IndexedLine>) -> Self {
+        Self {
+            rd: io::empty(),
+            indexed: Some(lines.into_iter()),
+            source: SourceRef::default(),
+            next_offset: 0,
+            deadline: Deadline::default(),
+        }
+    }
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(rd: R) -> Self {
+        Self {
+            rd,
+            indexed: None,
+            source: SourceRef::default(),
+            next_offset: 0,
+            deadline: Deadline::default(),
+        }
+    }
+
+    /// Marks the entries read as coming from the given dictionary.
+    pub fn with_origin(mut self, origin: Origin) -> Self {
+        self.source.origin = origin;
+        self
+    }
+
+    /// Makes the reader end early once the deadline passed.
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Where the line last read starts.
+    pub fn source(&self) -> SourceRef {
+        self.source
+    }
+
+    pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        if self.deadline.passed() {
+            return Ok(0);
+        }
+        if let Some(lines) = &mut self.indexed {
+            return Ok(match lines.next() {
+                Some(l) => {
+                    buf.push_str(&l.text);
+                    self.source = l.source;
+                    l.text.len()
+                }
+                None => 0,
+            });
+        }
+
+        let n = self.rd.read_line(buf)?;
+        self.source = SourceRef {
+            line: self.source.line + 1,
+            offset: self.next_offset,
+            origin: self.source.origin,
+        };
+        self.next_offset += n;
+        Ok(n)
+    }
+}
+
+/// How a search result matched the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MatchKind {
+    /// The query is the term's only keyword.
+    Exact,
+    /// The query is one of the term's keywords.
+    Keyword,
+    /// The query is part of one of the term's keywords.
+    Substring,
+}
+
+/// A search result together with why it matched.
+#[derive(Debug, Clone)]
+pub struct Match<'a> {
+    pub entry: &'a Entry,
+    /// The side of the entry that matched.
+    pub language: Language,
+    /// The keyword of the term that matched the query.
+    pub keyword: String,
+    pub kind: MatchKind,
+    /// Higher is better.
+    pub score: Score,
+    /// Where the query occurs in the rendered (`Display`) matched term.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Match<'_> {
+    /// The term on the matched side of the entry.
+    pub fn term(&self) -> &Term {
+        self.entry.term(self.language)
+    }
+}
+
+/// Parsed dictionary entries, ready to be searched.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    entries: Vec<Entry>,
+}
+
+impl Dictionary {
+    /// Parses every entry of a dictionary in the dict.cc tab separated format. Comments
+    /// and lines that fail to parse are skipped.
+    pub fn load(rd: impl BufRead) -> io::Result<Self> {
+        Self::load_filtered(LineReader::new(rd), |_| true)
+    }
+
+    /// Loads only the lines which may contain the given word in the column of the given
+    /// language. This is much cheaper than [`Dictionary::load`] when only a single word
+    /// is going to be looked up.
+    pub fn load_candidates<R: BufRead>(
+        rd: LineReader<R>,
+        word: &str,
+        language: Language,
+    ) -> io::Result<Self> {
+        Self::load_filtered(rd, |line| may_contain(line, word, language))
+    }
+
+    /// The entries which may contain the word or phrase in the given language: the lines
+    /// the index lists for its rarest word if there is an index, otherwise the candidates
+    /// found scanning the dictionary, see [`Dictionary::load_candidates`].
+    pub fn open(
+        source: &DictionarySource,
+        index: Option<&mut Index>,
+        word: &str,
+        language: Language,
+    ) -> io::Result<Self> {
+        match index {
+            Some(index) => {
+                let lines = index.candidates(word, language)?;
+                Self::load_filtered(LineReader::indexed(lines), |_| true)
+            }
+            None => Self::load_candidates(LineReader::new(source.reader()?), word, language),
+        }
+    }
+
+    /// A dictionary per query like [`Dictionary::open`] returns. Without an index, the
+    /// dictionary is scanned once for all of them, see [`Dictionary::load_batch`].
+    pub fn open_batch(
+        source: &DictionarySource,
+        index: Option<&mut Index>,
+        queries: &[String],
+        language: Language,
+    ) -> io::Result<Vec<Self>> {
+        match index {
+            Some(index) => queries
+                .iter()
+                .map(|q| Self::open(source, Some(index), q, language))
+                .collect(),
+            None => Self::load_batch(LineReader::new(source.reader()?), queries, language),
+        }
+    }
+
+    /// Loads the lines for which `filter` returns true.
+    pub fn load_filtered<R: BufRead>(
+        mut rd: LineReader<R>,
+        filter: impl Fn(&str) -> bool,
+    ) -> io::Result<Self> {
+        let mut entries = vec![];
+        let mut buf = String::with_capacity(512);
+
+        loop {
+            buf.clear();
+            if rd.read_line(&mut buf)? == 0 {
+                break;
+            }
+
+            if buf.starts_with('#') || !filter(&buf) {
+                continue;
+            }
+
+            if let Ok(entry) = Entry::parse(&buf, rd.source()) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Loads the candidate lines of many queries in a single pass, a dictionary per
+    /// query like [`Dictionary::load_candidates`] would. Lines are only checked against
+    /// the queries sharing a word with them, so that long lists of queries stay cheap.
+    pub fn load_batch<R: BufRead>(
+        mut rd: LineReader<R>,
+        queries: &[String],
+        language: Language,
+    ) -> io::Result<Vec<Self>> {
+        let column = match language {
+            Language::German => 0,
+            Language::English => 1,
+        };
+        // Every line matching a query contains its first word.
+        let mut wanted = HashMap::<String, Vec<usize>>::new();
+        for (i, query) in queries.iter().enumerate() {
+            if let Some(first) = query_components(query).first() {
+                wanted.entry(util::case_fold(first)).or_default().push(i);
+            }
+        }
+
+        let mut dicts = vec![Self::default(); queries.len()];
+        let mut buf = String::with_capacity(512);
+        let mut candidates = vec![];
+        loop {
+            buf.clear();
+            if rd.read_line(&mut buf)? == 0 {
+                break;
+            }
+            if buf.starts_with('#') {
+                continue;
+            }
+
+            candidates.clear();
+            let words = buf.split('\t').nth(column).unwrap_or_default();
+            for word in words.split(|c: char| c.is_whitespace() || "()[]{}<>/,;".contains(c)) {
+                if let Some(queries) = wanted.get(&util::case_fold(word)) {
+                    candidates.extend(queries.iter().copied());
+                }
+            }
+            candidates.sort_unstable();
+            candidates.dedup();
+            candidates.retain(|&i| may_contain(&buf, &queries[i], language));
+            if candidates.is_empty() {
+                continue;
+            }
+
+            if let Ok(entry) = Entry::parse(&buf, rd.source()) {
+                for &i in &candidates {
+                    dicts[i].entries.push(entry.clone());
+                }
+            }
+        }
+
+        Ok(dicts)
+    }
+
+    pub fn from_entries(entries: Vec<Entry>) -> Self {
+        Self { entries }
+    }
+
+    /// Adds the entries of another dictionary, e.g. of a custom one.
+    pub fn append(&mut self, mut other: Dictionary) {
+        self.entries.append(&mut other.entries);
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The entries whose German term is exactly the given word or phrase, ignoring case.
+    pub fn lookup_german(&self, word: &str) -> Vec<&Entry> {
+        self.lookup(word, Language::German)
+    }
+
+    /// The entries whose English term is exactly the given word or phrase, ignoring case.
+    pub fn lookup_english(&self, word: &str) -> Vec<&Entry> {
+        self.lookup(word, Language::English)
+    }
+
+    pub fn lookup(&self, word: &str, language: Language) -> Vec<&Entry> {
+        self.lookup_until(word, language, &Deadline::default())
+    }
+
+    /// Like [`Dictionary::lookup`], returning the entries found so far once the
+    /// deadline passed.
+    pub fn lookup_until(&self, word: &str, language: Language, deadline: &Deadline) -> Vec<&Entry> {
+        self.matches(word, language, deadline).collect()
+    }
+
+    /// Like [`Dictionary::lookup_until`], but finds the entries one by one.
+    pub fn matches<'a: 'b, 'b>(
+        &'a self,
+        word: &'b str,
+        language: Language,
+        deadline: &'b Deadline,
+    ) -> impl Iterator<Item = &'a Entry> + 'b {
+        self.entries
+            .iter()
+            .take_while(|_| !deadline.passed())
+            .filter(move |e| is_exact(e.term(language), word, language))
+    }
+
+    /// How the dictionary capitalizes the word, if it has it only in other
+    /// capitalizations: "Haus" for "haus" and "HAUS". Of several, the most common one.
+    pub fn spelling(&self, word: &str, language: Language) -> Option<String> {
+        let mut spellings = HashMap::<String, usize>::new();
+        let components = query_components(word);
+        for entry in self.lookup(word, language) {
+            let Some(spelling) = entry.term(language).variants().iter().find_map(|v| {
+                let keywords = match_keywords(v, language);
+                keywords_eq(&keywords, &components).then(|| keywords.join(" "))
+            }) else {
+                continue;
+            };
+            if spelling == word {
+                return None;
+            }
+            *spellings.entry(spelling).or_default() += 1;
+        }
+
+        spellings
+            .into_iter()
+            .max_by(|(a, m), (b, n)| m.cmp(n).then_with(|| b.cmp(a)))
+            .map(|(spelling, _)| spelling)
+    }
+
+    /// How the entries capitalize the keyword as it is in the index, lowercased, also
+    /// within phrases: "Hause" for "hause", as in "zu Hause". Of several, the most common
+    /// one.
+    pub fn keyword_spelling(&self, keyword: &str, language: Language) -> Option<String> {
+        let mut spellings = HashMap::<&str, usize>::new();
+        for k in self
+            .entries
+            .iter()
+            .flat_map(|e| e.term(language).keywords())
+        {
+            if k.to_lowercase() == keyword {
+                *spellings.entry(k).or_default() += 1;
+            }
+        }
+
+        spellings
+            .into_iter()
+            .max_by(|(a, m), (b, n)| m.cmp(n).then_with(|| b.cmp(a)))
+            .map(|(spelling, _)| spelling.to_string())
+    }
+
+    /// The entries read by `rd` whose term in the given language is exactly the word,
+    /// parsed while the dictionary is scanned, so that the first ones come before the
+    /// scan completes. Entries come in dictionary order, unranked.
+    pub fn search<R: BufRead>(rd: LineReader<R>, word: &str, language: Language) -> Search<R> {
+        Search {
+            rd,
+            word: word.to_string(),
+            language,
+            buf: String::with_capacity(512),
+        }
+    }
+
+    /// The entries read by `rd` together with their dictionary lines, for output showing
+    /// the lines as well. Only the lines for which `filter` returns true are parsed.
+    pub fn lines<R: BufRead, F: Fn(&str) -> bool>(rd: LineReader<R>, filter: F) -> Lines<R, F> {
+        Lines {
+            rd,
+            filter,
+            buf: String::with_capacity(512),
+        }
+    }
+
+    /// All entries whose term in the given language contains the word in one of its
+    /// keywords, best matches first.
+    pub fn find(&self, word: &str, language: Language) -> Vec<Match<'_>> {
+        self.search_with(word, language, &Weights::default())
+    }
+
+    /// Like [`Dictionary::find`], ranking the results with the given weights.
+    pub fn search_with(&self, word: &str, language: Language, weights: &Weights) -> Vec<Match<'_>> {
+        self.search_until(word, language, weights, &Deadline::default())
+    }
+
+    /// Like [`Dictionary::search_with`], ranking the matches found so far once the
+    /// deadline passed.
+    pub fn search_until(
+        &self,
+        word: &str,
+        language: Language,
+        weights: &Weights,
+        deadline: &Deadline,
+    ) -> Vec<Match<'_>> {
+        self.ranked(word, language, weights, deadline, |_| true)
+    }
+
+    /// The matches which `keep` returns true for, best first. Only these are ranked,
+    /// but the translations of all matches count for the frequency boost.
+    fn ranked(
+        &self,
+        word: &str,
+        language: Language,
+        weights: &Weights,
+        deadline: &Deadline,
+        keep: impl Fn(&Match) -> bool,
+    ) -> Vec<Match<'_>> {
+        let (mut matches, others): (Vec<_>, Vec<_>) = self
+            .entries
+            .iter()
+            .take_while(|_| !deadline.passed())
+            .filter_map(|entry| match_entry(entry, word, language, weights))
+            .partition(|m| keep(m));
+
+        // Common senses show up in the translations of many entries: "dog" is in "race
+        // dog" and "dog on the chain" too. Entries repeating a translation, like several
+        // annotated "hound"s, count once.
+        let translations: HashSet<_> = matches
+            .iter()
+            .chain(&others)
+            .map(translation_keywords)
+            .filter(|k| !k.is_empty())
+            .collect();
+        let keywords: Vec<_> = matches.iter().map(translation_keywords).collect();
+        let lengths: HashSet<_> = keywords.iter().map(Vec::len).filter(|&n| n > 0).collect();
+
+        // The count of translations containing the keywords and the last one counted.
+        let mut occurrences: HashMap<&[String], (usize, usize)> = keywords
+            .iter()
+            .map(|k| (k.as_slice(), (0, usize::MAX)))
+            .collect();
+        for (i, translation) in translations.iter().enumerate() {
+            for window in lengths.iter().flat_map(|&n| translation.windows(n)) {
+                if let Some((count, last)) = occurrences.get_mut(window) {
+                    if *last != i {
+                        *count += 1;
+                        *last = i;
+                    }
+                }
+            }
+        }
+
+        for (m, keywords) in matches.iter_mut().zip(&keywords) {
+            let (count, _) = occurrences[keywords.as_slice()];
+            m.score.frequency_boost = rank::frequency_boost(count, weights);
+        }
+
+        matches.sort_by(|a, b| b.score.total().total_cmp(&a.score.total()));
+        matches
+    }
+
+    /// The entries exactly matching the word, best first.
+    pub fn exact_matches(&self, word: &str, language: Language, weights: &Weights) -> Vec<&Entry> {
+        let exact = |m: &Match| m.kind == MatchKind::Exact;
+        self.ranked(word, language, weights, &Deadline::default(), exact)
+            .into_iter()
+            .map(|m| m.entry)
+            .collect()
+    }
+
+    /// The phrases and idioms containing the German word or phrase, best first.
+    pub fn phrases(&self, word: &str, weights: &Weights) -> Vec<Match<'_>> {
+        self.search_with(word, Language::German, weights)
+            .into_iter()
+            .filter(|m| m.kind != MatchKind::Substring && m.entry.is_phrase())
+            .collect()
+    }
+
+    /// The entries read by `rd` exactly matching the word, best first. Only the lines
+    /// which may match are parsed, so that nothing has to be loaded up front.
+    pub fn scan_exact_matches<R: BufRead>(
+        rd: LineReader<R>,
+        word: &str,
+        language: Language,
+        weights: &Weights,
+    ) -> io::Result<Vec<Entry>> {
+        // Ranking needs all of them.
+        let found = Self::search(rd, word, language).collect::<io::Result<_>>()?;
+        let dict = Self::from_entries(found);
+        Ok(dict
+            .exact_matches(word, language, weights)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Like [`Dictionary::scan_exact_matches`], reading only the lines the index lists if
+    /// there is one.
+    pub fn open_exact_matches(
+        source: &DictionarySource,
+        index: Option<&mut Index>,
+        word: &str,
+        language: Language,
+        weights: &Weights,
+    ) -> io::Result<Vec<Entry>> {
+        match index {
+            Some(index) => {
+                let rd = LineReader::indexed(index.candidates(word, language)?);
+                Self::scan_exact_matches(rd, word, language, weights)
+            }
+            None => {
+                let rd = LineReader::new(source.reader()?);
+                Self::scan_exact_matches(rd, word, language, weights)
+            }
+        }
+    }
+
+    /// The results of looking up the word like the CLI does: the entries exactly matching
+    /// it which the options keep, best first. Unless the options ask to match the case,
+    /// the word is looked up in the dictionary's spelling, see [`Dictionary::spelling`],
+    /// which is returned along with them.
+    pub fn meanings(
+        &self,
+        word: &str,
+        language: Language,
+        options: &Options,
+    ) -> (String, Vec<Match<'_>>) {
+        let word = match options.match_case {
+            true => None,
+            false => self.spelling(word, language),
+        }
+        .unwrap_or_else(|| word.to_string());
+        let keep = |m: &Match| m.kind == MatchKind::Exact && options.keeps(m.entry);
+        let matches = self.ranked(
+            &word,
+            language,
+            &options.weights,
+            &Deadline::default(),
+            keep,
+        );
+        (word, matches)
+    }
+
+    /// The noun entries for the given German word, that is the entries which carry
+    /// a gender or are marked as plural.
+    pub fn gender(&self, word: &str) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|e| {
+                e.german.match_exact(word) && (e.german.gender().is_some() || e.german.is_plural())
+            })
+            .collect()
+    }
+
+    /// The noun entries for the given German word grouped by gender, in the order the
+    /// genders first appear. Homographs like "der See" and "die See" get a group each.
+    pub fn genders(&self, word: &str) -> Vec<NounSense<'_>> {
+        let mut senses: Vec<NounSense> = vec![];
+        for entry in self.gender(word) {
+            let gender = entry.german.gender();
+            match senses.iter_mut().find(|s| s.gender == gender) {
+                Some(sense) => sense.entries.push(entry),
+                None => senses.push(NounSense {
+                    gender,
+                    entries: vec![entry],
+                }),
+            }
+        }
+        senses
+    }
+
+    /// The genders of the German noun with their translations and plurals. The plurals
+    /// are looked up in `plurals`, which may be the dictionary itself.
+    pub fn nouns<'a>(&'a self, plurals: &Dictionary, word: &'a str) -> Vec<Noun<'a>> {
+        self.genders(word)
+            .into_iter()
+            .map(|sense| {
+                let mut translations: Vec<String> = vec![];
+                for entry in &sense.entries {
+                    let translation = entry.english.to_string();
+                    if !translations.contains(&translation) {
+                        translations.push(translation);
+                    }
+                }
+                let plural = sense
+                    .gender
+                    .and_then(|_| plurals.plural_of(word, &sense.entries))
+                    .and_then(|t| t.primary_keyword().map(String::from));
+
+                Noun {
+                    headword: sense.entries[0].headword().unwrap_or(word),
+                    term: &sense.entries[0].german,
+                    gender: sense.gender,
+                    translations,
+                    plural,
+                }
+            })
+            .collect()
+    }
+
+    /// The articles of the German noun, as in "der/die" for nouns with several genders,
+    /// and its plural, if the dictionary has it. Nouns which only exist in the plural get
+    /// "die" and themselves. `None` if the word is no noun.
+    pub fn article_and_plural<'a>(&'a self, word: &'a str) -> Option<(String, &'a str)> {
+        let senses = self.genders(word);
+        let genders: Vec<_> = senses.iter().filter_map(|s| s.gender).collect();
+        if genders.is_empty() {
+            return (!senses.is_empty()).then(|| ("die".to_string(), word));
+        }
+
+        let articles: Vec<_> = genders.iter().map(Gender::to_string).collect();
+        let singular: Vec<_> = senses
+            .iter()
+            .filter(|s| s.gender.is_some())
+            .flat_map(|s| s.entries.iter().copied())
+            .collect();
+        let plural = self
+            .plural_of(word, &singular)
+            .and_then(|t| t.primary_keyword())
+            .unwrap_or_default();
+        Some((articles.join("/"), plural))
+    }
+
+    /// The German nouns exactly translating the English word, best first, each German
+    /// term once.
+    pub fn german_nouns(&self, english: &str, weights: &Weights) -> Vec<&Entry> {
+        let mut seen = HashSet::new();
+        self.exact_matches(english, Language::English, weights)
+            .into_iter()
+            .filter(|e| e.german.gender().is_some() || e.german.is_plural())
+            .filter(|e| seen.insert(e.german.to_dictcc_string()))
+            .collect()
+    }
+
+    /// The English words whose entries may hold the plurals of the German noun, see
+    /// [`Dictionary::load_plurals`]: the plural entries are found through the plurals of
+    /// the English translations.
+    pub fn plural_candidates(&self, word: &str) -> Vec<String> {
+        self.genders(word)
+            .iter()
+            .flat_map(|s| &s.entries)
+            .flat_map(|e| english_plurals(&e.english.keywords().collect::<Vec<_>>().join(" ")))
+            .collect()
+    }
+
+    /// Loads the plural entries which may translate to one of the candidates, see
+    /// [`Dictionary::plural_candidates`].
+    pub fn load_plurals<R: BufRead>(rd: LineReader<R>, candidates: &[String]) -> io::Result<Self> {
+        Self::load_filtered(rd, |line| {
+            line.contains("{pl}")
+                && candidates
+                    .iter()
+                    .any(|c| may_contain(line, c, Language::English))
+        })
+    }
+
+    /// The plural entries which may belong to the German noun, whose entries are in this
+    /// dictionary, read through the index if there is one, see [`Dictionary::load_plurals`].
+    pub fn open_plurals(
+        &self,
+        source: &DictionarySource,
+        index: Option<&mut Index>,
+        word: &str,
+    ) -> io::Result<Self> {
+        let candidates = self.plural_candidates(word);
+        match index {
+            Some(index) => {
+                let mut lines = vec![];
+                for candidate in &candidates {
+                    if let Some(last) = candidate.split_whitespace().last() {
+                        lines.extend(index.lookup(last, Language::English)?);
+                    }
+                }
+                Self::load_plurals(LineReader::indexed(lines), &candidates)
+            }
+            None => Self::load_plurals(LineReader::new(source.reader()?), &candidates),
+        }
+    }
+
+    /// Finds the plural of a noun among the plural entries, pairing them through the
+    /// translations: "Hunde {pl}" is the plural of "Hund {m}" because it is a form of
+    /// "Hund" and translates to "dogs".
+    pub fn plural_of(&self, word: &str, singular: &[&Entry]) -> Option<&Term> {
+        let plurals: Vec<_> = singular
+            .iter()
+            .flat_map(|e| english_plurals(&lowercase_keywords(&e.english)))
+            .collect();
+
+        self.entries
+            .iter()
+            .filter(|e| e.german.is_plural() && e.german.gender().is_none())
+            .filter(|e| {
+                let mut keywords = e.german.keywords();
+                matches!(
+                    (keywords.next(), keywords.next()),
+                    (Some(k), None) if is_plural_form(word, k)
+                )
+            })
+            .find(|e| plurals.contains(&lowercase_keywords(&e.english)))
+            .map(|e| &e.german)
+    }
+}
+
+/// The lazy scan of [`Dictionary::search`]. Only the lines which may hold the word are
+/// parsed.
+pub struct Search<R> {
+    rd: LineReader<R>,
+    word: String,
+    language: Language,
+    buf: String,
+}
+
+impl<R: BufRead> Iterator for Search<R> {
+    type Item = io::Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.rd.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            if self.buf.starts_with('#') || !may_contain(&self.buf, &self.word, self.language) {
+                continue;
+            }
+            let Ok(entry) = Entry::parse(&self.buf, self.rd.source()) else {
+                continue;
+            };
+            if is_exact(entry.term(self.language), &self.word, self.language) {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+/// The scan of [`Dictionary::lines`].
+pub struct Lines<R, F> {
+    rd: LineReader<R>,
+    filter: F,
+    buf: String,
+}
+
+impl<R: BufRead, F: Fn(&str) -> bool> Iterator for Lines<R, F> {
+    type Item = io::Result<(Entry, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.rd.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            if self.buf.starts_with('#') || !(self.filter)(&self.buf) {
+                continue;
+            }
+            if let Ok(entry) = Entry::parse(&self.buf, self.rd.source()) {
+                return Some(Ok((entry, self.buf.clone())));
+            }
+        }
+    }
+}
+
+/// Looks up one word after another, through the index if there is one. Without it, the
+/// whole dictionary is loaded once instead of being scanned for every word.
+pub struct Lookup<'a> {
+    source: &'a DictionarySource,
+    index: Option<Index>,
+    loaded: Dictionary,
+}
+
+impl<'a> Lookup<'a> {
+    pub fn new(source: &'a DictionarySource, index: Option<Index>) -> io::Result<Self> {
+        let loaded = match index {
+            Some(_) => Dictionary::default(),
+            None => Dictionary::load(source.reader()?)?,
+        };
+        Ok(Self {
+            source,
+            index,
+            loaded,
+        })
+    }
+
+    /// The entries exactly matching the word, best first.
+    pub fn exact_matches(
+        &mut self,
+        word: &str,
+        language: Language,
+        weights: &Weights,
+    ) -> io::Result<Vec<Entry>> {
+        let found;
+        let dict = match &mut self.index {
+            Some(index) => {
+                found = Dictionary::open(self.source, Some(index), word, language)?;
+                &found
+            }
+            None => &self.loaded,
+        };
+        Ok(dict
+            .exact_matches(word, language, weights)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+}
+
+/// The entries of a German noun with one gender, `None` for entries which only exist
+/// in the plural.
+#[derive(Debug)]
+pub struct NounSense<'a> {
+    pub gender: Option<Gender>,
+    pub entries: Vec<&'a Entry>,
+}
+
+/// A gender of a German noun, see [`Dictionary::nouns`].
+#[derive(Debug)]
+pub struct Noun<'a> {
+    /// Spelled like the dictionary does, which may differ from the query if diacritics
+    /// are ignored.
+    pub headword: &'a str,
+    /// The German term of the first entry.
+    pub term: &'a Term,
+    pub gender: Option<Gender>,
+    /// The distinct translations, in dictionary order.
+    pub translations: Vec<String>,
+    /// The plural, if the dictionary has it.
+    pub plural: Option<String>,
+}
+
+/// Whether `plural` may be the plural of the German noun `singular`: it starts with the
+/// singular, umlauts aside, as "Häuser" does with "Haus", followed by a short ending.
+/// Latin and Greek endings are replaced, as in "Museum" and "Museen".
+fn is_plural_form(singular: &str, plural: &str) -> bool {
+    const MAX_ENDING: usize = 3;
+    const FOREIGN_ENDINGS: &[&str] = &["um", "us", "on", "a"];
+
+    let singular = util::collation_key(singular);
+    let plural = util::collation_key(plural);
+    let stems = std::iter::once(singular.as_str()).chain(
+        FOREIGN_ENDINGS
+            .iter()
+            .filter_map(|e| singular.strip_suffix(e)),
+    );
+    stems
+        .filter(|stem| !stem.is_empty())
+        .filter_map(|stem| plural.strip_prefix(stem))
+        .any(|ending| ending.chars().count() <= MAX_ENDING)
+}
+
+/// The regular plural forms an English noun may have.
+pub fn english_plurals(singular: &str) -> Vec<String> {
+    let mut candidates = vec![format!("{}s", singular), format!("{}es", singular)];
+    if let Some(stem) = singular.strip_suffix('y') {
+        candidates.push(format!("{}ies", stem));
+    }
+    candidates
+}
+
+fn lowercase_keywords(term: &Term) -> String {
+    term.keywords().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Cheaply checks whether the raw dictionary line can contain the word, or all words
+/// of a phrase, in the column of the given language, without parsing it.
+pub fn may_contain(line: &str, word: &str, language: Language) -> bool {
+    let column = match language {
+        Language::German => 0,
+        Language::English => 1,
+    };
+
+    line.split('\t').nth(column).is_some_and(|c| {
+        word.split_whitespace()
+            .all(|w| util::case_fold_contains(c, w))
+    })
+}
+
+/// The lowercased keywords of the other side of the match, without the "to" of
+/// English infinitives.
+fn translation_keywords(m: &Match) -> Vec<String> {
+    let other = match m.language {
+        Language::German => &m.entry.english,
+        Language::English => &m.entry.german,
+    };
+    let mut keywords = other.keywords().peekable();
+    if m.language == Language::German {
+        keywords.next_if_eq(&"to");
+    }
+
+    keywords.map(str::to_lowercase).collect()
+}
+
+/// Whether the entry is kept when refining results by the given word or tag. Tags like
+/// "[zool.]" are looked up in the subjects, word class and annotations, words anywhere
+/// in the terms.
+pub fn refines(entry: &Entry, needle: &str) -> bool {
+    let tag = needle.trim_start_matches('[').trim_end_matches(']');
+    let is_tag = |v: &str| util::case_fold_eq(v, tag);
+
+    entry.subjects.iter().any(|s| is_tag(s.as_str()))
+        || entry.word_classes.iter().any(|c| is_tag(c.as_str()))
+        || [&entry.german, &entry.english]
+            .into_iter()
+            .flat_map(Term::annotations)
+            .any(|a| is_tag(a.value))
+        || util::case_fold_contains(&entry.german.to_string(), needle)
+        || util::case_fold_contains(&entry.english.to_string(), needle)
+}
+
+/// The entries whose translation contains the needle, for picking one of several results
+/// by typing part of its translation. If some translations are exactly the needle, only
+/// those are kept. Entries with the same translation count once, as they can't be told
+/// apart by it anyway.
+pub fn with_translation<'a>(entries: &'a [Entry], needle: &str) -> Vec<&'a Entry> {
+    let mut candidates: Vec<_> = entries
+        .iter()
+        .filter(|e| util::case_fold_contains(&e.english.to_string(), needle))
+        .collect();
+    if candidates.iter().any(|e| e.english.match_exact(needle)) {
+        candidates.retain(|e| e.english.match_exact(needle));
+    }
+    let mut seen = HashSet::new();
+    candidates.retain(|e| seen.insert(e.english.to_string()));
+    candidates
+}
+
+/// The key under which two dictionary lines are the same entry: the terms in canonical
+/// notation plus the word class, ignoring whitespace differences.
+pub fn normalize_line(line: &str) -> String {
+    let mut columns = line.split('\t').map(str::trim);
+
+    let mut normalize_term = || {
+        let column = columns.next().unwrap_or_default();
+        Term::parse(column)
+            .map(|t| t.to_dictcc_string())
+            .unwrap_or_else(|_| column.split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+    let german = normalize_term();
+    let english = normalize_term();
+    let class = columns.next().unwrap_or_default();
+
+    format!("{}\t{}\t{}", german, english, class)
+}
+
+/// The keywords a query is matched against. The "to" of English infinitives is left
+/// out, like query normalization does, and so are placeholders the parser doesn't
+/// recognize, like the English "sth.".
+fn match_keywords(term: &Term, language: Language) -> Vec<&str> {
+    let mut keywords: Vec<_> = term.keywords().filter(|k| !is_placeholder(k)).collect();
+    if language == Language::English && keywords.len() > 1 && keywords[0] == "to" {
+        keywords.remove(0);
+    }
+    keywords
+}
+
+/// The words of a query that are matched against keywords.
+pub fn query_components(word: &str) -> Vec<&str> {
+    word.split_whitespace()
+        .filter(|w| !is_placeholder(w))
+        .collect()
+}
+
+fn keywords_eq(keywords: &[&str], components: &[&str]) -> bool {
+    keywords.len() == components.len()
+        && keywords
+            .iter()
+            .zip(components)
+            .all(|(k, c)| util::case_fold_eq(k, c))
+}
+
+/// Whether the term is exactly the query, a word or a phrase of whitespace separated
+/// words matched against the keywords of one of the term's variants in order.
+pub fn is_exact(term: &Term, word: &str, language: Language) -> bool {
+    let components = query_components(word);
+    term.variants()
+        .iter()
+        .any(|v| keywords_eq(&match_keywords(v, language), &components))
+}
+
+/// How the query matches the keywords of a term, if it does: the matched keywords, the
+/// kind of match and its base score.
+fn match_keywords_with(
+    keywords: &[&str],
+    components: &[&str],
+    weights: &Weights,
+) -> Option<(String, MatchKind, f32)> {
+    if keywords_eq(keywords, components) {
+        Some((keywords.join(" "), MatchKind::Exact, weights.exact))
+    } else if let Some(window) = keywords
+        .windows(components.len())
+        .find(|w| keywords_eq(w, components))
+    {
+        Some((window.join(" "), MatchKind::Keyword, weights.keyword))
+    } else if let [word] = components[..] {
+        let keyword = keywords
+            .iter()
+            .find(|k| util::case_fold_contains(k, word))?;
+        let coverage = word.len() as f32 / keyword.len() as f32;
+        Some((
+            keyword.to_string(),
+            MatchKind::Substring,
+            weights.substring * coverage,
+        ))
+    } else {
+        None
+    }
+}
+
+fn match_entry<'a>(
+    entry: &'a Entry,
+    word: &str,
+    language: Language,
+    weights: &Weights,
+) -> Option<Match<'a>> {
+    let term = entry.term(language);
+    let components = query_components(word);
+    if components.is_empty() {
+        return None;
+    }
+
+    // The variant matching best counts.
+    let (keyword, kind, base) = term
+        .variants()
+        .iter()
+        .filter_map(|v| match_keywords_with(&match_keywords(v, language), &components, weights))
+        .reduce(|best, m| if m.2 > best.2 { m } else { best })?;
+
+    let rendered = term.to_string();
+    let mut ranges: Vec<_> = components
+        .iter()
+        .flat_map(|c| util::case_fold_find_all(&rendered, c))
+        .collect();
+    ranges.sort_by_key(|r| r.start);
+
+    Some(Match {
+        entry,
+        language,
+        keyword,
+        kind,
+        score: Score {
+            base,
+            length_penalty: rank::length_penalty(term, weights),
+            register_penalty: rank::register_penalty([&entry.german, &entry.english], weights),
+            complexity_penalty: rank::complexity_penalty([&entry.german, &entry.english], weights),
+            frequency_boost: 0.0,
+            word_class_boost: rank::word_class_boost(entry, word, language, weights),
+        },
+        ranges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(lines: &str) -> Dictionary {
+        Dictionary::load(lines.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn load_batch_in_one_pass() {
+        let lines = "Hund {m}\tdog\tnoun\n\
+                     Hundehütte {f}\tkennel\tnoun\n\
+                     (schnelle) Katze {f}\tcat\tnoun\n\
+                     jdn. auf den Hund bringen\tto ruin sb.\tverb\n";
+        let queries = ["hund", "Katze", "Maus", "den Hund"].map(String::from);
+
+        let dicts = Dictionary::load_batch(
+            LineReader::new(lines.as_bytes()),
+            &queries,
+            Language::German,
+        )
+        .unwrap();
+        let lines: Vec<Vec<_>> = dicts
+            .iter()
+            .map(|d| d.entries().iter().map(|e| e.source.line).collect())
+            .collect();
+        assert_eq!(lines, [vec![1, 4], vec![3], vec![], vec![4]]);
+    }
+
+    #[test]
+    fn search_lazily() {
+        let lines = "# comment\n\
+                     Hund {m}\tdog\tnoun\n\
+                     Hundehütte {f}\tkennel\tnoun\n\
+                     (kleiner) Hund {m}\tpuppy\tnoun\n\
+                     Hund {m} (\tbroken\tnoun\n\
+                     Hund {m}\thound\tnoun\n";
+
+        let mut found =
+            Dictionary::search(LineReader::new(lines.as_bytes()), "hund", Language::German);
+        assert_eq!(found.next().unwrap().unwrap().source.line, 2);
+        // The rest of the dictionary is only read on demand.
+        let rest: Vec<_> = found.map(|e| e.unwrap().source.line).collect();
+        assert_eq!(rest, [4, 6]);
+    }
+
+    #[test]
+    fn variants_match_on_their_own() {
+        let dict = dictionary(
+            "mutwillige / böswillige Beschädigung {f}\twilful damage\tnoun\n\
+             Beschädigung {f}\tdamage\tnoun\n\
+             und/oder\tand/or\tconj\n\
+             Ein/Aus-Anzeige {f}\ton/off indicator\tnoun\n",
+        );
+
+        for word in ["mutwillige Beschädigung", "böswillige beschädigung"] {
+            let found: Vec<_> = dict.lookup(word, Language::German);
+            assert_eq!(found.len(), 1, "{}", word);
+            assert_eq!(found[0].source.line, 1);
+        }
+        assert!(dict
+            .lookup("mutwillige böswillige Beschädigung", Language::German)
+            .is_empty());
+        // Slashes inside compounds separate no variants.
+        for word in ["oder", "ein", "Aus-Anzeige"] {
+            assert!(dict.lookup(word, Language::German).is_empty(), "{}", word);
+        }
+        assert_eq!(
+            dict.spelling("böswillige beschädigung", Language::German)
+                .as_deref(),
+            Some("böswillige Beschädigung")
+        );
+    }
+
+    #[test]
+    fn genders_and_plurals() {
+        let dict = dictionary(
+            "See {m}\tlake\tnoun\n\
+             See {f}\tsea\tnoun\n\
+             See {m}\tlough\tnoun\n\
+             Seen {pl}\tlakes\tnoun\n\
+             Leute {pl}\tpeople\tnoun\n",
+        );
+
+        let senses = dict.genders("See");
+        let genders: Vec<_> = senses.iter().map(|s| s.gender).collect();
+        assert_eq!(genders, [Some(Gender::Masculine), Some(Gender::Feminine)]);
+        assert_eq!(senses[0].entries.len(), 2);
+
+        let plural = dict.plural_of("See", &senses[0].entries).unwrap();
+        assert_eq!(plural.keywords().collect::<Vec<_>>(), ["Seen"]);
+        assert!(dict.plural_of("See", &senses[1].entries).is_none());
+
+        assert_eq!(dict.genders("Leute")[0].gender, None);
+    }
+
+    #[test]
+    fn plurals_are_forms_of_the_singular() {
+        let dict = dictionary(
+            "See {f}\tsea\tnoun\n\
+             See {f}\tbrine\tnoun\n\
+             Salzlaugen {pl}\tbrines\tnoun\n\
+             Haus {n}\thouse\tnoun\n\
+             Häuser {pl}\thouses\tnoun\n\
+             Museum {n}\tmuseum\tnoun\n\
+             Museen {pl}\tmuseums\tnoun\n",
+        );
+        let plural = |word| {
+            let senses = dict.genders(word);
+            dict.plural_of(word, &senses[0].entries)
+                .and_then(|t| t.primary_keyword())
+        };
+
+        assert_eq!(plural("See"), None);
+        assert_eq!(plural("Haus"), Some("Häuser"));
+        assert_eq!(plural("Museum"), Some("Museen"));
+    }
+
+    #[test]
+    fn genders_of_tricky_formats() {
+        let dict = dictionary(
+            "Filovirus {n} {ugs.: m}\tfilovirus\tnoun\t[biol.] [med.]\n\
+             Bulle {m} [ugs.]\tcop [coll.]\tnoun\n\
+             Bulle {f} [hist.]\tbull [papal]\tnoun\n\
+             Auto {n} [Automobil]\tcar\tnoun\n\
+             Autos {pl} [Automobile]\tcars\tnoun\n\
+             Eltern {pl}\tparents\tnoun\n",
+        );
+        let genders = |word| {
+            dict.genders(word)
+                .iter()
+                .map(|s| s.gender)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(genders("Filovirus"), [Some(Gender::Neutral)]);
+        assert_eq!(
+            genders("Bulle"),
+            [Some(Gender::Masculine), Some(Gender::Feminine)]
+        );
+        assert_eq!(genders("Eltern"), [None]);
+        assert!(genders("Automobil").is_empty());
+
+        let auto = &dict.genders("Auto")[0];
+        let plural = dict.plural_of("Auto", &auto.entries).unwrap();
+        assert_eq!(plural.keywords().collect::<Vec<_>>(), ["Autos"]);
+    }
+
+    #[test]
+    fn frequency_boost_with_many_candidates() {
+        let lines: String = (0..20_000)
+            .map(|i| format!("das Ding Nummer {i}\tthe thing number {i}\tnoun\n"))
+            .collect();
+        let dict = dictionary(&lines);
+
+        // Comparing every translation with all others takes half a minute here.
+        let start = Instant::now();
+        assert_eq!(dict.find("the", Language::English).len(), 20_000);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn search_match_metadata() {
+        let dict = dictionary(
+            "Hund {m}\tdog\tnoun\n\
+             Hundehütte {f}\tkennel\tnoun\n\
+             fauler Hund {m}\tlazybones\tnoun\n",
+        );
+
+        let matches = dict.find("hund", Language::German);
+        let kinds: Vec<_> = matches.iter().map(|m| m.kind).collect();
+        assert_eq!(
+            kinds,
+            [MatchKind::Exact, MatchKind::Keyword, MatchKind::Substring]
+        );
+
+        let exact = &matches[0];
+        assert_eq!(exact.keyword, "Hund");
+        assert_eq!(exact.term().to_string(), "der Hund");
+        assert_eq!(exact.ranges.len(), 1);
+        assert_eq!(exact.ranges[0], 4..8);
+
+        assert_eq!(matches[2].keyword, "Hundehütte");
+        assert!(matches[2].score.total() < matches[1].score.total());
+    }
+
+    #[test]
+    fn search_phrase() {
+        let dict = dictionary(
+            "jdn. ins Bockshorn jagen\tto buffalo sb.\tverb\n\
+             etw. berücksichtigen\tto take sth. into account\tverb\n\
+             Rechnung {f}\taccount\tnoun\n",
+        );
+
+        let german = dict.lookup_german("ins Bockshorn jagen");
+        assert_eq!(german.len(), 1);
+        assert_eq!(german[0].english.to_string(), "to buffalo sb.");
+
+        let english = dict.find("take into account", Language::English);
+        assert_eq!(english.len(), 1);
+        assert_eq!(english[0].kind, MatchKind::Exact);
+        assert_eq!(english[0].keyword, "take into account");
+
+        assert!(dict.lookup_english("into account").is_empty());
+        assert_eq!(
+            dict.find("into account", Language::English)[0].kind,
+            MatchKind::Keyword
+        );
+    }
+
+    #[test]
+    fn search_prefers_simple_entries() {
+        let dict = dictionary(
+            "(etw.) (zu viel) bekommen [ugs.]\tto get (too much)\t\n\
+             Bekommen {n}\treceipt\tnoun\n\
+             bekommen\tto get\tverb\n",
+        );
+
+        let matches = dict.find("bekommen", Language::German);
+        let english: Vec<_> = matches
+            .iter()
+            .map(|m| m.entry.english.to_string())
+            .collect();
+        assert_eq!(english, ["to get", "receipt", "to get (too much)"]);
+    }
+
+    #[test]
+    fn search_puts_common_translations_first() {
+        let dict = dictionary(
+            "Hund {m}, der nicht bellen kann\tbarkless dog [dog that can't bark]\tnoun\t\n\
+             Hund {m} an der Kette\tdog on the chain\tnoun\t\n\
+             Hund {m}\tdog\tnoun\t[zool.] [T]\n\
+             Hund {m} für Hunderennen\trace dog\tnoun\t\n\
+             Hund {m}\thound [coll: any dog]\tnoun\t[zool.] [T]\n\
+             Hund {m} mit Stammbaum\tpedigree dog\tnoun\t\n\
+             Hund {m} [ein Tier der Familie Canidae]\tcanine\tnoun\t[zool.] [T]\n\
+             Hund {m} [Förderwagen; Schreibweise neben »Hunt«]\tmining car\tnoun\t[mining]\n\
+             Hund {m} [Jagdhund]\thound\tnoun\t[zool.] [T]\n\
+             Hund {m} [ugs.] [pej.] [Schuft, Lump]\thound [dated] [coll.] [rogue]\tnoun\t\n",
+        );
+
+        let matches = dict.find("Hund", Language::German);
+        let exact: Vec<_> = matches
+            .iter()
+            .filter(|m| m.kind == MatchKind::Exact)
+            .map(|m| m.entry.english.to_string())
+            .collect();
+        assert_eq!(exact[0], "dog");
+    }
+
+    #[test]
+    fn spelling() {
+        let dict = dictionary(
+            "Haus {n}\thouse\tnoun\n\
+             Morgen {m}\tmorning\tnoun\n\
+             morgen\ttomorrow\tadv\n\
+             jdm. Beine machen\tto make sb. hurry\t\n",
+        );
+
+        assert_eq!(dict.spelling("haus", Language::German).unwrap(), "Haus");
+        assert_eq!(dict.spelling("HAUS", Language::German).unwrap(), "Haus");
+        assert_eq!(dict.spelling("Haus", Language::German), None);
+        assert_eq!(dict.spelling("morgen", Language::German), None);
+        assert_eq!(
+            dict.spelling("beine MACHEN", Language::German).unwrap(),
+            "Beine machen"
+        );
+        assert_eq!(dict.spelling("Hund", Language::German), None);
+
+        let matches = dict.find("Morgen", Language::German);
+        assert_eq!(matches[0].entry.english.to_string(), "morning");
+    }
+}
@@ -0,0 +1,301 @@
+//! A minimal line editor for the interactive mode, with cursor movement, the usual
+//! Emacs style shortcuts and a history that persists across sessions.
+//!
+//! Editing needs the terminal in raw mode, see [`RawMode`]. Without it, lines are read as
+//! they are.
+
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Read, Write},
+    path::PathBuf,
+};
+
+use crate::{console::RawMode, store};
+
+/// How many history entries are kept.
+const MAX_HISTORY: usize = 1000;
+
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+/// A key press, decoded from the bytes the terminal sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    KillToStart,
+    KillToEnd,
+    KillWord,
+    /// Ctrl-S, which the terminal passes on in raw mode.
+    Save,
+    Interrupt,
+    /// Ctrl-D, which ends the input on an empty line and deletes otherwise.
+    Eof,
+    /// Stdin was closed.
+    Closed,
+    Unknown,
+}
+
+impl LineEditor {
+    /// Creates an editor with the history stored in the given file, if any.
+    pub fn new(history_path: Option<PathBuf>) -> Self {
+        let mut history: Vec<String> = history_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|s| s.lines().map(String::from).collect())
+            .unwrap_or_default();
+        let excess = history.len().saturating_sub(MAX_HISTORY);
+        history.drain(..excess);
+
+        Self {
+            history,
+            history_path,
+        }
+    }
+
+    /// Reads a line after showing the prompt. Returns `None` at the end of input and when
+    /// the user presses Ctrl-C or Ctrl-D on an empty line.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let line = match RawMode::enable() {
+            Some(_raw) if io::stdout().is_terminal() => self.edit(prompt)?,
+            _ => read_plain(prompt)?,
+        };
+
+        if let Some(line) = &line {
+            self.add_history(line)?;
+        }
+
+        Ok(line)
+    }
+
+    fn add_history(&mut self, line: &str) -> io::Result<()> {
+        let line = line.trim();
+        if line.is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return Ok(());
+        }
+        self.history.push(line.to_string());
+
+        let Some(path) = &self.history_path else {
+            return Ok(());
+        };
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+            let mut contents = self.history.join("\n");
+            contents.push('\n');
+            store::write_atomic(path, contents.as_bytes())
+        } else {
+            store::append_line(path, line)
+        }
+    }
+
+    fn edit(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let mut stdin = io::stdin().lock();
+        let mut stdout = io::stdout().lock();
+
+        let mut line: Vec<char> = vec![];
+        let mut cursor = 0;
+        // The history entry being shown, `history.len()` for the line being typed.
+        let mut position = self.history.len();
+        let mut typed = vec![];
+
+        loop {
+            redraw(&mut stdout, prompt, &line, cursor)?;
+
+            match read_key(&mut stdin)? {
+                Key::Char(c) => {
+                    line.insert(cursor, c);
+                    cursor += 1;
+                }
+                Key::Enter => {
+                    write!(stdout, "\r\n")?;
+                    stdout.flush()?;
+                    return Ok(Some(line.into_iter().collect()));
+                }
+                Key::Backspace if cursor > 0 => {
+                    cursor -= 1;
+                    line.remove(cursor);
+                }
+                Key::Delete if cursor < line.len() => {
+                    line.remove(cursor);
+                }
+                Key::Left => cursor = cursor.saturating_sub(1),
+                Key::Right => cursor = (cursor + 1).min(line.len()),
+                Key::Home => cursor = 0,
+                Key::End => cursor = line.len(),
+                Key::Up if position > 0 => {
+                    if position == self.history.len() {
+                        typed = line.clone();
+                    }
+                    position -= 1;
+                    line = self.history[position].chars().collect();
+                    cursor = line.len();
+                }
+                Key::Down if position < self.history.len() => {
+                    position += 1;
+                    line = match self.history.get(position) {
+                        Some(entry) => entry.chars().collect(),
+                        None => typed.clone(),
+                    };
+                    cursor = line.len();
+                }
+                Key::KillToStart => {
+                    line.drain(..cursor);
+                    cursor = 0;
+                }
+                Key::KillToEnd => line.truncate(cursor),
+                Key::KillWord => {
+                    let end = cursor;
+                    while cursor > 0 && line[cursor - 1].is_whitespace() {
+                        cursor -= 1;
+                    }
+                    while cursor > 0 && !line[cursor - 1].is_whitespace() {
+                        cursor -= 1;
+                    }
+                    line.drain(cursor..end);
+                }
+                Key::Interrupt => {
+                    write!(stdout, "^C\r\n")?;
+                    stdout.flush()?;
+                    return Ok(None);
+                }
+                Key::Eof if line.is_empty() => {
+                    write!(stdout, "\r\n")?;
+                    stdout.flush()?;
+                    return Ok(None);
+                }
+                Key::Eof if cursor < line.len() => {
+                    line.remove(cursor);
+                }
+                Key::Closed => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn read_plain(prompt: &str) -> io::Result<Option<String>> {
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+
+    let mut buf = String::new();
+    if io::stdin().lock().read_line(&mut buf)? == 0 {
+        writeln!(stdout)?;
+        return Ok(None);
+    }
+
+    Ok(Some(buf.trim_end_matches(['\n', '\r']).to_string()))
+}
+
+fn redraw(mut wr: impl Write, prompt: &str, line: &[char], cursor: usize) -> io::Result<()> {
+    let text: String = line.iter().collect();
+    write!(wr, "\r{}{}\x1b[K", prompt, text)?;
+    let back = line.len() - cursor;
+    if back > 0 {
+        write!(wr, "\x1b[{}D", back)?;
+    }
+    wr.flush()
+}
+
+fn read_byte(rd: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut b = [0];
+    match rd.read(&mut b)? {
+        0 => Ok(None),
+        _ => Ok(Some(b[0])),
+    }
+}
+
+pub(crate) fn read_key(rd: &mut impl Read) -> io::Result<Key> {
+    let Some(b) = read_byte(rd)? else {
+        return Ok(Key::Closed);
+    };
+
+    Ok(match b {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x0b => Key::KillToEnd,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x13 => Key::Save,
+        0x15 => Key::KillToStart,
+        0x17 => Key::KillWord,
+        0x1b => read_escape(rd)?,
+        b if b < 0x20 => Key::Unknown,
+        b => read_char(rd, b)?,
+    })
+}
+
+/// Decodes the escape sequences of the arrow, home, end, page and delete keys.
+fn read_escape(rd: &mut impl Read) -> io::Result<Key> {
+    let Some(b'[' | b'O') = read_byte(rd)? else {
+        return Ok(Key::Unknown);
+    };
+
+    Ok(match read_byte(rd)? {
+        Some(b'A') => Key::Up,
+        Some(b'B') => Key::Down,
+        Some(b'C') => Key::Right,
+        Some(b'D') => Key::Left,
+        Some(b'H') => Key::Home,
+        Some(b'F') => Key::End,
+        Some(d @ b'0'..=b'9') => {
+            // Sequences like "\x1b[3~", possibly with parameters as in "\x1b[1;5C".
+            let mut last = d;
+            while let Some(b) = read_byte(rd)? {
+                if !(b.is_ascii_digit() || b == b';') {
+                    last = b;
+                    break;
+                }
+            }
+            match (d, last) {
+                (b'1' | b'7', b'~') => Key::Home,
+                (b'4' | b'8', b'~') => Key::End,
+                (b'3', b'~') => Key::Delete,
+                (b'5', b'~') => Key::PageUp,
+                (b'6', b'~') => Key::PageDown,
+                _ => Key::Unknown,
+            }
+        }
+        _ => Key::Unknown,
+    })
+}
+
+fn read_char(rd: &mut impl Read, first: u8) -> io::Result<Key> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+
+    let mut bytes = vec![first];
+    for _ in 1..len {
+        match read_byte(rd)? {
+            Some(b) => bytes.push(b),
+            None => break,
+        }
+    }
+
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|s| s.chars().next())
+        .map_or(Key::Unknown, Key::Char))
+}
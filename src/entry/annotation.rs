@@ -1,21 +1,35 @@
 use std::fmt;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AnnotationKind {
     Explanation, // [ ]
     Alternative, // < >
     Number,      // some between { }
+    Curly,       // the rest between { }, like "{ugs.: m}"
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Annotation {
-    pub value: String,
+/// An annotation of a term, with its text borrowed from the term.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation<'a> {
+    pub value: &'a str,
     pub kind: AnnotationKind,
 }
 
-impl fmt::Debug for Annotation {
+impl fmt::Debug for Annotation<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.kind.fmt(f)?;
         write!(f, "(\"{}\")", self.value)
     }
 }
+
+impl fmt::Display for Annotation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            AnnotationKind::Explanation => write!(f, "[{}]", self.value),
+            AnnotationKind::Alternative => write!(f, "<{}>", self.value),
+            AnnotationKind::Number | AnnotationKind::Curly => write!(f, "{{{}}}", self.value),
+        }
+    }
+}
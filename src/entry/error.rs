@@ -0,0 +1,49 @@
+/// Why a dictionary line couldn't be parsed. Positions are byte offsets into the term
+/// at fault, the slice is the part of it which couldn't be parsed.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("unclosed parenthesis \"{slice}\" at position {position}")]
+    UnclosedParenthesis { position: usize, slice: String },
+    #[error("unexpected char \"{slice}\" at position {position}")]
+    UnexpectedChar { position: usize, slice: String },
+    #[error("unfinished placeholder case \"{slice}\" at position {position}")]
+    UnfinishedCase { position: usize, slice: String },
+    #[error("unclosed curly brace \"{slice}\" at position {position}")]
+    UnclosedCurly { position: usize, slice: String },
+    #[error("unclosed parenthesis in keyword \"{slice}\" at position {position}")]
+    UnclosedKeywordParenthesis { position: usize, slice: String },
+    #[error(
+        "special char before closing parenthesis in keyword \"{slice}\" at position {position}"
+    )]
+    SpecialCharInKeyword { position: usize, slice: String },
+    #[error("no english component")]
+    MissingEnglish,
+}
+
+impl ParseError {
+    /// Where in the term parsing failed.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Self::UnclosedParenthesis { position, .. }
+            | Self::UnexpectedChar { position, .. }
+            | Self::UnfinishedCase { position, .. }
+            | Self::UnclosedCurly { position, .. }
+            | Self::UnclosedKeywordParenthesis { position, .. }
+            | Self::SpecialCharInKeyword { position, .. } => Some(*position),
+            Self::MissingEnglish => None,
+        }
+    }
+
+    /// The part of the term which couldn't be parsed.
+    pub fn slice(&self) -> Option<&str> {
+        match self {
+            Self::UnclosedParenthesis { slice, .. }
+            | Self::UnexpectedChar { slice, .. }
+            | Self::UnfinishedCase { slice, .. }
+            | Self::UnclosedCurly { slice, .. }
+            | Self::UnclosedKeywordParenthesis { slice, .. }
+            | Self::SpecialCharInKeyword { slice, .. } => Some(slice),
+            Self::MissingEnglish => None,
+        }
+    }
+}
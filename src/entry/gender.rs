@@ -1,12 +1,25 @@
 use std::fmt;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Gender {
     Masculine,
     Feminine,
     Neutral,
 }
 
+impl Gender {
+    /// Parses the tag dict.cc uses inside curly braces, as in "{m}".
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "m" => Some(Self::Masculine),
+            "f" => Some(Self::Feminine),
+            "n" => Some(Self::Neutral),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Gender {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
@@ -1,3 +1,5 @@
+mod error;
+pub use error::*;
 mod gender;
 pub use gender::*;
 mod annotation;
@@ -7,3 +9,9 @@ pub use placeholder::*;
 mod part;
 mod term;
 pub use term::*;
+mod record;
+pub use record::*;
+mod subject;
+pub use subject::*;
+mod word_class;
+pub use word_class::*;
@@ -1,499 +1,243 @@
-use super::{Annotation, AnnotationKind, Case, Gender, Placeholder};
-use std::cmp;
+use std::{fmt, iter::Peekable, ops::Range};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub enum Part {
-    Keyword(String),          // Keywords
-    Extra(Vec<Part>),         // In parantheses
-    VariantSeparator,         // The character "/"
-    Placeholder(Placeholder), // etw. jd. jdn. jdm. jds.
-    Annotation(Annotation), // Information inside [] (explanation), <> (alternative), {} (numbers) but not cases
-    Gender(Gender),         // {m} {n} {f}
-}
+use super::{Annotation, AnnotationKind, Case, Gender, ParseError, Placeholder};
+use crate::lexer::{self, LiteralMarker, Token, Tokens};
 
-pub struct Parser<'a> {
-    state: state::State<'a>,
+/// Where the text of a part is in the text of its term. Terms are short, so the offsets
+/// fit in `u32`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Span {
+    start: u32,
+    end: u32,
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(input: &'a str) -> Self {
-        Self::make(input, false)
-    }
-
-    fn make(s: &'a str, stop_at_parens: bool) -> Self {
-        Self {
-            state: state::State::Base(state::Base::new(s, stop_at_parens)),
-        }
+impl Span {
+    pub fn range(self) -> Range<usize> {
+        self.start as usize..self.end as usize
     }
+}
 
-    fn parse(mut self) -> Result<(Vec<Part>, &'a str), anyhow::Error> {
-        use state::Result as R;
-
-        let mut parts = vec![];
+/// A part of a term. Its text is kept in the term, which holds the texts of all its
+/// parts one after another, so that a term takes two allocations instead of one per
+/// part.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Part {
+    Keyword(Span),                     // Keywords
+    Extra(Box<[Part]>),                // In parantheses
+    VariantSeparator { spaced: bool }, // The character "/", spaced if between whitespace
+    Placeholder(Placeholder),          // etw. jd. jdn. jdm. jds.
+    Annotation(AnnotationKind, Span), // Information inside [] (explanation), <> (alternative), {} (numbers and others) but not cases
+    Gender(Gender),                   // {m} {n} {f}
+    Quoted(Span),                     // Inside “” or „“, without the quotes
+}
 
-        loop {
-            match self.state.step() {
-                R::Keep(state, part) => {
-                    self.state = state;
-                    parts.extend(part)
-                }
-                R::Done(res) => {
-                    let (part, left) = res?;
-                    parts.extend(part);
-                    return Ok((parts, left));
-                }
-            };
+/// Collects the texts of the parts of a term.
+#[derive(Debug, Default)]
+pub struct Text(String);
+
+impl Text {
+    pub fn push(&mut self, s: &str) -> Span {
+        let start = self.0.len() as u32;
+        self.0.push_str(s);
+        Span {
+            start,
+            end: self.0.len() as u32,
         }
     }
 
-    pub fn parse_parts(self) -> Result<Vec<Part>, anyhow::Error> {
-        self.parse().map(|p| p.0)
+    /// Copies the parts of another term with their texts.
+    pub fn copy(&mut self, text: &str, parts: &[Part]) -> Vec<Part> {
+        parts
+            .iter()
+            .map(|p| match p {
+                Part::Keyword(s) => Part::Keyword(self.push(&text[s.range()])),
+                Part::Extra(ps) => Part::Extra(self.copy(text, ps).into()),
+                Part::Annotation(kind, s) => Part::Annotation(*kind, self.push(&text[s.range()])),
+                Part::Quoted(s) => Part::Quoted(self.push(&text[s.range()])),
+                p => p.clone(),
+            })
+            .collect()
     }
-}
 
-impl cmp::PartialOrd for Part {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        use Part::*;
-
-        match (self, other) {
-            (Keyword(a), Keyword(b)) => {
-                let res = a.to_uppercase().cmp(&b.to_uppercase());
-                Some(if res != cmp::Ordering::Equal {
-                    res
-                } else {
-                    b.cmp(a)
-                })
-            }
-            (Extra(a), Extra(b)) => a.partial_cmp(b),
-            (VariantSeparator, VariantSeparator) => Some(cmp::Ordering::Equal),
-            (Gender(a), Gender(b)) => a.eq(b).then_some(cmp::Ordering::Equal),
-            _ => None,
-        }
+    pub fn into_boxed_str(self) -> Box<str> {
+        self.0.into_boxed_str()
     }
 }
 
-mod state {
-    use super::Part;
-    use anyhow::anyhow;
+/// The parts with their texts, for debug output.
+pub struct Parts<'a>(pub &'a str, pub &'a [Part]);
 
-    fn is_special_char(c: char) -> bool {
-        matches!(c, '[' | '{' | '<' | '(' | '/' | ' ')
+impl fmt::Debug for Parts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(text, parts) = *self;
+        f.debug_list()
+            .entries(parts.iter().map(|p| PartWithText(text, p)))
+            .finish()
     }
+}
 
-    fn is_special_char_with_end_paren(c: char) -> bool {
-        matches!(c, '[' | '{' | '<' | '(' | ')' | '/' | ' ')
-    }
-
-    pub(super) struct Base<'a> {
-        s: &'a str,
-        stop_at_parens: bool,
-        done: bool,
-    }
-
-    impl<'a> Base<'a> {
-        pub(super) fn new(input: &'a str, nested: bool) -> Self {
-            Self {
-                s: input,
-                stop_at_parens: nested,
-                done: false,
-            }
-        }
-
-        fn step(mut self) -> self::Result<'a> {
-            use self::Result as R;
-            use State as S;
-
-            if self.is_empty() || self.done {
-                return R::Done(if self.stop_at_parens && !self.done {
-                    Err(anyhow!("base: unclosed parenthesis"))
-                } else {
-                    Ok((None, self.s))
-                });
-            }
-
-            let i = match self.find(if self.stop_at_parens {
-                is_special_char_with_end_paren
-            } else {
-                is_special_char
-            }) {
-                Some(i) => i,
-                None => {
-                    let consumed = self.s;
-                    self.s = "";
-                    return self.handle_word(consumed);
-                }
-            };
-
-            let without_consumed = self.s;
-            let consumed = &self.s[..i];
-            let without_ch = &self.s[i..];
-            let ch = &self.s[i..i + 1];
-            let is_valid_parens = i == 0 || (i > 0 && matches!(self.s.get(i - 1..i), Some(" ")));
-            self.s = &self.s[i + 1..];
-
-            match ch {
-                " " if consumed.is_empty() => R::Keep(S::Base(self), None),
-                " " => self.handle_word(consumed),
-                "/" if consumed.is_empty() => R::Keep(S::Base(self), Some(Part::VariantSeparator)),
-                "/" => {
-                    self.s = without_ch;
-                    self.handle_word(consumed)
-                }
-                "[" | "<" => R::Keep(
-                    S::Annotation(Annotation {
-                        kind: match ch {
-                            "[" => super::AnnotationKind::Explanation,
-                            "<" => super::AnnotationKind::Alternative,
-                            _ => unreachable!(),
-                        },
-                        b: self,
-                    }),
-                    None,
-                ),
-                "{" => R::Keep(S::Gender(Curly(self)), None),
-                "(" if is_valid_parens => R::Keep(
-                    S::Extra(Extra(
-                        Box::new(super::Parser::make(self.s, true)),
-                        self.stop_at_parens,
-                    )),
-                    None,
-                ),
-                "(" => {
-                    self.s = without_consumed;
-                    R::Keep(
-                        S::KeywordParens(KeywordParens {
-                            b: self,
-                            search_at: i + 1,
-                            parens_closed: false,
-                        }),
-                        None,
-                    )
-                }
-                ")" if self.stop_at_parens => {
-                    if consumed.is_empty() {
-                        R::Done(Ok((None, self.s)))
-                    } else {
-                        self.done = true;
-                        self.handle_word(consumed)
-                    }
-                }
-                _ => R::Done(Err(anyhow!("base: unexpected special char \"{}\"", ch))),
-            }
-        }
-
-        fn handle_word(self, word: &'a str) -> self::Result<'a> {
-            use self::Result as R;
-            use State as S;
-
-            let maybe_person_placeholder_case = match word {
-                "jd." => Some(super::Case::Nominative),
-                "jdn." => Some(super::Case::Accusative),
-                "jds." => Some(super::Case::Dative),
-                "jdm." => Some(super::Case::Genitive),
-                _ => None,
-            };
-
-            if let Some(case) = maybe_person_placeholder_case {
-                return R::Keep(
-                    S::Base(self),
-                    Some(Part::Placeholder(super::Placeholder::Person(case))),
-                );
-            }
-
-            match word {
-                "etw." | "sich" => R::Keep(S::Placeholder(Placeholder(self, word)), None),
-                _ => R::Keep(S::Base(self), Some(Part::Keyword(word.to_string()))),
+struct PartWithText<'a>(&'a str, &'a Part);
+
+impl fmt::Debug for PartWithText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(text, part) = *self;
+        match part {
+            Part::Keyword(s) => f.debug_tuple("Keyword").field(&&text[s.range()]).finish(),
+            Part::Extra(ps) => f.debug_tuple("Extra").field(&Parts(text, ps)).finish(),
+            Part::VariantSeparator { spaced } => f
+                .debug_struct("VariantSeparator")
+                .field("spaced", spaced)
+                .finish(),
+            Part::Placeholder(ph) => f.debug_tuple("Placeholder").field(ph).finish(),
+            Part::Annotation(kind, s) => {
+                let annotation = Annotation {
+                    kind: *kind,
+                    value: &text[s.range()],
+                };
+                f.debug_tuple("Annotation").field(&annotation).finish()
             }
+            Part::Gender(g) => f.debug_tuple("Gender").field(g).finish(),
+            Part::Quoted(s) => f.debug_tuple("Quoted").field(&&text[s.range()]).finish(),
         }
     }
+}
 
-    impl std::ops::Deref for Base<'_> {
-        type Target = str;
+/// Builds the parts of a term from its [tokens](crate::lexer).
+pub struct Parser<'a> {
+    input: &'a str,
+    tokens: Peekable<Tokens<'a>>,
+    text: Text,
+}
 
-        fn deref(&self) -> &Self::Target {
-            self.s
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            tokens: lexer::lex(input).peekable(),
+            text: Text::default(),
         }
     }
 
-    pub(super) struct Extra<'a>(Box<super::Parser<'a>>, bool);
-
-    impl<'a> Extra<'a> {
-        fn step(self) -> self::Result<'a> {
-            use self::Result as R;
-            use State as S;
-
-            match self.0.parse() {
-                Ok((parts, s)) => R::Keep(
-                    S::Base(Base {
-                        s,
-                        stop_at_parens: self.1,
-                        done: false,
-                    }),
-                    Some(Part::Extra(parts)),
-                ),
-                Err(e) => R::Done(Err(e)),
-            }
-        }
+    /// The parts of the term with their texts.
+    pub fn parse_parts(mut self) -> Result<(Text, Vec<Part>), ParseError> {
+        let parts = self.parse(None)?;
+        Ok((self.text, parts))
     }
 
-    pub(super) struct Placeholder<'a>(Base<'a>, &'a str);
-
-    impl<'a> Placeholder<'a> {
-        fn step(mut self) -> self::Result<'a> {
-            use self::Result as R;
-            use State as S;
-
-            let get_placeholder = |case| match self.1 {
-                "etw." => super::Placeholder::Thing(case),
-                "sich" => super::Placeholder::Reflexive(case),
-                _ => unreachable!(),
-            };
-            let default_part = || Some(Part::Placeholder(get_placeholder(None)));
-
-            if self.0.is_empty() {
-                return R::Done(Ok((default_part(), "")));
-            }
-
-            let mut s = self.0.s;
-            let reset_state = || {
-                R::Keep(
-                    S::Base(Base {
-                        s,
-                        stop_at_parens: self.0.stop_at_parens,
-                        done: self.0.done,
-                    }),
-                    default_part(),
-                )
-            };
-
-            match s.find('[') {
-                Some(i) if i < 2 => s = &s[i + 1..],
-                Some(_) | None => return reset_state(),
-            };
-
-            if s.is_empty() {
-                return R::Done(Err(anyhow!(
-                    "thing_placeholder: unfinished placeholder case"
-                )));
-            }
-
-            if let Some("+") = s.get(..1) {
-                s = &s[1..];
-            }
-
-            if s.len() < 5 {
-                return reset_state();
-            }
+    /// The parts up to the parenthesis closing the one opened at the given position, or
+    /// up to the end of the term if none is open.
+    fn parse(&mut self, open: Option<usize>) -> Result<Vec<Part>, ParseError> {
+        let mut parts = vec![];
 
-            let case = match s.get(..4) {
-                Some("Nom.") => super::Case::Nominative,
-                Some("Akk.") => super::Case::Accusative,
-                Some("Gen.") => super::Case::Genitive,
-                Some("Dat.") => super::Case::Dative,
-                Some(_) | None => return reset_state(),
+        while let Some(token) = self.tokens.next() {
+            let (at, token) = token.map_err(|e| self.error(e))?;
+            let part = match token {
+                Token::Open => Part::Extra(self.parse(Some(at))?.into()),
+                Token::Close => return Ok(parts),
+                Token::Slash => Part::VariantSeparator {
+                    spaced: self.spaced(at),
+                },
+                Token::Literal { value, marker } => self.literal(value, marker),
+                Token::Text(word) => self.word(word)?,
+                Token::Quoted(quoted) => Part::Quoted(self.text.push(quoted)),
             };
+            parts.push(part);
+        }
 
-            s = &s[4..];
-
-            if &s[..1] != "]" {
-                return reset_state();
-            }
-
-            self.0.s = &s[1..];
-            R::Keep(
-                S::Base(self.0),
-                Some(Part::Placeholder(get_placeholder(Some(case)))),
-            )
+        match open {
+            Some(position) => Err(ParseError::UnclosedParenthesis {
+                position,
+                slice: self.input[position..].to_string(),
+            }),
+            None => Ok(parts),
         }
     }
 
-    pub(super) struct Annotation<'a> {
-        kind: super::AnnotationKind,
-        b: Base<'a>,
+    /// Whether the slash at the given position has whitespace on both sides, as between
+    /// the alternatives of "mutwillige / böswillige Beschädigung" but not in "und/oder".
+    fn spaced(&self, at: usize) -> bool {
+        self.input[..at].ends_with(char::is_whitespace)
+            && self.input[at + 1..].starts_with(char::is_whitespace)
     }
 
-    impl<'a> Annotation<'a> {
-        fn step(mut self) -> self::Result<'a> {
-            use self::Result as R;
-            use State as S;
-
-            let (start_char, end_char) = match self.kind {
-                super::AnnotationKind::Alternative => ('<', '>'),
-                super::AnnotationKind::Explanation => ('[', ']'),
-                super::AnnotationKind::Number => unreachable!(), // The Curly state takes care of this
-            };
-
-            let mut nesting = 1;
-            let mut end = None;
-            let mut first_non_annotation_end = None;
-
-            for (i, c) in self.b.char_indices() {
-                if c == end_char {
-                    nesting -= 1;
-                    if nesting == 0 {
-                        end = Some(i);
-                        break;
-                    }
-                } else if c == start_char {
-                    nesting += 1;
-                } else if self.b.stop_at_parens && is_special_char_with_end_paren(c)
-                    || (!self.b.stop_at_parens && is_special_char(c))
-                {
-                    first_non_annotation_end = Some(i)
-                }
-            }
-
-            let end = match end {
-                Some(end) => end,
-                None => {
-                    return match first_non_annotation_end {
-                        Some(i) => {
-                            let consumed = start_char.to_string() + &self.b[..i];
-                            self.b.s = &self.b.s[i..];
-                            R::Keep(S::Base(self.b), Some(Part::Keyword(consumed)))
-                        }
-                        None => R::Done(Ok((
-                            Some(Part::Keyword(start_char.to_string() + self.b.s)),
-                            "",
-                        ))),
-                    };
-                }
-            };
-
-            let part = Part::Annotation(super::Annotation {
-                kind: self.kind,
-                value: self.b[..end].to_string(),
-            });
-
-            self.b.s = &self.b.s[end + 1..];
-
-            R::Keep(S::Base(self.b), Some(part))
+    fn word(&mut self, word: &str) -> Result<Part, ParseError> {
+        let case = match word {
+            "jd." => Some(Case::Nominative),
+            "jdn." => Some(Case::Accusative),
+            "jdm." => Some(Case::Dative),
+            "jds." => Some(Case::Genitive),
+            _ => None,
+        };
+        if let Some(case) = case {
+            return Ok(Part::Placeholder(Placeholder::Person(case)));
         }
-    }
-
-    pub(super) struct Curly<'a>(Base<'a>);
-
-    impl<'a> Curly<'a> {
-        fn step(mut self) -> self::Result<'a> {
-            use self::Result as R;
-            use super::Gender as G;
-            use State as S;
-
-            let end = match self.0.s.find('}') {
-                Some(end) => end,
-                None => return R::Done(Err(anyhow!("curly: unfinished curly clause"))),
-            };
 
-            let gender_str = &self.0.s[..end];
-            let gender = match gender_str {
-                "m" => Some(G::Masculine),
-                "f" => Some(G::Feminine),
-                "n" => Some(G::Neutral),
-                "pl" | "pl." | "sg" | "sg." => {
-                    self.0.s = &self.0.s[end + 1..];
-
-                    return R::Keep(
-                        S::Base(self.0),
-                        Some(Part::Annotation(super::Annotation {
-                            kind: super::AnnotationKind::Number,
-                            value: match gender_str {
-                                "pl" | "pl." => "nur plural",
-                                "sg" | "sg." => "singular",
-                                _ => unreachable!(),
-                            }
-                            .into(),
-                        })),
-                    );
+        let placeholder: fn(Option<Case>) -> Placeholder = match word {
+            "etw." => Placeholder::Thing,
+            "sich" => Placeholder::Reflexive,
+            _ => return Ok(Part::Keyword(self.text.push(word))),
+        };
+
+        // The case may follow in square brackets, as in "etw. [Dat.]".
+        let case = match self.tokens.peek() {
+            Some(Ok((_, Token::Literal { value, marker }))) if *marker == LiteralMarker::Square => {
+                match value.strip_prefix('+').unwrap_or(value) {
+                    "Nom." => Some(Case::Nominative),
+                    "Akk." => Some(Case::Accusative),
+                    "Gen." => Some(Case::Genitive),
+                    "Dat." => Some(Case::Dative),
+                    _ => None,
                 }
-                _ => None,
-            };
-
-            self.0.s = &self.0.s[end + 1..];
-
-            R::Keep(S::Base(self.0), gender.map(Part::Gender))
+            }
+            Some(Ok((position, Token::Text("[")))) if position + 1 == self.input.len() => {
+                return Err(ParseError::UnfinishedCase {
+                    position: *position,
+                    slice: "[".into(),
+                })
+            }
+            _ => None,
+        };
+        if case.is_some() {
+            self.tokens.next();
         }
-    }
 
-    pub(super) struct KeywordParens<'a> {
-        b: Base<'a>,
-        search_at: usize,
-        parens_closed: bool,
+        Ok(Part::Placeholder(placeholder(case)))
     }
 
-    impl<'a> KeywordParens<'a> {
-        fn step(mut self) -> Result<'a> {
-            use self::Result as R;
-            use State as S;
-
-            let just_parens = |c| c == ')';
-
-            let i = match self.b[self.search_at..].find(if self.parens_closed {
-                is_special_char
-            } else if self.b.stop_at_parens {
-                is_special_char_with_end_paren
-            } else {
-                just_parens
-            }) {
-                Some(i) => i,
-                None => return R::Done(Err(anyhow!("keyword_parens: unmatched parens"))),
-            } + self.search_at;
-
-            match &self.b[i..i + 1] {
-                ")" => {
-                    self.parens_closed = true;
-                    self.search_at = i + 1;
-                    if self.search_at == self.b.len() {
-                        R::Done(Ok((
-                            Some(Part::Keyword(self.b[..self.search_at].to_string())),
-                            "",
-                        )))
-                    } else {
-                        R::Keep(S::KeywordParens(self), None)
-                    }
-                }
-                _ if self.parens_closed => {
-                    let consumed = self.b[..i].to_string();
-                    self.b.s = &self.b.s[i..];
-                    R::Keep(
-                        S::Base(self.b),
-                        if consumed.is_empty() {
-                            None
-                        } else {
-                            Some(Part::Keyword(consumed))
-                        },
-                    )
+    fn error(&self, error: lexer::Error) -> ParseError {
+        let slice = |at: usize, len| self.input[at..at + len].to_string();
+
+        match error {
+            lexer::Error::UnclosedCurly(position) => ParseError::UnclosedCurly {
+                position,
+                slice: slice(position, self.input.len() - position),
+            },
+            lexer::Error::UnclosedWordParenthesis(position) => {
+                ParseError::UnclosedKeywordParenthesis {
+                    position,
+                    slice: slice(position, self.input.len() - position),
                 }
-                _ => R::Done(Err(anyhow!(
-                    "keyword_parens: special char before closing parens"
-                ))),
             }
+            lexer::Error::SpecialCharInWord { at, len } => ParseError::SpecialCharInKeyword {
+                position: at,
+                slice: slice(at, len),
+            },
         }
     }
 
-    pub(super) enum Result<'a> {
-        Keep(State<'a>, Option<Part>),
-        Done(std::result::Result<(Option<Part>, &'a str), anyhow::Error>),
-    }
-
-    pub(super) enum State<'a> {
-        Base(Base<'a>),
-        Extra(Extra<'a>),
-        Placeholder(Placeholder<'a>),
-        Annotation(Annotation<'a>),
-        Gender(Curly<'a>),
-        KeywordParens(KeywordParens<'a>),
-    }
-
-    impl<'a> State<'a> {
-        pub(super) fn step(self) -> Result<'a> {
-            match self {
-                Self::Base(s) => s.step(),
-                Self::Annotation(s) => s.step(),
-                Self::Placeholder(s) => s.step(),
-                Self::Gender(s) => s.step(),
-                Self::Extra(s) => s.step(),
-                Self::KeywordParens(s) => s.step(),
-            }
+    fn literal(&mut self, value: &str, marker: LiteralMarker) -> Part {
+        let mut annotation = |kind, value: &str| Part::Annotation(kind, self.text.push(value));
+
+        match marker {
+            LiteralMarker::Square => annotation(AnnotationKind::Explanation, value),
+            LiteralMarker::Angle => annotation(AnnotationKind::Alternative, value),
+            LiteralMarker::Curly => match value {
+                "pl" | "pl." => annotation(AnnotationKind::Number, "nur plural"),
+                "sg" | "sg." => annotation(AnnotationKind::Number, "singular"),
+                // Kept, so that formatting the term loses nothing.
+                _ => Gender::from_tag(value)
+                    .map_or_else(|| annotation(AnnotationKind::Curly, value), Part::Gender),
+            },
         }
     }
 }
@@ -504,19 +248,49 @@ mod tests {
 
     use super::*;
 
+    /// The parts of a term with their texts, to compare with the expected ones.
+    #[derive(Debug, PartialEq)]
+    enum Expected<'a> {
+        Keyword(&'a str),
+        Extra(Vec<Expected<'a>>),
+        VariantSeparator,
+        Placeholder(Placeholder),
+        Annotation(Annotation<'a>),
+        Gender(Gender),
+        Quoted(&'a str),
+    }
+
+    fn resolve<'a>(text: &'a str, parts: &[Part]) -> Vec<Expected<'a>> {
+        parts
+            .iter()
+            .map(|p| match p {
+                Part::Keyword(s) => Expected::Keyword(&text[s.range()]),
+                Part::Extra(ps) => Expected::Extra(resolve(text, ps)),
+                Part::VariantSeparator { .. } => Expected::VariantSeparator,
+                Part::Placeholder(ph) => Expected::Placeholder(*ph),
+                Part::Annotation(kind, s) => Expected::Annotation(Annotation {
+                    kind: *kind,
+                    value: &text[s.range()],
+                }),
+                Part::Gender(g) => Expected::Gender(*g),
+                Part::Quoted(s) => Expected::Quoted(&text[s.range()]),
+            })
+            .collect()
+    }
+
     #[test]
     fn parse_parts() {
         let data = [
             (
                 "(an etw. [Dat.]) herumbasteln [ugs.]",
                 vec![
-                    Part::Extra(vec![
-                        Part::Keyword("an".into()),
-                        Part::Placeholder(Placeholder::Thing(Some(Case::Dative))),
+                    Expected::Extra(vec![
+                        Expected::Keyword("an"),
+                        Expected::Placeholder(Placeholder::Thing(Some(Case::Dative))),
                     ]),
-                    Part::Keyword("herumbasteln".into()),
-                    Part::Annotation(Annotation {
-                        value: "ugs.".into(),
+                    Expected::Keyword("herumbasteln"),
+                    Expected::Annotation(Annotation {
+                        value: "ugs.",
                         kind: AnnotationKind::Explanation,
                     }),
                 ],
@@ -524,49 +298,76 @@ mod tests {
             (
                 "sich [Akk.] ((bis) zu etw. [Dat.]) steigern",
                 vec![
-                    Part::Placeholder(Placeholder::Reflexive(Some(Case::Accusative))),
-                    Part::Extra(vec![
-                        Part::Extra(vec![Part::Keyword("bis".into())]),
-                        Part::Keyword("zu".into()),
-                        Part::Placeholder(Placeholder::Thing(Some(Case::Dative))),
+                    Expected::Placeholder(Placeholder::Reflexive(Some(Case::Accusative))),
+                    Expected::Extra(vec![
+                        Expected::Extra(vec![Expected::Keyword("bis")]),
+                        Expected::Keyword("zu"),
+                        Expected::Placeholder(Placeholder::Thing(Some(Case::Dative))),
                     ]),
-                    Part::Keyword("steigern".into()),
+                    Expected::Keyword("steigern"),
                 ],
             ),
             (
                 "sich [Akk.] (auf jdn./etw.) aufstützen",
                 vec![
-                    Part::Placeholder(Placeholder::Reflexive(Some(Case::Accusative))),
-                    Part::Extra(vec![
-                        Part::Keyword("auf".into()),
-                        Part::Placeholder(Placeholder::Person(Case::Accusative)),
-                        Part::VariantSeparator,
-                        Part::Placeholder(Placeholder::Thing(None)),
+                    Expected::Placeholder(Placeholder::Reflexive(Some(Case::Accusative))),
+                    Expected::Extra(vec![
+                        Expected::Keyword("auf"),
+                        Expected::Placeholder(Placeholder::Person(Case::Accusative)),
+                        Expected::VariantSeparator,
+                        Expected::Placeholder(Placeholder::Thing(None)),
                     ]),
-                    Part::Keyword("aufstützen".into()),
+                    Expected::Keyword("aufstützen"),
+                ],
+            ),
+            (
+                "Aktion {f} ”Brot für die Welt”",
+                vec![
+                    Expected::Keyword("Aktion"),
+                    Expected::Gender(Gender::Feminine),
+                    Expected::Quoted("Brot für die Welt"),
+                ],
+            ),
+            (
+                "Division {f} „Florian Geyer“ (Nr. 8)",
+                vec![
+                    Expected::Keyword("Division"),
+                    Expected::Gender(Gender::Feminine),
+                    Expected::Quoted("Florian Geyer"),
+                    Expected::Extra(vec![Expected::Keyword("Nr."), Expected::Keyword("8")]),
+                ],
+            ),
+            (
+                "'ne Menge [ugs.]",
+                vec![
+                    Expected::Keyword("'ne"),
+                    Expected::Keyword("Menge"),
+                    Expected::Annotation(Annotation {
+                        value: "ugs.",
+                        kind: AnnotationKind::Explanation,
+                    }),
                 ],
             ),
             (
                 "Vanadoandrosit-(Ce) {m}",
                 vec![
-                    Part::Keyword("Vanadoandrosit-(Ce)".into()),
-                    Part::Gender(Gender::Masculine),
+                    Expected::Keyword("Vanadoandrosit-(Ce)"),
+                    Expected::Gender(Gender::Masculine),
                 ],
             ),
             (
                 "Uluguru-(Zwerg-)Galago {m}",
                 vec![
-                    Part::Keyword("Uluguru-(Zwerg-)Galago".into()),
-                    Part::Gender(Gender::Masculine),
+                    Expected::Keyword("Uluguru-(Zwerg-)Galago"),
+                    Expected::Gender(Gender::Masculine),
                 ],
             ),
             (
                 "vicanite-(Ce) [Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5|(AsO4|BO3|Si3B3O18|SiO4)3]]",
                 vec![
-                    Part::Keyword("vicanite-(Ce)".into()),
-                    Part::Annotation(Annotation {
-                        value: "Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5|(AsO4|BO3|Si3B3O18|SiO4)3]"
-                            .into(),
+                    Expected::Keyword("vicanite-(Ce)"),
+                    Expected::Annotation(Annotation {
+                        value: "Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5|(AsO4|BO3|Si3B3O18|SiO4)3]",
                         kind: AnnotationKind::Explanation,
                     }),
                 ],
@@ -574,17 +375,17 @@ mod tests {
             (
                 "((für) etw. [Akk.]) pauken [ugs.] [intensiv lernen]",
                 vec![
-                    Part::Extra(vec![
-                        Part::Extra(vec![Part::Keyword("für".into())]),
-                        Part::Placeholder(Placeholder::Thing(Some(Case::Accusative))),
+                    Expected::Extra(vec![
+                        Expected::Extra(vec![Expected::Keyword("für")]),
+                        Expected::Placeholder(Placeholder::Thing(Some(Case::Accusative))),
                     ]),
-                    Part::Keyword("pauken".into()),
-                    Part::Annotation(Annotation {
-                        value: "ugs.".into(),
+                    Expected::Keyword("pauken"),
+                    Expected::Annotation(Annotation {
+                        value: "ugs.",
                         kind: AnnotationKind::Explanation,
                     }),
-                    Part::Annotation(Annotation {
-                        value: "intensiv lernen".into(),
+                    Expected::Annotation(Annotation {
+                        value: "intensiv lernen",
                         kind: AnnotationKind::Explanation,
                     }),
                 ],
@@ -592,59 +393,115 @@ mod tests {
             (
                 "unter Spannung von > 50 V",
                 vec![
-                    Part::Keyword("unter".into()),
-                    Part::Keyword("Spannung".into()),
-                    Part::Keyword("von".into()),
-                    Part::Keyword(">".into()),
-                    Part::Keyword("50".into()),
-                    Part::Keyword("V".into()),
+                    Expected::Keyword("unter"),
+                    Expected::Keyword("Spannung"),
+                    Expected::Keyword("von"),
+                    Expected::Keyword(">"),
+                    Expected::Keyword("50"),
+                    Expected::Keyword("V"),
                 ],
             ),
             (
                 "Blattgemüse {pl.}",
                 vec![
-                    Part::Keyword("Blattgemüse".into()),
-                    Part::Annotation(Annotation {
-                        value: "nur plural".into(),
+                    Expected::Keyword("Blattgemüse"),
+                    Expected::Annotation(Annotation {
+                        value: "nur plural",
                         kind: AnnotationKind::Number,
                     }),
                 ],
             ),
             (
                 "{sg.}",
-                vec![Part::Annotation(Annotation {
-                    value: "singular".into(),
+                vec![Expected::Annotation(Annotation {
+                    value: "singular",
                     kind: AnnotationKind::Number,
                 })],
             ),
             (
                 "Requiem(, nach Worten der heiligen Schrift)",
-                vec![Part::Keyword(
-                    "Requiem(, nach Worten der heiligen Schrift)".into(),
+                vec![Expected::Keyword(
+                    "Requiem(, nach Worten der heiligen Schrift)",
                 )],
             ),
-            // (
-            //     "(A(B))",
-            //     vec![Part::Extra(vec![Part::Keyword("A(B)".into())])],
-            // ),
+            (
+                "(A(B))",
+                vec![Expected::Extra(vec![Expected::Keyword("A(B)")])],
+            ),
             (
                 "Filovirus {n} {ugs.: m}",
                 vec![
-                    Part::Keyword("Filovirus".into()),
-                    Part::Gender(Gender::Neutral),
+                    Expected::Keyword("Filovirus"),
+                    Expected::Gender(Gender::Neutral),
+                    Expected::Annotation(Annotation {
+                        value: "ugs.: m",
+                        kind: AnnotationKind::Curly,
+                    }),
+                ],
+            ),
+            (
+                "radial keratotomy<RK>",
+                vec![
+                    Expected::Keyword("radial"),
+                    Expected::Keyword("keratotomy"),
+                    Expected::Annotation(Annotation {
+                        value: "RK",
+                        kind: AnnotationKind::Alternative,
+                    }),
                 ],
             ),
-            ("<SFL-Haubitze", vec![Part::Keyword("<SFL-Haubitze".into())]),
+            ("<SFL-Haubitze", vec![Expected::Keyword("<SFL-Haubitze")]),
             (
                 "(<SFL-Haubitze)",
-                vec![Part::Extra(vec![Part::Keyword("<SFL-Haubitze".into())])],
+                vec![Expected::Extra(vec![Expected::Keyword("<SFL-Haubitze")])],
             ),
         ];
 
         for (input, expected) in data {
-            println!("{}", input);
-            let output = Parser::new(input).parse_parts().unwrap();
-            assert_eq!(output, expected);
+            let (text, parts) = Parser::new(input).parse_parts().unwrap();
+            assert_eq!(resolve(&text.0, &parts), expected, "{}", input);
         }
     }
+
+    #[test]
+    fn parse_errors() {
+        let error = |input| Parser::new(input).parse_parts().unwrap_err();
+
+        assert_eq!(
+            error("Hund (groß (sehr)"),
+            ParseError::UnclosedParenthesis {
+                position: 5,
+                slice: "(groß (sehr)".into()
+            }
+        );
+        assert_eq!(
+            error("(a (b"),
+            ParseError::UnclosedParenthesis {
+                position: 3,
+                slice: "(b".into()
+            }
+        );
+        assert_eq!(
+            error("der Hund(e {m}"),
+            ParseError::UnclosedKeywordParenthesis {
+                position: 4,
+                slice: "Hund(e {m}".into()
+            }
+        );
+        assert_eq!(
+            error("Hund {m"),
+            ParseError::UnclosedCurly {
+                position: 5,
+                slice: "{m".into()
+            }
+        );
+        assert_eq!(
+            error("etw. ["),
+            ParseError::UnfinishedCase {
+                position: 5,
+                slice: "[".into()
+            }
+        );
+        assert_eq!(error("Hund {m").position(), Some(5));
+    }
 }
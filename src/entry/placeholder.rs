@@ -3,6 +3,7 @@ use std::fmt;
 use anyhow::anyhow;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Case {
     Nominative,
     Accusative,
@@ -44,9 +45,23 @@ impl Case {
         }
     }
 
+    /// The German name of the case, e.g. "Dativ".
+    pub fn name(&self) -> &str {
+        self.repr_str(true)
+    }
+
     fn repr_letter(&self) -> char {
         unsafe { self.repr_str(true).chars().next().unwrap_unchecked() }
     }
+
+    fn repr_abbreviation(&self) -> &str {
+        match self {
+            Self::Nominative => "Nom.",
+            Self::Accusative => "Akk.",
+            Self::Dative => "Dat.",
+            Self::Genitive => "Gen.",
+        }
+    }
 }
 
 impl TryFrom<&str> for Case {
@@ -63,13 +78,117 @@ impl TryFrom<&str> for Case {
     }
 }
 
+/// How the case a placeholder requires is written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseStyle {
+    /// A single letter, as in "etw(D)".
+    #[default]
+    Letter,
+    /// The dict.cc abbreviations, as in "etw. [Dat.]".
+    Short,
+    /// Full German words, as in "etwas (Dativ)".
+    Full,
+}
+
+impl TryFrom<&str> for CaseStyle {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "letter" => Ok(Self::Letter),
+            "short" => Ok(Self::Short),
+            "full" => Ok(Self::Full),
+            _ => Err(anyhow!("unknown case style \"{}\"", value)),
+        }
+    }
+}
+
+/// Whether placeholders are abbreviated, as in "jdm. etw. leihen", or written out, as in
+/// "jemandem etwas leihen", which is easier to read for beginners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaceholderStyle {
+    #[default]
+    Compact,
+    Expanded,
+}
+
+impl TryFrom<&str> for PlaceholderStyle {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "compact" => Ok(Self::Compact),
+            "expanded" => Ok(Self::Expanded),
+            _ => Err(anyhow!("unknown placeholder style \"{}\"", value)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Placeholder {
     Reflexive(Option<Case>),
     Thing(Option<Case>),
     Person(Case),
 }
 
+impl Placeholder {
+    /// Formats the placeholder with its case in the given style. The full case style
+    /// always writes the placeholder out.
+    pub fn render(&self, case_style: CaseStyle, style: PlaceholderStyle) -> String {
+        match (case_style, style) {
+            (CaseStyle::Letter, PlaceholderStyle::Compact) => return self.to_string(),
+            (CaseStyle::Short, PlaceholderStyle::Compact) => return self.to_dictcc_string(),
+            _ => {}
+        }
+
+        let (s, case) = match self {
+            Self::Thing(case) => ("etwas", *case),
+            Self::Reflexive(case) => ("sich", *case),
+            Self::Person(case) => (
+                match case {
+                    Case::Nominative => "jemand",
+                    Case::Accusative => "jemanden",
+                    Case::Dative => "jemandem",
+                    Case::Genitive => "jemandes",
+                },
+                // The word itself already shows the case.
+                (case_style == CaseStyle::Full).then_some(*case),
+            ),
+        };
+        match (case, case_style) {
+            (Some(case), CaseStyle::Letter) => format!("{}({})", s, case.repr_letter()),
+            (Some(case), CaseStyle::Short) => format!("{} [{}]", s, case.repr_abbreviation()),
+            (Some(case), CaseStyle::Full) => format!("{} ({})", s, case.repr_str(true)),
+            (None, _) => s.to_string(),
+        }
+    }
+
+    /// Formats the placeholder the way dict.cc writes it, e.g. "etw. [Dat.]" or "jdn.".
+    pub fn to_dictcc_string(&self) -> String {
+        match self {
+            Self::Thing(case) | Self::Reflexive(case) => {
+                let s = match self {
+                    Self::Thing(_) => "etw.",
+                    Self::Reflexive(_) => "sich",
+                    _ => unreachable!(),
+                };
+                match case {
+                    Some(case) => format!("{} [{}]", s, case.repr_abbreviation()),
+                    None => s.to_string(),
+                }
+            }
+            Self::Person(case) => match case {
+                Case::Nominative => "jd.",
+                Case::Accusative => "jdn.",
+                Case::Dative => "jdm.",
+                Case::Genitive => "jds.",
+            }
+            .to_string(),
+        }
+    }
+}
+
 impl fmt::Display for Placeholder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -93,3 +212,14 @@ impl fmt::Display for Placeholder {
         }
     }
 }
+
+/// Whether the word stands for a placeholder, also the ones the parser leaves as
+/// keywords, like the English "sth." or "sb.'s".
+pub(crate) fn is_placeholder(word: &str) -> bool {
+    word.split('/').all(|w| {
+        matches!(
+            w.trim_end_matches("'s"),
+            "sth." | "sb." | "etw." | "jd." | "jdn." | "jdm." | "jds."
+        )
+    })
+}
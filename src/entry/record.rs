@@ -0,0 +1,265 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
+use unicode_normalization::UnicodeNormalization;
+
+use super::{is_placeholder, ParseError, RenderOptions, Subject, Term, WordClass};
+use crate::{index, query::Language, util};
+
+/// The dictionary an entry comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Origin {
+    /// The embedded dictionary, or the one given with `--dict`.
+    #[default]
+    Main,
+    /// One of the user's own dictionaries, kept in the dictionaries directory.
+    Custom,
+}
+
+impl Origin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Main => "main",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+impl TryFrom<&str> for Origin {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "main" => Ok(Self::Main),
+            "custom" => Ok(Self::Custom),
+            _ => Err(anyhow!(
+                "unknown dictionary source \"{}\", expected main or custom",
+                value
+            )),
+        }
+    }
+}
+
+/// Where an entry comes from in the dictionary file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceRef {
+    /// 1-based line number.
+    pub line: usize,
+    /// Byte offset of the start of the line.
+    pub offset: usize,
+    pub origin: Origin,
+}
+
+impl fmt::Display for SourceRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, byte {}", self.line, self.offset)
+    }
+}
+
+/// Identifies an entry by its contents rather than by its position, so that it stays
+/// the same across dictionary updates which don't change the entry itself. Written as
+/// 16 hexadecimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryId(u64);
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for EntryId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if s.len() != 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(anyhow!("invalid entry id \"{}\"", s));
+        }
+        Ok(Self(u64::from_str_radix(s, 16)?))
+    }
+}
+
+/// A single dictionary line: a German term, its English translation, the
+/// word class column and the subject tags.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    pub german: Term,
+    pub english: Term,
+    pub word_classes: Vec<WordClass>,
+    pub subjects: Vec<Subject>,
+    pub source: SourceRef,
+}
+
+impl Entry {
+    /// Parses a tab separated dictionary line.
+    pub fn parse(line: &str, source: SourceRef) -> Result<Self, ParseError> {
+        let line = util::strip_invisible(line);
+        let mut columns = line.trim_end_matches(['\n', '\r']).split('\t');
+
+        let german = Term::parse(columns.next().unwrap_or_default())?;
+        let english = columns
+            .next()
+            .ok_or(ParseError::MissingEnglish)
+            .and_then(Term::parse)?;
+        let word_classes = WordClass::parse_column(columns.next().unwrap_or_default());
+        let subjects = Subject::parse_column(columns.next().unwrap_or_default());
+
+        Ok(Self {
+            german,
+            english,
+            word_classes,
+            subjects,
+            source,
+        })
+    }
+
+    /// The entry's ID, a hash over its columns in the notation the parser normalizes
+    /// them to, so that neither spacing nor how characters are composed matter.
+    pub fn id(&self) -> EntryId {
+        let join = |values: Vec<&str>| values.join(" ");
+        let columns = [
+            self.german.to_dictcc_string(),
+            self.english.to_dictcc_string(),
+            join(self.word_classes.iter().map(|c| c.as_str()).collect()),
+            join(self.subjects.iter().map(|s| s.as_str()).collect()),
+        ];
+
+        let mut hash = index::FNV_OFFSET;
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                hash = index::fnv1a(hash, b"\t");
+            }
+            hash = index::fnv1a(hash, column.nfc().collect::<String>().as_bytes());
+        }
+        EntryId(hash)
+    }
+
+    /// The term of the given language.
+    pub fn term(&self, language: Language) -> &Term {
+        match language {
+            Language::German => &self.german,
+            Language::English => &self.english,
+        }
+    }
+
+    /// The word the entry is filed under, the primary keyword of the German term.
+    pub fn headword(&self) -> Option<&str> {
+        self.german.primary_keyword()
+    }
+
+    /// Whether the entry is a phrase or idiom rather than a word: the German term has
+    /// several keywords besides placeholders, and the entry has no word class or is a
+    /// verb phrase like "auf etw. [Akk.] ankommen". Multi-word nouns like "Rotes Kreuz"
+    /// are words.
+    pub fn is_phrase(&self) -> bool {
+        let keywords = self.german.keywords().filter(|k| !is_placeholder(k));
+        keywords.count() > 1 && self.word_classes.iter().all(|c| *c == WordClass::Verb)
+    }
+
+    /// Renders the entry like [`Display`](fmt::Display) does, with its terms rendered
+    /// with the given options.
+    pub fn render(&self, options: RenderOptions) -> String {
+        let mut out = format!(
+            "{} = {}",
+            self.german.render(options),
+            self.english.render(options)
+        );
+        if !self.word_classes.is_empty() {
+            let classes: Vec<_> = self.word_classes.iter().map(|c| c.to_string()).collect();
+            out.push_str(&format!("  [{}]", classes.join(" ")));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(RenderOptions::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, EntryId, SourceRef};
+
+    #[test]
+    fn ids_depend_on_contents_only() {
+        let parse = |line: &str, n: usize| {
+            Entry::parse(
+                line,
+                SourceRef {
+                    line: n,
+                    offset: n * 100,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let id = parse("Hund {m}\tdog\tnoun\t[zool.]\n", 1).id();
+        assert_eq!(parse("Hund  {m}\tdog\tnoun\t[zool.] \r\n", 7).id(), id);
+        assert_eq!(
+            parse("\u{feff}Hu\u{ad}nd {m}\tdog\tnoun\t[zool.]", 2).id(),
+            id
+        );
+        assert_eq!(
+            parse("Mädchen {n}\tgirl", 1).id(),
+            parse("Ma\u{308}dchen {n}\tgirl", 2).id()
+        );
+        assert_eq!(
+            parse("Hund {m}\tdog\t\t", 1).id(),
+            parse("Hund {m}\tdog", 1).id()
+        );
+        assert_ne!(parse("Hund {m}\tdog\tnoun\t\n", 1).id(), id);
+        assert_ne!(
+            parse("Hund {m}\tdog\t\tnoun", 1).id(),
+            parse("Hund {m}\tdog\tnoun", 1).id()
+        );
+        assert_ne!(parse("Hunde {pl}\tdogs\tnoun\t[zool.]\n", 1).id(), id);
+
+        assert_eq!(id.to_string().parse::<EntryId>().unwrap(), id);
+        assert!("abc".parse::<EntryId>().is_err());
+        assert!("+00000000000000a".parse::<EntryId>().is_err());
+    }
+
+    #[test]
+    fn phrases() {
+        let is_phrase = |line: &str| {
+            Entry::parse(line, SourceRef::default())
+                .unwrap()
+                .is_phrase()
+        };
+
+        assert!(is_phrase(
+            "auf etw. [Akk.] ankommen\tto depend on sth.\tverb\t"
+        ));
+        assert!(is_phrase("Auf Wiedersehen!\tBye! [coll.]\t\t"));
+        assert!(!is_phrase("jdm. etw. geben\tto give sb. sth.\tverb\t"));
+        assert!(!is_phrase("Rotes Kreuz {n}\tRed Cross\tnoun\t"));
+        assert!(!is_phrase("Hund {m}\tdog\tnoun\t"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let entry = Entry::parse(
+            "jdm. etw. [Akk.] (aus)leihen [ugs.]\tto lend sb. sth.\tverb\t[fin.]\n",
+            SourceRef::default(),
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""english":"to lend sb. sth.""#), "{}", json);
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), entry);
+
+        // The layout terms had in memory, with a span beyond the text.
+        let spans = json.replace(
+            r#""english":"to lend sb. sth.""#,
+            r#""english":{"text":"lend","parts":[{"Keyword":{"start":0,"end":40}}]}"#,
+        );
+        assert!(serde_json::from_str::<Entry>(&spans).is_err());
+    }
+}
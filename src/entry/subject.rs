@@ -0,0 +1,74 @@
+use std::fmt;
+
+use crate::util;
+
+/// A subject tag like "[med.]" from the fourth column of the dictionary, stored
+/// without the brackets.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Subject(String);
+
+impl Subject {
+    /// Parses the subject tags of a subject column, dropping duplicates.
+    pub fn parse_column(column: &str) -> Vec<Self> {
+        let mut subjects: Vec<Self> = vec![];
+        for subject in column.split_ascii_whitespace().map(Self::from) {
+            if !subjects.contains(&subject) {
+                subjects.push(subject);
+            }
+        }
+        subjects
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the tag is the given one, ignoring case, brackets and the abbreviation
+    /// dot, so that "med", "Med." and "[med.]" all match "[med.]".
+    pub fn matches(&self, tag: &str) -> bool {
+        let normalize = |v: &str| {
+            v.trim_start_matches('[')
+                .trim_end_matches(']')
+                .trim_end_matches('.')
+                .to_string()
+        };
+        let (a, b) = (normalize(&self.0), normalize(tag));
+        util::case_fold_eq(&a, &b)
+    }
+}
+
+impl From<&str> for Subject {
+    fn from(value: &str) -> Self {
+        Self(
+            value
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+        )
+    }
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Subject;
+
+    #[test]
+    fn parse_and_match() {
+        let subjects = Subject::parse_column("[med.] [MedTech.] [med.] [T] ");
+        assert_eq!(subjects.len(), 3);
+        assert_eq!(subjects[0].to_string(), "[med.]");
+
+        assert!(subjects[0].matches("med"));
+        assert!(subjects[0].matches("[Med.]"));
+        assert!(!subjects[0].matches("medtech"));
+        assert!(subjects[1].matches("medtech"));
+        assert!(subjects[2].matches("t"));
+    }
+}
@@ -1,28 +1,54 @@
 use std::{
+    borrow::Cow,
     cmp::Ordering,
     fmt::{self, Display},
 };
 
+use anyhow::anyhow;
+
 use super::{
-    part::{Parser, Part},
-    Annotation, AnnotationKind,
+    is_placeholder,
+    part::{Parser, Part, Parts, Span, Text},
+    Annotation, AnnotationKind, CaseStyle, Gender, ParseError, Placeholder, PlaceholderStyle,
 };
+use crate::util;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A parsed term. The texts of its parts are kept one after another in a single buffer,
+/// in the order of the parts, so that equal terms have equal buffers.
+///
+/// With the serde feature, terms are serialized in dict.cc notation, see
+/// [`Term::to_dictcc_string`], and parsed again when deserialized.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Term {
-    parts: Vec<Part>,
+    text: Box<str>,
+    parts: Box<[Part]>,
 }
 
 impl Term {
-    pub fn parse(input: &str) -> Result<Term, anyhow::Error> {
-        Parser::new(input).parse_parts().map(|v| Self { parts: v })
+    pub fn parse(input: &str) -> Result<Term, ParseError> {
+        let (text, parts) = Parser::new(input).parse_parts()?;
+        Ok(Self {
+            text: text.into_boxed_str(),
+            parts: parts.into(),
+        })
+    }
+
+    /// A term of some of the parts of this one.
+    fn with_parts(&self, parts: &[Part]) -> Term {
+        let mut text = Text::default();
+        let parts = text.copy(&self.text, parts);
+        Self {
+            text: text.into_boxed_str(),
+            parts: parts.into(),
+        }
+    }
+
+    fn str(&self, span: Span) -> &str {
+        &self.text[span.range()]
     }
 
     pub fn match_exact(&self, input: &str) -> bool {
-        let mut it = self.parts.iter().filter_map(|p| match p {
-            Part::Keyword(w) => Some(w),
-            _ => None,
-        });
+        let mut it = self.keywords();
         let keyword = match it.next() {
             Some(k) => k,
             None => return false,
@@ -31,13 +57,202 @@ impl Term {
             return false;
         }
 
-        input.len() == keyword.len() && crate::util::case_fold_eq(input, keyword)
+        util::case_fold_eq(input, keyword)
+    }
+
+    /// The words of the term, including the words of quoted segments.
+    pub fn keywords(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().flat_map(|p| {
+            let (keyword, quoted) = match p {
+                Part::Keyword(w) => (Some(self.str(*w)), None),
+                Part::Quoted(q) => (None, Some(self.str(*q).split_whitespace())),
+                _ => (None, None),
+            };
+            keyword.into_iter().chain(quoted.into_iter().flatten())
+        })
+    }
+
+    /// The keyword the term is filed under: the first keyword which isn't a placeholder,
+    /// skipping the "to" of English infinitives. Words in parentheses don't count, so
+    /// "(kleiner) Hund" is filed under "Hund".
+    pub fn primary_keyword(&self) -> Option<&str> {
+        let mut keywords = self.keywords().filter(|k| !is_placeholder(k)).peekable();
+        let first = keywords.next()?;
+        match keywords.peek() {
+            Some(next) if first == "to" => Some(next),
+            _ => Some(first),
+        }
+    }
+
+    pub fn gender(&self) -> Option<Gender> {
+        self.parts.iter().find_map(|p| match p {
+            Part::Gender(g) => Some(*g),
+            _ => None,
+        })
+    }
+
+    /// All annotations of the term, including those inside parentheses.
+    pub fn annotations(&self) -> Vec<Annotation<'_>> {
+        self.flat_parts()
+            .into_iter()
+            .filter_map(|p| match p {
+                Part::Annotation(kind, value) => Some(Annotation {
+                    kind: *kind,
+                    value: self.str(*value),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// All placeholders of the term, including those inside parentheses.
+    pub fn placeholders(&self) -> Vec<&Placeholder> {
+        self.flat_parts()
+            .into_iter()
+            .filter_map(|p| match p {
+                Part::Placeholder(ph) => Some(ph),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The placeholders of the term with the keyword they follow, if any, including
+    /// those inside parentheses. Alternatives separated by slashes are one slot: "sich
+    /// [Akk.] (an jdm./etw.) freuen" has "sich" following no keyword and "jdm." or
+    /// "etw." following "an".
+    pub fn placeholder_slots(&self) -> Vec<(Option<&str>, Vec<&Placeholder>)> {
+        type Slot<'a> = (Option<&'a str>, Vec<&'a Placeholder>);
+
+        fn collect<'a>(term: &'a Term, parts: &'a [Part], out: &mut Vec<Slot<'a>>) {
+            let mut before = None;
+            // Whether the previous parts are a placeholder and a slash.
+            let mut alternative = false;
+            let mut after_placeholder = false;
+            for p in parts {
+                match p {
+                    Part::Keyword(k) => before = Some(term.str(*k)),
+                    Part::Placeholder(ph) => match out.last_mut() {
+                        Some((_, alternatives)) if alternative => alternatives.push(ph),
+                        _ => out.push((before.take(), vec![ph])),
+                    },
+                    Part::Extra(ps) => {
+                        collect(term, ps, out);
+                        before = None;
+                    }
+                    _ => {}
+                }
+                alternative = after_placeholder && matches!(p, Part::VariantSeparator { .. });
+                after_placeholder = matches!(p, Part::Placeholder(_));
+            }
+        }
+
+        let mut out = vec![];
+        collect(self, &self.parts, &mut out);
+        out
+    }
+
+    /// The parts with the contents of parentheses inlined.
+    fn flat_parts(&self) -> Vec<&Part> {
+        fn collect<'a>(parts: &'a [Part], out: &mut Vec<&'a Part>) {
+            for p in parts {
+                match p {
+                    Part::Extra(ps) => collect(ps, out),
+                    p => out.push(p),
+                }
+            }
+        }
+
+        let mut out = vec![];
+        collect(&self.parts, &mut out);
+        out
+    }
+
+    pub fn is_plural(&self) -> bool {
+        self.parts.iter().any(|p| {
+            matches!(p, Part::Annotation(AnnotationKind::Number, value) if self.str(*value) == "nur plural")
+        })
     }
 }
 
-impl Display for Term {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut parts = self.parts.clone();
+impl Term {
+    /// Formats the term back into dict.cc notation, with all parts in their original
+    /// order, so that parsing the result yields the same term again. Only the notation
+    /// is normalized, e.g. whitespace, quotes and "[+Akk.]" to "[Akk.]".
+    pub fn to_dictcc_string(&self) -> String {
+        format_parts_dictcc(&self.text, &self.parts)
+    }
+}
+
+/// How parentheses nested inside parentheses are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Nesting {
+    /// The same delimiters at every depth, as in "((bis) zu etw)".
+    #[default]
+    Parentheses,
+    /// Parentheses and brackets alternate with depth, as in "([bis] zu etw)".
+    Alternating,
+    /// Only the outermost parentheses are kept, as in "(bis zu etw)".
+    Flatten,
+}
+
+/// How terms with variants, like "mutwillige / böswillige Beschädigung", are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VariantStyle {
+    /// As dict.cc writes them, "mutwillige/böswillige Beschädigung".
+    #[default]
+    Joined,
+    /// Every variant written out, "mutwillige Beschädigung / böswillige Beschädigung".
+    Split,
+}
+
+impl TryFrom<&str> for VariantStyle {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "joined" => Ok(Self::Joined),
+            "split" => Ok(Self::Split),
+            _ => Err(anyhow!("unknown variant style \"{}\"", value)),
+        }
+    }
+}
+
+/// Options for [`Term::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+    pub nesting: Nesting,
+    pub case_style: CaseStyle,
+    pub placeholders: PlaceholderStyle,
+    pub variants: VariantStyle,
+}
+
+impl Nesting {
+    fn delimiters(self, depth: usize) -> (&'static str, &'static str) {
+        match self {
+            Self::Alternating if depth % 2 == 1 => ("[", "]"),
+            Self::Flatten if depth > 0 => ("", ""),
+            _ => ("(", ")"),
+        }
+    }
+}
+
+impl Term {
+    /// Renders the term like [`Display`] does, with the given treatment of nested
+    /// parentheses, placeholder cases and variants.
+    pub fn render(&self, options: RenderOptions) -> String {
+        if options.variants == VariantStyle::Split {
+            let variants = self.variants();
+            if variants.len() > 1 {
+                let options = RenderOptions {
+                    variants: VariantStyle::Joined,
+                    ..options
+                };
+                let rendered: Vec<_> = variants.iter().map(|v| v.render(options)).collect();
+                return rendered.join(" / ");
+            }
+        }
+
+        let mut parts = self.parts.to_vec();
         parts.sort_by(|a, b| {
             if matches!(a, Part::Gender(_)) {
                 Ordering::Less
@@ -47,51 +262,171 @@ impl Display for Term {
                 Ordering::Equal
             }
         });
-        f.write_str(&format_parts(&parts))
+        format_parts(&self.text, &parts, 0, options)
     }
-}
 
-impl std::cmp::PartialOrd for Term {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let keywords_self = self.parts.iter().filter(|p| matches!(p, Part::Keyword(_)));
-        let keywords_other = other.parts.iter().filter(|p| matches!(p, Part::Keyword(_)));
+    /// The alternative headwords of a term with variants: "mutwillige / böswillige
+    /// Beschädigung" stands for "mutwillige Beschädigung" and "böswillige Beschädigung".
+    /// Only spaced slashes between words separate variants, not those inside compounds as
+    /// in "und/oder" and "Ein/Aus-Anzeige", between placeholders as in "jdn./etw." or
+    /// inside parentheses. A term without variants is its only variant.
+    pub fn variants(&self) -> Vec<Cow<'_, Term>> {
+        let is_keyword = |i: usize| matches!(self.parts.get(i), Some(Part::Keyword(_)));
+        // The runs of words separated by slashes, as inclusive ranges of parts.
+        let mut runs: Vec<(usize, usize)> = vec![];
+        for (i, p) in self.parts.iter().enumerate() {
+            let separates_words = matches!(p, Part::VariantSeparator { spaced: true })
+                && i > 0
+                && is_keyword(i - 1)
+                && is_keyword(i + 1);
+            if !separates_words {
+                continue;
+            }
+            match runs.last_mut() {
+                Some(run) if run.1 == i - 1 => run.1 = i + 1,
+                _ => runs.push((i - 1, i + 1)),
+            }
+        }
+        if runs.is_empty() {
+            return vec![Cow::Borrowed(self)];
+        }
 
-        if let Some(res) = keywords_self.partial_cmp(keywords_other) {
-            if res != Ordering::Equal {
-                return Some(res);
+        let mut variants = vec![vec![]];
+        let mut at = 0;
+        for (start, end) in runs {
+            for v in &mut variants {
+                v.extend_from_slice(&self.parts[at..start]);
             }
+            variants = variants
+                .into_iter()
+                .flat_map(|v: Vec<Part>| {
+                    self.parts[start..=end].iter().step_by(2).map(move |alt| {
+                        let mut v = v.clone();
+                        v.push(alt.clone());
+                        v
+                    })
+                })
+                .collect();
+            at = end + 1;
+        }
+        for v in &mut variants {
+            v.extend_from_slice(&self.parts[at..]);
         }
 
-        let extra_self = self.parts.iter().filter(|p| matches!(p, Part::Extra(_)));
-        let extra_other = other.parts.iter().filter(|p| matches!(p, Part::Extra(_)));
+        variants
+            .into_iter()
+            .map(|parts| Cow::Owned(self.with_parts(&parts)))
+            .collect()
+    }
 
-        if let Some(res) = extra_self.partial_cmp(extra_other) {
-            if res != Ordering::Equal {
-                return Some(res);
-            }
+    /// The number of parenthesized groups in the term, including nested ones.
+    pub fn group_count(&self) -> usize {
+        fn count(parts: &[Part]) -> usize {
+            parts
+                .iter()
+                .map(|p| match p {
+                    Part::Extra(ps) => 1 + count(ps),
+                    _ => 0,
+                })
+                .sum()
+        }
+
+        count(&self.parts)
+    }
+
+    /// How deeply parentheses are nested in the term, 0 if there are none.
+    pub fn depth(&self) -> usize {
+        fn depth(parts: &[Part]) -> usize {
+            parts
+                .iter()
+                .map(|p| match p {
+                    Part::Extra(ps) => 1 + depth(ps),
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0)
+        }
+
+        depth(&self.parts)
+    }
+}
+
+impl fmt::Debug for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Term")
+            .field("parts", &Parts(&self.text, &self.parts))
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Term {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_dictcc_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Term {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Term::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The alternate form (`{:#}`) is [`Term::to_dictcc_string`], which keeps every part in
+/// its place, annotations included.
+impl Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return f.write_str(&self.to_dictcc_string());
         }
+        f.write_str(&self.render(RenderOptions::default()))
+    }
+}
 
-        None
+/// Terms sort like in a German dictionary: by their keywords, with umlauts next to the
+/// plain vowels. Between otherwise equal keywords, plain vowels come before umlauts and
+/// then lowercase before uppercase. The remaining ties, e.g. terms differing only in
+/// annotations, are broken by the dict.cc notation.
+impl Ord for Term {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lowercase = |t: &Self| t.keywords().map(str::to_lowercase).collect::<Vec<_>>();
+        self.keywords()
+            .map(util::collation_key)
+            .cmp(other.keywords().map(util::collation_key))
+            .then_with(|| lowercase(self).cmp(&lowercase(other)))
+            .then_with(|| other.keywords().cmp(self.keywords()))
+            .then_with(|| self.to_dictcc_string().cmp(&other.to_dictcc_string()))
     }
 }
 
-fn format_parts(parts: &[Part]) -> String {
+impl PartialOrd for Term {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn format_parts(text: &str, parts: &[Part], depth: usize, options: RenderOptions) -> String {
     let mut out = String::new();
 
     for p in parts.iter().filter_map(|p| match p {
-        Part::Keyword(k) => Some(k.clone()),
-        Part::Placeholder(ph) => Some(ph.to_string()),
-        Part::VariantSeparator => Some("/".to_string()),
+        Part::Keyword(k) => Some(text[k.range()].to_string()),
+        Part::Placeholder(ph) => Some(ph.render(options.case_style, options.placeholders)),
+        Part::VariantSeparator { .. } => Some("/".to_string()),
         Part::Gender(g) => Some(g.to_string()),
-        Part::Annotation(Annotation {
-            value,
-            kind: AnnotationKind::Number,
-        }) => Some(format!("[{}]", value)),
-        Part::Extra(ps) => Some("(".to_string() + &format_parts(ps) + ")"),
+        Part::Annotation(AnnotationKind::Number, value) => {
+            Some(format!("[{}]", &text[value.range()]))
+        }
+        Part::Quoted(q) => Some(format!("“{}”", &text[q.range()])),
+        Part::Extra(ps) => {
+            let (open, close) = options.nesting.delimiters(depth);
+            Some(open.to_string() + &format_parts(text, ps, depth + 1, options) + close)
+        }
         _ => None,
     }) {
         if !out.is_empty() && !out.ends_with("/") && p != "/" {
-            out.push_str(" ");
+            out.push(' ');
         }
 
         out.push_str(&p);
@@ -99,3 +434,291 @@ fn format_parts(parts: &[Part]) -> String {
 
     out
 }
+
+fn format_parts_dictcc(text: &str, parts: &[Part]) -> String {
+    let mut out = String::new();
+
+    for (i, p) in parts.iter().enumerate() {
+        let formatted = match p {
+            Part::Keyword(k) => text[k.range()].to_string(),
+            Part::Placeholder(ph) => ph.to_dictcc_string(),
+            Part::VariantSeparator { .. } => "/".to_string(),
+            Part::Gender(g) => match g {
+                Gender::Masculine => "{m}",
+                Gender::Feminine => "{f}",
+                Gender::Neutral => "{n}",
+            }
+            .to_string(),
+            Part::Annotation(kind, value) => {
+                let value = &text[value.range()];
+                match kind {
+                    AnnotationKind::Explanation => format!("[{}]", value),
+                    AnnotationKind::Alternative => format!("<{}>", value),
+                    AnnotationKind::Number if value == "nur plural" => "{pl}".to_string(),
+                    AnnotationKind::Number => "{sg}".to_string(),
+                    AnnotationKind::Curly => format!("{{{}}}", value),
+                }
+            }
+            Part::Quoted(q) => format!("“{}”", &text[q.range()]),
+            Part::Extra(ps) => "(".to_string() + &format_parts_dictcc(text, ps) + ")",
+        };
+
+        // Slashes are written as they were parsed, "und/oder" but "mutwillige / böswillige".
+        let is_tight_separator =
+            |j: usize| matches!(parts.get(j), Some(Part::VariantSeparator { spaced: false }));
+
+        if i > 0 && !is_tight_separator(i) && !is_tight_separator(i - 1) {
+            out.push(' ');
+        }
+
+        out.push_str(&formatted);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use super::{CaseStyle, Nesting, PlaceholderStyle, RenderOptions, Term, VariantStyle};
+
+    #[test]
+    fn to_dictcc_string() {
+        let data = [
+            (
+                "(an etw. [Dat.]) herumbasteln [ugs.]",
+                "(an etw. [Dat.]) herumbasteln [ugs.]",
+            ),
+            (
+                "sich [+Akk.] (auf jdn./etw.) aufstützen",
+                "sich [Akk.] (auf jdn./etw.) aufstützen",
+            ),
+            (
+                "mutwillige / böswillige Beschädigung {f}",
+                "mutwillige / böswillige Beschädigung {f}",
+            ),
+            ("Blattgemüse {pl.}", "Blattgemüse {pl}"),
+            (
+                "Aktion {f} „Brot für die Welt“",
+                "Aktion {f} “Brot für die Welt”",
+            ),
+            ("Filovirus {n} {ugs.: m}", "Filovirus {n} {ugs.: m}"),
+            ("jdm. etw. leihen", "jdm. etw. leihen"),
+            (
+                "sich [Akk.] ((bis) zu etw. [Dat.]) steigern",
+                "sich [Akk.] ((bis) zu etw. [Dat.]) steigern",
+            ),
+            (
+                "etw. nicht (mehr (länger)) mit ansehen können",
+                "etw. nicht (mehr (länger)) mit ansehen können",
+            ),
+            (
+                "vicanite-(Ce) [Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5]]",
+                "vicanite-(Ce) [Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5]]",
+            ),
+        ];
+
+        for (input, expected) in data {
+            let term = Term::parse(input).unwrap();
+            assert_eq!(term.to_dictcc_string(), expected);
+            assert_eq!(Term::parse(expected).unwrap(), term);
+        }
+    }
+
+    #[test]
+    fn alternate_display_round_trips() {
+        let lines = [
+            "Active Noise Control <ANC> {f} [Antischallsystem]\tactive noise control <ANC>",
+            "Austrian Standards Institute <ASI> {n} [vormals: Österreichisches Normungsinstitut <ON>]\tAustrian Standards Institute <ASI>",
+            "Filovirus {n} {ugs.: m} [z. B. Ebola]\tfilovirus [e.g. Ebola]",
+            "mithin {adv} [geh.] [somit, folglich]\tso {conj} [and for this reason; therefore]",
+            "Muskateller-Salbei / Muskatellersalbei {m} {auch: f}\tclary (sage) [Salvia sclarea]",
+            "jdn. (bei jdm.) verpetzen [ugs.]\tto sneak on sb. (to sb.) [Br.] [dated]",
+        ];
+
+        for column in lines.iter().flat_map(|l| l.split('\t')) {
+            let term = Term::parse(column).unwrap();
+            assert_eq!(format!("{:#}", term), column);
+        }
+        let term = Term::parse("Filovirus {n} {ugs.: m} [z. B. Ebola]").unwrap();
+        assert_eq!(term.to_string(), "das Filovirus");
+    }
+
+    #[test]
+    fn sorts_like_a_dictionary() {
+        let mut terms: Vec<_> = [
+            "Zebra {n}",
+            "Äpfel {pl}",
+            "Apfel {m} [ugs.]",
+            "Apfel {m}",
+            "apfel",
+            "Apfelbaum {m}",
+            "(auf) etw. achten",
+        ]
+        .into_iter()
+        .map(|t| Term::parse(t).unwrap())
+        .collect();
+        terms.sort();
+
+        let sorted: Vec<_> = terms.iter().map(|t| t.to_dictcc_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "(auf) etw. achten",
+                "apfel",
+                "Apfel {m}",
+                "Apfel {m} [ugs.]",
+                "Äpfel {pl}",
+                "Apfelbaum {m}",
+                "Zebra {n}",
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_keyword() {
+        let cases = [
+            ("(kleiner) Hund {m}", Some("Hund")),
+            ("jdm. etw. geben", Some("geben")),
+            ("to give sb. sth.", Some("give")),
+            ("sb.'s house", Some("house")),
+            ("to", Some("to")),
+            ("[ugs.] {m}", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Term::parse(input).unwrap().primary_keyword(), expected);
+        }
+    }
+
+    #[test]
+    fn render_nesting() {
+        let term = Term::parse("((bis) zu etw.) kommen").unwrap();
+        assert_eq!(term.depth(), 2);
+
+        let data = [
+            (Nesting::Parentheses, "((bis) zu etw) kommen"),
+            (Nesting::Alternating, "([bis] zu etw) kommen"),
+            (Nesting::Flatten, "(bis zu etw) kommen"),
+        ];
+
+        for (nesting, expected) in data {
+            let options = RenderOptions {
+                nesting,
+                ..Default::default()
+            };
+            assert_eq!(term.render(options), expected);
+        }
+        assert_eq!(term.to_string(), term.render(RenderOptions::default()));
+    }
+
+    #[test]
+    fn render_case_style() {
+        let term = Term::parse("jdm. etw. [Akk.] leihen").unwrap();
+
+        let data = [
+            (CaseStyle::Letter, "jdm etw(A) leihen"),
+            (CaseStyle::Short, "jdm. etw. [Akk.] leihen"),
+            (CaseStyle::Full, "jemandem (Dativ) etwas (Akkusativ) leihen"),
+        ];
+
+        for (case_style, expected) in data {
+            let options = RenderOptions {
+                case_style,
+                ..Default::default()
+            };
+            assert_eq!(term.render(options), expected);
+        }
+
+        let data = [
+            (CaseStyle::Letter, "jemandem etwas(A) leihen"),
+            (CaseStyle::Short, "jemandem etwas [Akk.] leihen"),
+            (CaseStyle::Full, "jemandem (Dativ) etwas (Akkusativ) leihen"),
+        ];
+
+        for (case_style, expected) in data {
+            let options = RenderOptions {
+                case_style,
+                placeholders: PlaceholderStyle::Expanded,
+                ..Default::default()
+            };
+            assert_eq!(term.render(options), expected);
+        }
+
+        let term = Term::parse("sich [Dat.] etw. merken").unwrap();
+        let options = RenderOptions {
+            placeholders: PlaceholderStyle::Expanded,
+            ..Default::default()
+        };
+        assert_eq!(term.render(options), "sich(D) etwas merken");
+    }
+
+    #[test]
+    fn variants() {
+        let data = [
+            (
+                "mutwillige / böswillige Beschädigung {f}",
+                &["mutwillige Beschädigung {f}", "böswillige Beschädigung {f}"][..],
+                "die mutwillige Beschädigung / die böswillige Beschädigung",
+            ),
+            (
+                "Muskateller-Salbei / Muskatellersalbei {m} {auch: f}",
+                &[
+                    "Muskateller-Salbei {m} {auch: f}",
+                    "Muskatellersalbei {m} {auch: f}",
+                ],
+                "der Muskateller-Salbei / der Muskatellersalbei",
+            ),
+            (
+                "sich [Akk.] (auf jdn./etw.) aufstützen",
+                &["sich [Akk.] (auf jdn./etw.) aufstützen"],
+                "sich(A) (auf jdn/etw) aufstützen",
+            ),
+            ("und/oder", &["und/oder"], "und/oder"),
+            (
+                "Ein/Aus-Anzeige {f}",
+                &["Ein/Aus-Anzeige {f}"],
+                "die Ein/Aus-Anzeige",
+            ),
+        ];
+
+        let split = RenderOptions {
+            variants: VariantStyle::Split,
+            ..Default::default()
+        };
+        for (input, variants, rendered) in data {
+            let term = Term::parse(input).unwrap();
+            let actual: Vec<_> = term
+                .variants()
+                .iter()
+                .map(|v| v.to_dictcc_string())
+                .collect();
+            assert_eq!(actual, variants);
+            assert_eq!(term.render(split), rendered);
+        }
+    }
+
+    #[test]
+    #[ignore = "parses the whole embedded dictionary"]
+    fn to_dictcc_string_round_trips_dictionary() {
+        let dict = include_bytes!("../dict.txt.zst");
+        let rd = BufReader::new(zstd::stream::read::Decoder::with_buffer(&dict[..]).unwrap());
+
+        for line in rd.lines() {
+            let line = line.unwrap();
+            for column in line.split('\t').take(2) {
+                let Ok(term) = Term::parse(column) else {
+                    continue;
+                };
+                let formatted = term.to_dictcc_string();
+                assert_eq!(
+                    Term::parse(&formatted).ok().as_ref(),
+                    Some(&term),
+                    "\"{}\" formatted as \"{}\"",
+                    column,
+                    formatted
+                );
+            }
+        }
+    }
+}
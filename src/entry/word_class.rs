@@ -0,0 +1,91 @@
+use std::fmt;
+
+/// The word class of an entry, as given in the third column of the dictionary.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WordClass {
+    Noun,
+    Verb,
+    Adjective,
+    Adverb,
+    PastParticiple,
+    PresentParticiple,
+    Preposition,
+    Pronoun,
+    Conjunction,
+    Prefix,
+    Suffix,
+    /// Rare classes like "archaic:adv" or "[none]", kept verbatim.
+    Other(String),
+}
+
+impl WordClass {
+    /// Parses the whitespace separated classes of a word class column.
+    pub fn parse_column(column: &str) -> Vec<Self> {
+        column.split_ascii_whitespace().map(Self::from).collect()
+    }
+
+    /// The abbreviation dict.cc uses for the class.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Noun => "noun",
+            Self::Verb => "verb",
+            Self::Adjective => "adj",
+            Self::Adverb => "adv",
+            Self::PastParticiple => "past-p",
+            Self::PresentParticiple => "pres-p",
+            Self::Preposition => "prep",
+            Self::Pronoun => "pron",
+            Self::Conjunction => "conj",
+            Self::Prefix => "prefix",
+            Self::Suffix => "suffix",
+            Self::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for WordClass {
+    fn from(value: &str) -> Self {
+        match value {
+            "noun" => Self::Noun,
+            "verb" => Self::Verb,
+            "adj" => Self::Adjective,
+            "adv" => Self::Adverb,
+            "past-p" => Self::PastParticiple,
+            "pres-p" => Self::PresentParticiple,
+            "prep" => Self::Preposition,
+            "pron" => Self::Pronoun,
+            "conj" => Self::Conjunction,
+            "prefix" => Self::Prefix,
+            "suffix" => Self::Suffix,
+            _ => Self::Other(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for WordClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WordClass;
+
+    #[test]
+    fn parse_column() {
+        let classes = WordClass::parse_column("adj archaic:adv past-p");
+        assert_eq!(
+            classes,
+            [
+                WordClass::Adjective,
+                WordClass::Other("archaic:adv".to_string()),
+                WordClass::PastParticiple
+            ]
+        );
+
+        let formatted: Vec<_> = classes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(formatted.join(" "), "adj archaic:adv past-p");
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+
+use crate::entry::{Subject, Term, WordClass};
+
+/// A condition on a raw dictionary line, used to build reduced dictionaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// The word class column contains the given class, e.g. "noun".
+    Class(WordClass),
+    /// The subject column contains the given tag, e.g. "comp." for "[comp.]".
+    Subject(Subject),
+    /// The German keywords are all among the `count` most frequent ones, e.g. "top=5000"
+    /// for the common vocabulary. The keywords are picked by [`Filter::rank`].
+    Top {
+        count: usize,
+        keywords: HashSet<String>,
+    },
+}
+
+impl Filter {
+    /// Checks the filter against the tab separated columns of a dictionary line.
+    pub fn matches(&self, line: &str) -> bool {
+        let mut columns = line.split('\t');
+        let german = columns.next().unwrap_or_default();
+        let mut columns = columns.skip(1);
+        let class = columns.next().unwrap_or_default();
+        let subjects = columns.next().unwrap_or_default();
+
+        match self {
+            Self::Class(c) => WordClass::parse_column(class).contains(c),
+            Self::Subject(s) => Subject::parse_column(subjects)
+                .iter()
+                .any(|v| v.matches(s.as_str())),
+            Self::Top { keywords, .. } => Term::parse(german).is_ok_and(|term| {
+                let mut words = term.keywords().peekable();
+                words.peek().is_some() && words.all(|w| keywords.contains(&w.to_lowercase()))
+            }),
+        }
+    }
+
+    /// Picks the most frequent keywords for [`Filter::Top`], given the lowercased German
+    /// keywords with the number of lines they occur in, as
+    /// [`Index::keywords`](crate::index::Index::keywords) lists them.
+    pub fn rank(&mut self, frequencies: &[(String, usize)]) {
+        let Self::Top { count, keywords } = self else {
+            return;
+        };
+
+        let mut ranked: Vec<_> = frequencies.iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        *keywords = ranked
+            .into_iter()
+            .take(*count)
+            .map(|(k, _)| k.clone())
+            .collect();
+    }
+}
+
+impl TryFrom<&str> for Filter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        let (key, arg) = value
+            .split_once('=')
+            .ok_or_else(|| anyhow!("filter \"{}\" is not of the form key=value", value))?;
+
+        match key {
+            "class" => Ok(Self::Class(arg.into())),
+            "subject" => Ok(Self::Subject(arg.into())),
+            "top" => Ok(Self::Top {
+                count: arg
+                    .parse()
+                    .map_err(|_| anyhow!("\"{}\" is not a number of words", arg))?,
+                keywords: HashSet::new(),
+            }),
+            _ => Err(anyhow!("unknown filter \"{}\"", key)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_match() {
+        let parse = |s| Filter::try_from(s).unwrap();
+        assert_eq!(parse("class=noun"), Filter::Class(WordClass::Noun));
+        assert_eq!(
+            parse("top=2"),
+            Filter::Top {
+                count: 2,
+                keywords: HashSet::new()
+            }
+        );
+        assert!(Filter::try_from("top=many").is_err());
+        assert!(Filter::try_from("top").is_err());
+        assert!(Filter::try_from("size=2").is_err());
+
+        let mut top = parse("top=2");
+        top.rank(&[
+            ("hund".to_string(), 5),
+            ("haus".to_string(), 9),
+            ("katze".to_string(), 3),
+            ("alt".to_string(), 5),
+        ]);
+        assert!(top.matches("Haus {n}\thouse\tnoun\t\n"));
+        assert!(top.matches("alt\told\tadj\t\n"));
+        assert!(!top.matches("Hund {m}\tdog\tnoun\t\n"));
+        assert!(!top.matches("altes Haus {n}\told house\tnoun\t\n"));
+    }
+}
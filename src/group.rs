@@ -0,0 +1,272 @@
+//! Telling apart results which look the same in the terse output.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    entry::{AnnotationKind, Entry, RenderOptions, Term, WordClass},
+    query::Language,
+};
+
+/// For every entry, the annotations and subject tags that distinguish it from the other
+/// entries that are displayed identically, e.g. "[Br.]" and "[Am.]" for two entries
+/// translating to "lift". Entries without such near-duplicates get no tags.
+pub fn differences<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Vec<Vec<String>> {
+    let entries: Vec<_> = entries.into_iter().collect();
+    let texts: Vec<_> = entries.iter().map(|e| e.to_string()).collect();
+
+    let mut groups = HashMap::<&str, Vec<usize>>::new();
+    for (i, text) in texts.iter().enumerate() {
+        groups.entry(text).or_default().push(i);
+    }
+
+    let tags: Vec<_> = entries.iter().map(|e| tags(e)).collect();
+
+    texts
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let group = &groups[text.as_str()];
+            if group.len() < 2 {
+                return vec![];
+            }
+
+            tags[i]
+                .iter()
+                .filter(|tag| !group.iter().all(|&j| tags[j].contains(tag)))
+                .cloned()
+                .collect()
+        })
+        .collect()
+}
+
+/// The translations of two words side by side, telling near-synonyms apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comparison {
+    pub shared: Vec<String>,
+    pub only_first: Vec<String>,
+    pub only_second: Vec<String>,
+}
+
+/// Compares the English translations of two sets of entries. Translations count as
+/// the same if their words are, so "to use [sth.]" and "to use" are shared.
+pub fn compare(first: &[Entry], second: &[Entry]) -> Comparison {
+    let key = |e: &Entry| {
+        e.english
+            .keywords()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let first_keys: HashSet<_> = first.iter().map(key).collect();
+    let second_keys: HashSet<_> = second.iter().map(key).collect();
+
+    let mut comparison = Comparison::default();
+    let mut seen = HashSet::new();
+    for entry in first.iter().chain(second) {
+        let k = key(entry);
+        if !seen.insert(k.clone()) {
+            continue;
+        }
+
+        let list = match (first_keys.contains(&k), second_keys.contains(&k)) {
+            (true, true) => &mut comparison.shared,
+            (true, false) => &mut comparison.only_first,
+            _ => &mut comparison.only_second,
+        };
+        list.push(entry.english.to_string());
+    }
+
+    comparison
+}
+
+/// Collapses the terms which are rendered the same, differing only in annotations, into
+/// one, followed by the union of their annotations, e.g. "stark [ugs.], [fig.]" for
+/// "stark [ugs.]" and "stark [fig.]". Terms without such near-duplicates are rendered
+/// as they are. The result is in the order the terms first appear in.
+pub fn collapse<'a>(
+    terms: impl IntoIterator<Item = &'a Term>,
+    render: RenderOptions,
+) -> Vec<String> {
+    let mut groups: Vec<(String, Vec<&Term>)> = vec![];
+    for term in terms {
+        let text = term.render(render);
+        match groups.iter_mut().find(|(t, _)| *t == text) {
+            Some((_, group)) => group.push(term),
+            None => groups.push((text, vec![term])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(text, group)| {
+            if group.len() < 2 {
+                return text;
+            }
+
+            // The annotations the rendering leaves out.
+            let mut qualifiers: Vec<String> = vec![];
+            for annotation in group.iter().flat_map(|t| t.annotations()) {
+                let qualifier = annotation.to_string();
+                if annotation.kind != AnnotationKind::Number && !qualifiers.contains(&qualifier) {
+                    qualifiers.push(qualifier);
+                }
+            }
+            match qualifiers.is_empty() {
+                true => text,
+                false => format!("{} {}", text, qualifiers.join(", ")),
+            }
+        })
+        .collect()
+}
+
+/// The results of a word class, summarizing results too many to list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSummary {
+    /// The first word class of the results, if they have one.
+    pub class: Option<WordClass>,
+    pub count: usize,
+    /// The first few translations, collapsed as by [`collapse`].
+    pub translations: Vec<String>,
+}
+
+/// Groups the results of a lookup in the given language by their first word class, in
+/// the order the classes first appear in, with up to `translations` translations each.
+pub fn summarize<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+    language: Language,
+    render: RenderOptions,
+    translations: usize,
+) -> Vec<ClassSummary> {
+    let mut groups: Vec<(Option<&WordClass>, Vec<&Term>)> = vec![];
+    for entry in entries {
+        let class = entry.word_classes.first();
+        let translation = match language {
+            Language::German => &entry.english,
+            Language::English => &entry.german,
+        };
+        match groups.iter_mut().find(|(c, _)| *c == class) {
+            Some((_, terms)) => terms.push(translation),
+            None => groups.push((class, vec![translation])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(class, terms)| ClassSummary {
+            class: class.cloned(),
+            count: terms.len(),
+            translations: collapse(terms, render)
+                .into_iter()
+                .take(translations)
+                .collect(),
+        })
+        .collect()
+}
+
+/// The annotations of both terms and the subject tags, as displayed.
+fn tags(entry: &Entry) -> Vec<String> {
+    [&entry.german, &entry.english]
+        .into_iter()
+        .flat_map(|t| t.annotations())
+        .map(|a| a.to_string())
+        .chain(entry.subjects.iter().map(|s| s.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collapse, compare, differences, summarize};
+    use crate::{
+        entry::{RenderOptions, Term, WordClass},
+        query::Language,
+        Dictionary,
+    };
+
+    #[test]
+    fn differences_of_near_duplicates() {
+        let dict = Dictionary::load(
+            "Aufzug {m}\tlift [Br.]\tnoun\t[tech.]\n\
+             Aufzug {m}\televator [Am.]\tnoun\t[tech.]\n\
+             Aufzug {m}\tlift [Am.]\tnoun\t[tech.]\n\
+             Aufzug {m}\tact\tnoun\t[theatre]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let differences = differences(dict.entries());
+        assert_eq!(differences, [vec!["[Br.]"], vec![], vec!["[Am.]"], vec![]]);
+    }
+
+    #[test]
+    fn compare_translations() {
+        let dict = Dictionary::load(
+            "benutzen\tto use [sth.]\tverb\n\
+             benutzen\tto make use of\tverb\n\
+             verwenden\tto use\tverb\n\
+             verwenden\tto employ\tverb\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let (first, second) = dict.entries().split_at(2);
+
+        let comparison = compare(first, second);
+        assert_eq!(comparison.shared, ["to use"]);
+        assert_eq!(comparison.only_first, ["to make use of"]);
+        assert_eq!(comparison.only_second, ["to employ"]);
+    }
+
+    #[test]
+    fn collapse_near_duplicates() {
+        let terms: Vec<_> = [
+            "stark [ugs.]",
+            "kräftig",
+            "stark [fig.]",
+            "stark",
+            "Hund {m}",
+        ]
+        .into_iter()
+        .map(|t| Term::parse(t).unwrap())
+        .collect();
+
+        assert_eq!(
+            collapse(&terms, RenderOptions::default()),
+            ["stark [ugs.], [fig.]", "kräftig", "der Hund"]
+        );
+    }
+
+    #[test]
+    fn summarize_by_class() {
+        let dict = Dictionary::load(
+            "laufen\tto run\tverb\t\n\
+             Laufen {n}\trunning\tnoun\t\n\
+             laufen\tto walk\tverb\t\n\
+             laufen\tto run [fig.]\tverb\t\n\
+             laufen\tto go\t\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let summary = summarize(
+            dict.entries(),
+            Language::German,
+            RenderOptions::default(),
+            2,
+        );
+        let summary: Vec<_> = summary
+            .iter()
+            .map(|s| (s.class.clone(), s.count, s.translations.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    Some(WordClass::Verb),
+                    3,
+                    vec!["to run [fig.]".to_string(), "to walk".to_string()]
+                ),
+                (Some(WordClass::Noun), 1, vec!["running".to_string()]),
+                (None, 1, vec!["to go".to_string()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use fst::{Map, MapBuilder, Streamer};
+
+use crate::{
+    dictionary,
+    entry::{Origin, SourceRef, Term},
+    query::Language,
+    util,
+};
+
+const VERSION: u32 = 4;
+
+const TEXT_FILE: &str = "dict.txt";
+const OFFSETS_FILE: &str = "offsets.bin";
+const GERMAN_NAME: &str = "german";
+const ENGLISH_NAME: &str = "english";
+
+/// A build whose temporary directory wasn't touched for this long is taken for one
+/// which was interrupted.
+const BUILD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// A persistent keyword index over a dictionary.
+///
+/// The index directory holds the decompressed dictionary text, a table with the byte
+/// offset of every line and, per language, an FST mapping each lowercased keyword of
+/// that language's column to the numbers of the lines it occurs in. Both keyword FSTs
+/// share the text and the offset table.
+pub struct Index {
+    text: BufReader<fs::File>,
+    offsets: Vec<u8>,
+    german: Keywords,
+    english: Keywords,
+}
+
+/// The keywords of one language's column.
+struct Keywords {
+    /// Maps each keyword to the position of its line numbers in `lines`.
+    map: Map<Vec<u8>>,
+    /// For every keyword the number of lines it occurs in followed by the line numbers,
+    /// all as little endian `u32`s.
+    lines: Vec<u8>,
+}
+
+/// A dictionary line found through the index.
+#[derive(Debug, Clone)]
+pub struct IndexedLine {
+    pub source: SourceRef,
+    pub text: String,
+}
+
+/// Why a dictionary has no usable index, in which case it has to be scanned.
+#[derive(thiserror::Error, Debug)]
+pub enum Unavailable {
+    #[error("there is no dictionary index yet")]
+    Missing,
+    #[error("the dictionary index was built by an older version")]
+    Outdated,
+    #[error("the dictionary index is damaged ({0})")]
+    Damaged(io::Error),
+}
+
+/// Identifies the dictionary contents, so that indexes of other dictionaries or older
+/// versions of the same one are never used.
+pub fn fingerprint(data: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET, data)
+}
+
+/// The initial state of an FNV-1a hash.
+pub(crate) const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// Continues an FNV-1a hash with more data.
+pub(crate) fn fnv1a(hash: u64, data: &[u8]) -> u64 {
+    data.iter().fold(hash, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The directory inside `cache_dir` the index for the given dictionary lives in.
+pub fn index_dir(cache_dir: &Path, fingerprint: u64) -> PathBuf {
+    cache_dir.join(format!("index-v{}-{:016x}", VERSION, fingerprint))
+}
+
+/// The indexes of the given dictionary built by older versions, which can't be used.
+pub fn outdated_dirs(cache_dir: &Path, fingerprint: u64) -> Vec<PathBuf> {
+    let suffix = format!("-{:016x}", fingerprint);
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let version = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("index-v"))
+                .and_then(|n| n.strip_suffix(&suffix))
+                .and_then(|v| v.parse::<u32>().ok());
+            version.is_some_and(|v| v < VERSION)
+        })
+        .map(|e| e.path())
+        .collect()
+}
+
+/// Whether another process is building the index in `dir` right now.
+pub fn is_building(dir: &Path) -> bool {
+    fs::metadata(dir.with_extension("tmp"))
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age < BUILD_TIMEOUT)
+        })
+}
+
+/// The lowercased keywords of the given language with the number of lines they occur
+/// in, like [`Index::keywords`] lists them, for when there is no index.
+pub fn count_keywords(
+    mut rd: impl BufRead,
+    language: Language,
+) -> io::Result<Vec<(String, usize)>> {
+    let column = match language {
+        Language::German => 0,
+        Language::English => 1,
+    };
+    let mut counts = HashMap::<String, usize>::new();
+    let mut buf = String::with_capacity(512);
+    let mut seen = HashSet::new();
+
+    loop {
+        buf.clear();
+        if rd.read_line(&mut buf)? == 0 {
+            break;
+        }
+        if buf.starts_with('#') {
+            continue;
+        }
+        let Some(Ok(term)) = buf.split('\t').nth(column).map(Term::parse) else {
+            continue;
+        };
+
+        seen.clear();
+        for keyword in term.keywords() {
+            let keyword = keyword.to_lowercase();
+            if seen.insert(keyword.clone()) {
+                *counts.entry(keyword).or_default() += 1;
+            }
+        }
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_unstable();
+    Ok(counts)
+}
+
+impl Index {
+    /// Opens a previously built index. Returns `None` if there is none in `dir`.
+    pub fn open(dir: &Path) -> io::Result<Option<Self>> {
+        if !dir.exists() {
+            return Ok(None);
+        }
+
+        let text = fs::File::open(dir.join(TEXT_FILE))?;
+        let offsets = fs::read(dir.join(OFFSETS_FILE))?;
+        let german = Keywords::open(dir, GERMAN_NAME)?;
+        let english = Keywords::open(dir, ENGLISH_NAME)?;
+
+        // Catch truncated files, which would make lookups fail later on.
+        let last_offset = offsets
+            .rchunks(8)
+            .next()
+            .and_then(|b| b.try_into().ok())
+            .map_or(0, u64::from_le_bytes);
+        if offsets.len() % 8 != 0 || last_offset > text.metadata()?.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt index"));
+        }
+
+        Ok(Some(Self {
+            text: BufReader::new(text),
+            offsets,
+            german,
+            english,
+        }))
+    }
+
+    /// Builds the index for the dictionary read from `rd` into `dir`. The files are
+    /// written to a temporary directory first, so an interrupted build leaves no index.
+    pub fn build(dir: &Path, mut rd: impl BufRead) -> io::Result<Self> {
+        let tmp = dir.with_extension("tmp");
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        fs::create_dir_all(&tmp)?;
+
+        let mut text = BufWriter::new(fs::File::create(tmp.join(TEXT_FILE))?);
+        let mut offsets = BufWriter::new(fs::File::create(tmp.join(OFFSETS_FILE))?);
+        let mut german = HashMap::<String, Vec<usize>>::new();
+        let mut english = HashMap::<String, Vec<usize>>::new();
+
+        let mut buf = String::with_capacity(512);
+        let mut offset = 0;
+        let mut line = 0;
+
+        loop {
+            buf.clear();
+            let n = rd.read_line(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+
+            line += 1;
+            text.write_all(buf.as_bytes())?;
+            offsets.write_all(&(offset as u64).to_le_bytes())?;
+            offset += n;
+
+            if buf.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = buf.split('\t');
+            for keywords in [&mut german, &mut english] {
+                let Some(Ok(term)) = columns.next().map(Term::parse) else {
+                    break;
+                };
+
+                for keyword in term.keywords() {
+                    let lines = keywords.entry(keyword.to_lowercase()).or_default();
+                    if lines.last() != Some(&line) {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+
+        text.flush()?;
+        offsets.flush()?;
+        Keywords::write(&tmp, GERMAN_NAME, german)?;
+        Keywords::write(&tmp, ENGLISH_NAME, english)?;
+
+        fs::rename(&tmp, dir)?;
+
+        Self::open(dir)?.ok_or_else(|| io::Error::other("index vanished after build"))
+    }
+
+    fn keywords_of(&self, language: Language) -> &Keywords {
+        match language {
+            Language::German => &self.german,
+            Language::English => &self.english,
+        }
+    }
+
+    /// The number of dictionary lines the keyword occurs in, in the column of the given
+    /// language, ignoring case.
+    pub fn frequency(&self, keyword: &str, language: Language) -> usize {
+        self.lines(keyword, language).len()
+    }
+
+    /// All lowercased keywords of the given language with the number of lines they occur
+    /// in, in byte order.
+    pub fn keywords(&self, language: Language) -> Vec<(String, usize)> {
+        let keywords = self.keywords_of(language);
+        let mut stream = keywords.map.stream();
+        let mut all = Vec::with_capacity(keywords.map.len());
+        while let Some((keyword, at)) = stream.next() {
+            if let Ok(keyword) = std::str::from_utf8(keyword) {
+                all.push((keyword.to_string(), keywords.count_at(at)));
+            }
+        }
+        all
+    }
+
+    /// Returns the dictionary lines which contain the given keyword in the column of the
+    /// given language, ignoring case.
+    pub fn lookup(&mut self, keyword: &str, language: Language) -> io::Result<Vec<IndexedLine>> {
+        self.lines(keyword, language)
+            .into_iter()
+            .map(|line| self.read_line(line))
+            .collect()
+    }
+
+    /// The lines which may match the word or phrase, looked up through its rarest word:
+    /// every line matching a phrase contains it.
+    pub fn candidates(&mut self, word: &str, language: Language) -> io::Result<Vec<IndexedLine>> {
+        let rarest = dictionary::query_components(word)
+            .into_iter()
+            .min_by_key(|c| self.frequency(c, language));
+        match rarest {
+            Some(rarest) => self.lookup(rarest, language),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// The numbers of the lines the keyword occurs in. The FSTs are keyed by the
+    /// lowercased keywords, so if diacritics are ignored all keywords have to be
+    /// compared instead.
+    fn lines(&self, keyword: &str, language: Language) -> Vec<usize> {
+        let keywords = self.keywords_of(language);
+
+        if !util::ignores_diacritics() {
+            return keywords
+                .map
+                .get(keyword.to_lowercase())
+                .map(|at| keywords.lines_at(at))
+                .unwrap_or_default();
+        }
+
+        let mut lines = vec![];
+        let mut stream = keywords.map.stream();
+        while let Some((k, at)) = stream.next() {
+            if std::str::from_utf8(k).is_ok_and(|k| util::case_fold_eq(k, keyword)) {
+                lines.extend(keywords.lines_at(at));
+            }
+        }
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+
+    fn read_line(&mut self, line: usize) -> io::Result<IndexedLine> {
+        let at = (line - 1) * 8;
+        let offset = self
+            .offsets
+            .get(at..at + 8)
+            .and_then(|b| b.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt index"))?;
+
+        self.text.seek(SeekFrom::Start(offset))?;
+        let mut text = String::new();
+        self.text.read_line(&mut text)?;
+
+        Ok(IndexedLine {
+            source: SourceRef {
+                line,
+                offset: offset as usize,
+                origin: Origin::Main,
+            },
+            text,
+        })
+    }
+}
+
+impl Keywords {
+    fn open(dir: &Path, name: &str) -> io::Result<Self> {
+        let map = Map::new(fs::read(dir.join(name).with_extension("fst"))?).map_err(fst_error)?;
+        let lines = fs::read(dir.join(name).with_extension("lines"))?;
+        if lines.len() % 4 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt index"));
+        }
+
+        Ok(Self { map, lines })
+    }
+
+    fn write(dir: &Path, name: &str, keywords: HashMap<String, Vec<usize>>) -> io::Result<()> {
+        let mut keywords: Vec<_> = keywords.into_iter().collect();
+        keywords.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let file = fs::File::create(dir.join(name).with_extension("fst"))?;
+        let mut map = MapBuilder::new(BufWriter::new(file)).map_err(fst_error)?;
+        let mut wr = BufWriter::new(fs::File::create(dir.join(name).with_extension("lines"))?);
+        let mut at = 0;
+        for (keyword, lines) in keywords {
+            map.insert(&keyword, at).map_err(fst_error)?;
+            for n in std::iter::once(lines.len()).chain(lines) {
+                let n = u32::try_from(n).map_err(io::Error::other)?;
+                wr.write_all(&n.to_le_bytes())?;
+                at += 4;
+            }
+        }
+
+        map.into_inner().map_err(fst_error)?.flush()?;
+        wr.flush()
+    }
+
+    fn u32_at(&self, at: usize) -> Option<usize> {
+        let bytes = self.lines.get(at..at + 4)?.try_into().ok()?;
+        Some(u32::from_le_bytes(bytes) as usize)
+    }
+
+    fn count_at(&self, at: u64) -> usize {
+        self.u32_at(at as usize).unwrap_or(0)
+    }
+
+    fn lines_at(&self, at: u64) -> Vec<usize> {
+        let at = at as usize;
+        let count = self.u32_at(at).unwrap_or(0);
+        (1..=count).map_while(|i| self.u32_at(at + 4 * i)).collect()
+    }
+}
+
+fn fst_error(err: fst::Error) -> io::Error {
+    match err {
+        fst::Error::Io(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidData, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_and_lookup() {
+        let dir = std::env::temp_dir().join(format!("index-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let text =
+            "# dict.cc export\nHund {m}\tdog\tnoun\nhündisch\tdoglike\tadj\nKatze {f}\tcat\tnoun\n";
+        let mut index = Index::build(&dir, text.as_bytes()).unwrap();
+
+        let lines = index.lookup("HUND", Language::German).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].source.line, 2);
+        assert_eq!(lines[0].text, "Hund {m}\tdog\tnoun\n");
+        assert_eq!(index.frequency("cat", Language::English), 1);
+        assert_eq!(index.frequency("cat", Language::German), 0);
+        assert_eq!(
+            index.keywords(Language::English),
+            [
+                ("cat".to_string(), 1),
+                ("dog".to_string(), 1),
+                ("doglike".to_string(), 1)
+            ]
+        );
+
+        let mut reopened = Index::open(&dir).unwrap().unwrap();
+        assert_eq!(
+            reopened.lookup("hündisch", Language::German).unwrap()[0]
+                .source
+                .line,
+            3
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
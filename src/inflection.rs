@@ -0,0 +1,423 @@
+//! Finding the dictionary form of inflected German words. dict.cc mostly has lemmas,
+//! the infinitives of verbs and the nominative singular of nouns and adjectives, so
+//! many forms like "größten" or "aufgemacht" find nothing by themselves.
+//!
+//! Strong and irregular verbs are looked up in a table, everything else has its endings
+//! stripped by rule. This yields candidates, only some of which are actual words; the
+//! dictionary decides which.
+
+use crate::{
+    entry::{Entry, WordClass},
+    util,
+};
+
+/// Strong and mixed verbs: the infinitive, the present stem of the second and third
+/// person singular if its vowel changes, the past stem and the past participle.
+const STRONG_VERBS: &[(&str, &str, &str, &str)] = &[
+    ("beginnen", "", "begann", "begonnen"),
+    ("beißen", "", "biss", "gebissen"),
+    ("bieten", "", "bot", "geboten"),
+    ("binden", "", "band", "gebunden"),
+    ("bitten", "", "bat", "gebeten"),
+    ("blasen", "bläs", "blies", "geblasen"),
+    ("bleiben", "", "blieb", "geblieben"),
+    ("brechen", "brich", "brach", "gebrochen"),
+    ("brennen", "", "brannte", "gebrannt"),
+    ("bringen", "", "brachte", "gebracht"),
+    ("denken", "", "dachte", "gedacht"),
+    ("empfehlen", "empfiehl", "empfahl", "empfohlen"),
+    ("essen", "iss", "aß", "gegessen"),
+    ("fahren", "fähr", "fuhr", "gefahren"),
+    ("fallen", "fäll", "fiel", "gefallen"),
+    ("fangen", "fäng", "fing", "gefangen"),
+    ("finden", "", "fand", "gefunden"),
+    ("fliegen", "", "flog", "geflogen"),
+    ("fliehen", "", "floh", "geflohen"),
+    ("fließen", "", "floss", "geflossen"),
+    ("fressen", "friss", "fraß", "gefressen"),
+    ("frieren", "", "fror", "gefroren"),
+    ("geben", "gib", "gab", "gegeben"),
+    ("gehen", "", "ging", "gegangen"),
+    ("gelingen", "", "gelang", "gelungen"),
+    ("gelten", "gilt", "galt", "gegolten"),
+    ("genießen", "", "genoss", "genossen"),
+    ("geschehen", "geschieh", "geschah", "geschehen"),
+    ("gewinnen", "", "gewann", "gewonnen"),
+    ("gießen", "", "goss", "gegossen"),
+    ("gleichen", "", "glich", "geglichen"),
+    ("graben", "gräb", "grub", "gegraben"),
+    ("greifen", "", "griff", "gegriffen"),
+    ("halten", "hält", "hielt", "gehalten"),
+    ("hängen", "", "hing", "gehangen"),
+    ("heben", "", "hob", "gehoben"),
+    ("heißen", "", "hieß", "geheißen"),
+    ("helfen", "hilf", "half", "geholfen"),
+    ("kennen", "", "kannte", "gekannt"),
+    ("klingen", "", "klang", "geklungen"),
+    ("kommen", "", "kam", "gekommen"),
+    ("laden", "läd", "lud", "geladen"),
+    ("lassen", "läss", "ließ", "gelassen"),
+    ("laufen", "läuf", "lief", "gelaufen"),
+    ("leiden", "", "litt", "gelitten"),
+    ("leihen", "", "lieh", "geliehen"),
+    ("lesen", "lies", "las", "gelesen"),
+    ("liegen", "", "lag", "gelegen"),
+    ("lügen", "", "log", "gelogen"),
+    ("meiden", "", "mied", "gemieden"),
+    ("messen", "miss", "maß", "gemessen"),
+    ("nehmen", "nimm", "nahm", "genommen"),
+    ("nennen", "", "nannte", "genannt"),
+    ("pfeifen", "", "pfiff", "gepfiffen"),
+    ("raten", "rät", "riet", "geraten"),
+    ("reißen", "", "riss", "gerissen"),
+    ("reiten", "", "ritt", "geritten"),
+    ("rennen", "", "rannte", "gerannt"),
+    ("riechen", "", "roch", "gerochen"),
+    ("rufen", "", "rief", "gerufen"),
+    ("scheiden", "", "schied", "geschieden"),
+    ("scheinen", "", "schien", "geschienen"),
+    ("schieben", "", "schob", "geschoben"),
+    ("schießen", "", "schoss", "geschossen"),
+    ("schlafen", "schläf", "schlief", "geschlafen"),
+    ("schlagen", "schläg", "schlug", "geschlagen"),
+    ("schließen", "", "schloss", "geschlossen"),
+    ("schneiden", "", "schnitt", "geschnitten"),
+    ("schreiben", "", "schrieb", "geschrieben"),
+    ("schreien", "", "schrie", "geschrien"),
+    ("schweigen", "", "schwieg", "geschwiegen"),
+    ("schwimmen", "", "schwamm", "geschwommen"),
+    ("sehen", "sieh", "sah", "gesehen"),
+    ("singen", "", "sang", "gesungen"),
+    ("sinken", "", "sank", "gesunken"),
+    ("sitzen", "", "saß", "gesessen"),
+    ("sprechen", "sprich", "sprach", "gesprochen"),
+    ("springen", "", "sprang", "gesprungen"),
+    ("stechen", "stich", "stach", "gestochen"),
+    ("stehen", "", "stand", "gestanden"),
+    ("stehlen", "stiehl", "stahl", "gestohlen"),
+    ("steigen", "", "stieg", "gestiegen"),
+    ("sterben", "stirb", "starb", "gestorben"),
+    ("stoßen", "stöß", "stieß", "gestoßen"),
+    ("streiten", "", "stritt", "gestritten"),
+    ("tragen", "träg", "trug", "getragen"),
+    ("treffen", "triff", "traf", "getroffen"),
+    ("treiben", "", "trieb", "getrieben"),
+    ("treten", "tritt", "trat", "getreten"),
+    ("trinken", "", "trank", "getrunken"),
+    ("vergessen", "vergiss", "vergaß", "vergessen"),
+    ("verlieren", "", "verlor", "verloren"),
+    ("wachsen", "wächs", "wuchs", "gewachsen"),
+    ("waschen", "wäsch", "wusch", "gewaschen"),
+    ("weisen", "", "wies", "gewiesen"),
+    ("werfen", "wirf", "warf", "geworfen"),
+    ("wiegen", "", "wog", "gewogen"),
+    ("ziehen", "", "zog", "gezogen"),
+    ("zwingen", "", "zwang", "gezwungen"),
+];
+
+/// Verbs too irregular for [`STRONG_VERBS`], with all their inflected forms.
+const IRREGULAR_VERBS: &[(&str, &[&str])] = &[
+    (
+        "sein",
+        &[
+            "bin", "bist", "ist", "sind", "seid", "war", "warst", "waren", "wart", "gewesen",
+            "sei", "seien", "wäre", "wären",
+        ],
+    ),
+    (
+        "haben",
+        &[
+            "habe", "hast", "hat", "habt", "hatte", "hattest", "hatten", "hattet", "gehabt",
+            "hätte", "hätten",
+        ],
+    ),
+    (
+        "werden",
+        &[
+            "werde", "wirst", "wird", "werdet", "wurde", "wurdest", "wurden", "wurdet", "geworden",
+            "würde", "würden",
+        ],
+    ),
+    (
+        "wissen",
+        &["weiß", "weißt", "wisst", "wusste", "wussten", "gewusst"],
+    ),
+    (
+        "können",
+        &[
+            "kann", "kannst", "könnt", "konnte", "konnten", "gekonnt", "könnte", "könnten",
+        ],
+    ),
+    (
+        "müssen",
+        &[
+            "muss", "musst", "müsst", "musste", "mussten", "gemusst", "müsste",
+        ],
+    ),
+    (
+        "wollen",
+        &["will", "willst", "wollt", "wollte", "wollten", "gewollt"],
+    ),
+    (
+        "dürfen",
+        &[
+            "darf", "darfst", "dürft", "durfte", "durften", "gedurft", "dürfte",
+        ],
+    ),
+    (
+        "sollen",
+        &["soll", "sollst", "sollt", "sollte", "sollten", "gesollt"],
+    ),
+    (
+        "mögen",
+        &[
+            "mag", "magst", "mögt", "mochte", "mochten", "gemocht", "möchte", "möchten",
+        ],
+    ),
+    (
+        "tun",
+        &["tue", "tust", "tut", "tat", "tatst", "taten", "getan"],
+    ),
+];
+
+/// Prefixes which are split off verbs in main clauses, "kommt an" for "ankommen".
+const SEPARABLE_PREFIXES: &[&str] = &[
+    "ab", "an", "auf", "aus", "bei", "durch", "ein", "fest", "fort", "her", "hin", "los", "mit",
+    "nach", "um", "vor", "weg", "weiter", "wieder", "zu", "zurück", "zusammen",
+];
+
+/// Prefixes which stay on verbs and replace the "ge" of their participles, "verstanden"
+/// for "verstehen".
+const INSEPARABLE_PREFIXES: &[&str] = &["be", "emp", "ent", "er", "ge", "miss", "ver", "zer"];
+
+/// The endings stripped from adjectives and nouns, longest first among those sharing a
+/// tail: superlatives, comparatives, then declension and plural endings.
+const ENDINGS: &[&str] = &[
+    "esten", "ester", "estem", "estes", "este", "sten", "ster", "stem", "stes", "ste", "eren",
+    "erer", "erem", "eres", "ere", "en", "em", "es", "er", "e", "n", "s",
+];
+
+/// A candidate dictionary form of an inflected word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lemma {
+    pub word: String,
+    /// Whether the word is the infinitive of a verb, or else a noun or adjective.
+    pub verb: bool,
+}
+
+impl Lemma {
+    /// Whether the entry is of the kind of word the lemma is, or has no word class to
+    /// tell. This keeps "größten" from finding the plural "Größen" as a verb.
+    pub fn fits(&self, entry: &Entry) -> bool {
+        entry.word_classes.is_empty()
+            || entry.word_classes.iter().any(|class| match class {
+                WordClass::Verb => self.verb,
+                WordClass::Noun | WordClass::Adjective | WordClass::Adverb => !self.verb,
+                _ => false,
+            })
+    }
+}
+
+/// The candidate lemmas of an inflected German word or separable verb like "fängt an",
+/// most likely first. The word itself isn't among them.
+pub fn lemmas(word: &str) -> Vec<Lemma> {
+    let verb = |word| Lemma { word, verb: true };
+    let mut lemmas = vec![];
+    match word.split_whitespace().collect::<Vec<_>>()[..] {
+        [word] => {
+            lemmas.extend(verb_lemmas(&word.to_lowercase()).into_iter().map(verb));
+            // The endings are lowercase, so "HÄUSERN" is taken as "häusern" too.
+            lemmas.extend(
+                util::casing_variants(word)
+                    .iter()
+                    .flat_map(|word| nominal_lemmas(word))
+                    .map(|word| Lemma { word, verb: false }),
+            );
+        }
+        // A separated prefix goes back to the front.
+        [word, prefix] if SEPARABLE_PREFIXES.contains(&prefix.to_lowercase().as_str()) => {
+            let prefix = prefix.to_lowercase();
+            for lemma in verb_lemmas(&word.to_lowercase()) {
+                lemmas.push(verb(format!("{}{}", prefix, lemma)));
+            }
+        }
+        _ => {}
+    }
+
+    let mut seen = vec![word.to_lowercase()];
+    lemmas.retain(|lemma| {
+        let lower = lemma.word.to_lowercase();
+        let new = lemma.word.chars().count() > 2 && !seen.contains(&lower);
+        seen.push(lower);
+        new
+    });
+    lemmas
+}
+
+/// The infinitives the lowercase word may be a form of, those of strong and irregular
+/// verbs first.
+fn verb_lemmas(word: &str) -> Vec<String> {
+    let mut lemmas = vec![];
+
+    for (prefix, rest) in prefix_splits(word) {
+        for (infinitive, forms) in IRREGULAR_VERBS {
+            if forms.contains(&rest) {
+                lemmas.push(format!("{}{}", prefix, infinitive));
+            }
+        }
+        for &(infinitive, present, past, participle) in STRONG_VERBS {
+            let inseparable = INSEPARABLE_PREFIXES.contains(&prefix);
+            let is_form = strong_forms(present, past).any(|f| f == rest)
+                || rest == participle
+                || (inseparable && participle.strip_prefix("ge") == Some(rest));
+            if is_form {
+                lemmas.push(format!("{}{}", prefix, infinitive));
+            }
+        }
+    }
+
+    // Weak participles like "gemacht" and "aufgemacht".
+    for (prefix, rest) in prefix_splits(word) {
+        if INSEPARABLE_PREFIXES.contains(&prefix) {
+            continue;
+        }
+        if let Some(rest) = rest.strip_prefix("ge") {
+            for stem in [rest.strip_suffix("et"), rest.strip_suffix('t')]
+                .into_iter()
+                .flatten()
+            {
+                lemmas.extend(infinitives(stem).map(|i| format!("{}{}", prefix, i)));
+            }
+        }
+    }
+
+    // Weak past and present forms.
+    for ending in [
+        "etest", "etet", "eten", "ete", "test", "tet", "ten", "te", "est", "et", "st", "t", "e",
+    ] {
+        if let Some(stem) = word.strip_suffix(ending) {
+            lemmas.extend(infinitives(stem));
+        }
+    }
+
+    lemmas
+}
+
+/// The present and past forms of a strong verb, given its present stem if it changes
+/// and its past stem: "läuf", "läufst", "läuft" and "lief", "liefst", "liefen", "lieft",
+/// with "est" and "et" for stems like "stand". Impossible spellings like "liesst" don't
+/// harm.
+fn strong_forms<'a>(present: &'a str, past: &'a str) -> impl Iterator<Item = String> + 'a {
+    let present = (!present.is_empty())
+        .then(|| {
+            [
+                present.to_string(),
+                present.to_string() + "st",
+                present.to_string() + "t",
+            ]
+        })
+        .into_iter()
+        .flatten();
+    let plural = if past.ends_with('e') { "n" } else { "en" };
+    let past = [
+        past.to_string(),
+        past.to_string() + "st",
+        past.to_string() + plural,
+        past.to_string() + "t",
+        past.to_string() + "est",
+        past.to_string() + "et",
+    ];
+    present.chain(past)
+}
+
+/// The ways of splitting a verb into a prefix and the rest, without a prefix first.
+fn prefix_splits(word: &str) -> impl Iterator<Item = (&str, &str)> {
+    let prefixes = SEPARABLE_PREFIXES.iter().chain(INSEPARABLE_PREFIXES);
+    std::iter::once(("", word)).chain(prefixes.filter_map(move |&prefix| {
+        let rest = word.strip_prefix(prefix)?;
+        (rest.chars().count() > 2).then_some((prefix, rest))
+    }))
+}
+
+/// The infinitives of a weak verb stem: "wandern" for "wander", "machen" for "mach".
+fn infinitives(stem: &str) -> impl Iterator<Item = String> {
+    let short = (stem.ends_with("el") || stem.ends_with("er")).then(|| format!("{}n", stem));
+    short.into_iter().chain([format!("{}en", stem)])
+}
+
+/// The nouns and adjectives the word may be a form of: the word with its ending
+/// stripped, and with the umlaut of plurals and comparatives like "Häuser" and "größer"
+/// taken back.
+fn nominal_lemmas(word: &str) -> Vec<String> {
+    let mut lemmas = vec![];
+    if let Some(stem) = word.strip_suffix("innen") {
+        lemmas.push(format!("{}in", stem));
+    }
+    // Superlatives of stems ending in a sibilant have no "s" of their own: "größten".
+    let sibilant_superlatives = ["te", "ten", "ter", "tem", "tes"]
+        .iter()
+        .filter_map(|ending| word.strip_suffix(ending))
+        .filter(|stem| stem.ends_with(['s', 'ß', 'z', 'x']));
+    for stem in ENDINGS
+        .iter()
+        .filter_map(|ending| word.strip_suffix(ending))
+        .chain(sibilant_superlatives)
+        .chain([word])
+    {
+        lemmas.push(stem.to_string());
+        if let Some(plain) = remove_umlaut(stem) {
+            lemmas.push(plain);
+        }
+    }
+    lemmas
+}
+
+/// The word with its last umlaut replaced by the plain vowel, if it has one.
+fn remove_umlaut(word: &str) -> Option<String> {
+    let (i, c) = word
+        .char_indices()
+        .rev()
+        .find(|(_, c)| "äöüÄÖÜ".contains(*c))?;
+    let plain = match c {
+        'ä' => 'a',
+        'ö' => 'o',
+        'ü' => 'u',
+        'Ä' => 'A',
+        'Ö' => 'O',
+        _ => 'U',
+    };
+    Some(format!(
+        "{}{}{}",
+        &word[..i],
+        plain,
+        &word[i + c.len_utf8()..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lemmas;
+
+    #[test]
+    fn find_lemmas() {
+        let first = |word| lemmas(word).into_iter().next().map(|l| l.word);
+        assert_eq!(first("ging").as_deref(), Some("gehen"));
+        assert_eq!(first("läuft").as_deref(), Some("laufen"));
+        assert_eq!(first("angekommen").as_deref(), Some("ankommen"));
+        assert_eq!(first("verstanden").as_deref(), Some("verstehen"));
+        assert_eq!(first("verstandest").as_deref(), Some("verstehen"));
+        assert_eq!(first("fängt an").as_deref(), Some("anfangen"));
+        assert_eq!(first("war").as_deref(), Some("sein"));
+
+        let has = |word, lemma: &str| lemmas(word).iter().any(|l| l.word == lemma);
+        assert!(has("machte", "machen"));
+        assert!(has("aufgemacht", "aufmachen"));
+        assert!(has("wandert", "wandern"));
+        assert!(has("Häuser", "Haus"));
+        assert!(has("Äpfel", "Apfel"));
+        assert!(has("Lehrerinnen", "Lehrerin"));
+        assert!(has("größten", "groß"));
+        assert!(has("schöneren", "schön"));
+        assert!(!has("Hund", "Hund"));
+    }
+}
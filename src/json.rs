@@ -0,0 +1,315 @@
+//! Serializable views of dictionary data, for machine readable output.
+
+use std::{
+    borrow::Borrow,
+    io::{self, BufRead, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Card,
+    dictionary::{Deadline, LineReader},
+    entry::{Entry, Gender, Term},
+    query::{self, Language, NormalizeOptions},
+    rank::Weights,
+    Dictionary,
+};
+
+#[derive(Debug, Serialize)]
+pub struct EntryJson {
+    /// See [`Entry::id`].
+    pub id: String,
+    pub german: TermJson,
+    pub english: TermJson,
+    pub word_classes: Vec<String>,
+    pub subjects: Vec<String>,
+    pub line: usize,
+    /// The dictionary the entry comes from, "main" or "custom".
+    pub source: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TermJson {
+    /// The term as the terse output shows it.
+    pub text: String,
+    pub keywords: Vec<String>,
+    pub gender: Option<&'static str>,
+    pub placeholders: Vec<String>,
+    pub annotations: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GenderJson {
+    pub word: String,
+    pub gender: Option<&'static str>,
+    pub article: &'static str,
+    pub plural: bool,
+    /// The translations of the noun with this gender.
+    pub translations: Vec<String>,
+    /// The plural of the noun, if the dictionary has it.
+    pub plural_form: Option<String>,
+}
+
+/// A sense of a German word, see [`Card`].
+#[derive(Debug, Serialize)]
+pub struct CardJson {
+    pub headword: String,
+    pub word_class: Option<String>,
+    pub gender: Option<&'static str>,
+    pub article: Option<&'static str>,
+    pub plural_form: Option<String>,
+    pub translations: Vec<RegisterJson>,
+    pub subjects: Vec<String>,
+    pub references: Vec<String>,
+    pub see_also: Vec<EntryJson>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterJson {
+    /// The annotation marking the register, none for unmarked entries.
+    pub register: Option<String>,
+    pub entries: Vec<EntryJson>,
+}
+
+/// Several queries answered at once, so that clients glossing a whole page don't
+/// have to make a request per word.
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub queries: Vec<String>,
+    /// The language of all queries.
+    #[serde(default = "default_language")]
+    pub language: Language,
+    /// The maximum number of results per query, all of them if absent.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    pub query: String,
+    pub results: Vec<EntryJson>,
+}
+
+/// Answers every query of the request, in the order they were given. The queries are
+/// normalized like the CLI does. Once the deadline passed, the remaining queries get no
+/// results.
+pub fn batch(dict: &Dictionary, request: &BatchRequest, deadline: &Deadline) -> Vec<BatchResult> {
+    request
+        .queries
+        .iter()
+        .map(|query| {
+            let normalized =
+                query::normalize(query, &NormalizeOptions::for_language(request.language));
+            let matches =
+                dict.search_until(&normalized, request.language, &Weights::default(), deadline);
+            let limit = request.limit.unwrap_or(matches.len());
+
+            BatchResult {
+                query: query.clone(),
+                results: matches.iter().take(limit).map(|m| m.entry.into()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// The entries exactly matching the word and kept by `keep`, best first.
+pub fn exact_matches(
+    dict: &Dictionary,
+    word: &str,
+    language: Language,
+    weights: &Weights,
+    deadline: &Deadline,
+    keep: impl Fn(&Entry) -> bool,
+) -> Vec<EntryJson> {
+    // Ranking compares every entry, only the few exact matches are kept anyway.
+    let exact = Dictionary::from_entries(
+        dict.lookup_until(word, language, deadline)
+            .into_iter()
+            .cloned()
+            .collect(),
+    );
+    exact
+        .exact_matches(word, language, weights)
+        .into_iter()
+        .filter(|e| keep(e))
+        .map(EntryJson::from)
+        .collect()
+}
+
+/// The genders of the German noun with their translations and plurals. The plural
+/// entries are looked up in `plurals`, which may be `dict` itself.
+pub fn genders(dict: &Dictionary, plurals: &Dictionary, word: &str) -> Vec<GenderJson> {
+    dict.nouns(plurals, word)
+        .into_iter()
+        .map(|noun| GenderJson {
+            translations: noun.translations,
+            plural_form: noun.plural,
+            ..GenderJson::new(noun.headword, noun.term)
+        })
+        .collect()
+}
+
+/// Writes the entries exactly matching the word and kept by `keep` as newline delimited
+/// JSON while the dictionary is scanned, see [`write_ndjson`].
+pub fn stream_matches<R: BufRead>(
+    rd: LineReader<R>,
+    word: &str,
+    language: Language,
+    keep: impl Fn(&Entry) -> bool,
+    limit: usize,
+    wr: impl Write,
+) -> io::Result<usize> {
+    write_ndjson(Dictionary::search(rd, word, language), keep, limit, wr)
+}
+
+/// Writes the entries kept by `keep` as newline delimited JSON as they come, flushing
+/// after every entry, so that consumers can show results before the scan completes.
+/// Stops after `limit` entries. Returns the number of entries written.
+pub fn write_ndjson<E: Borrow<Entry>>(
+    entries: impl IntoIterator<Item = io::Result<E>>,
+    keep: impl Fn(&Entry) -> bool,
+    limit: usize,
+    mut wr: impl Write,
+) -> io::Result<usize> {
+    let mut count = 0;
+
+    for entry in entries {
+        if count == limit {
+            break;
+        }
+        let entry = entry?;
+        let entry = entry.borrow();
+        if !keep(entry) {
+            continue;
+        }
+
+        serde_json::to_writer(&mut wr, &EntryJson::from(entry))?;
+        wr.write_all(b"\n")?;
+        wr.flush()?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+fn default_language() -> Language {
+    Language::German
+}
+
+impl From<&Entry> for EntryJson {
+    fn from(entry: &Entry) -> Self {
+        Self {
+            id: entry.id().to_string(),
+            german: (&entry.german).into(),
+            english: (&entry.english).into(),
+            word_classes: entry.word_classes.iter().map(|c| c.to_string()).collect(),
+            subjects: entry
+                .subjects
+                .iter()
+                .map(|s| s.as_str().to_string())
+                .collect(),
+            line: entry.source.line,
+            source: entry.source.origin.as_str(),
+        }
+    }
+}
+
+impl From<&Term> for TermJson {
+    fn from(term: &Term) -> Self {
+        Self {
+            text: term.to_string(),
+            keywords: term.keywords().map(String::from).collect(),
+            gender: term.gender().map(gender_name),
+            placeholders: term
+                .placeholders()
+                .iter()
+                .map(|p| p.to_dictcc_string())
+                .collect(),
+            annotations: term.annotations().iter().map(|a| a.to_string()).collect(),
+        }
+    }
+}
+
+impl CardJson {
+    pub fn new(card: &Card, plural_form: Option<String>) -> Self {
+        Self {
+            headword: card.headword.to_string(),
+            word_class: card.word_class.map(|c| c.to_string()),
+            gender: card.gender.map(gender_name),
+            article: match card.gender {
+                Some(Gender::Masculine) => Some("der"),
+                Some(Gender::Feminine) => Some("die"),
+                Some(Gender::Neutral) => Some("das"),
+                None if card.plural_only => Some("die"),
+                None => None,
+            },
+            plural_form,
+            translations: card
+                .registers
+                .iter()
+                .map(|(register, entries)| RegisterJson {
+                    register: register.map(String::from),
+                    entries: entries.iter().map(|e| EntryJson::from(*e)).collect(),
+                })
+                .collect(),
+            subjects: card.subjects.iter().map(|s| s.to_string()).collect(),
+            references: card.references.iter().map(|r| r.to_string()).collect(),
+            see_also: card.see_also.iter().map(|e| EntryJson::from(*e)).collect(),
+        }
+    }
+}
+
+impl GenderJson {
+    /// Describes the gender of a noun entry's German term.
+    pub fn new(word: &str, term: &Term) -> Self {
+        let gender = term.gender();
+
+        Self {
+            word: word.to_string(),
+            gender: gender.map(gender_name),
+            article: match gender {
+                Some(Gender::Masculine) => "der",
+                Some(Gender::Feminine) | None => "die",
+                Some(Gender::Neutral) => "das",
+            },
+            plural: gender.is_none() && term.is_plural(),
+            translations: vec![],
+            plural_form: None,
+        }
+    }
+}
+
+pub(crate) fn gender_name(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Masculine => "masculine",
+        Gender::Feminine => "feminine",
+        Gender::Neutral => "neuter",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch, BatchRequest};
+    use crate::{dictionary::Deadline, Dictionary};
+
+    #[test]
+    fn batch_groups_results_per_query() {
+        let dict = Dictionary::load(
+            "Hund {m}\tdog\tnoun\t\nKatze {f}\tcat\tnoun\t\nHundehütte {f}\tkennel\tnoun\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let request: BatchRequest =
+            serde_json::from_str(r#"{"queries": ["Hund", "Katze", "Maus"], "limit": 1}"#).unwrap();
+
+        let results = batch(&dict, &request, &Deadline::default());
+
+        let summary: Vec<_> = results
+            .iter()
+            .map(|r| (r.query.as_str(), r.results.len()))
+            .collect();
+        assert_eq!(summary, [("Hund", 1), ("Katze", 1), ("Maus", 0)]);
+        assert_eq!(results[0].results[0].english.text, "dog");
+    }
+}
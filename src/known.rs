@@ -0,0 +1,58 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::store;
+
+/// Words the user already knows and doesn't want glossed anymore, stored as a
+/// plain text file with one word per line.
+#[derive(Debug, Clone, Default)]
+pub struct KnownWords(BTreeSet<String>);
+
+impl KnownWords {
+    /// Reads the known words from the given file. A missing file yields an empty list.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(s) => Ok(Self(
+                s.lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_lowercase)
+                    .collect(),
+            )),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = String::new();
+        for word in &self.0 {
+            out.push_str(word);
+            out.push('\n');
+        }
+
+        store::write_atomic(path, out.as_bytes())
+    }
+
+    /// Returns whether the word was not known before.
+    pub fn insert(&mut self, word: &str) -> bool {
+        self.0.insert(word.to_lowercase())
+    }
+
+    /// Returns whether the word was known before.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.0.remove(&word.to_lowercase())
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(&word.to_lowercase())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
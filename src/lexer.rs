@@ -1,3 +1,6 @@
+//! Splitting dict.cc terms into tokens: words, parentheses, slashes, bracketed literals
+//! and quoted segments. The parser in [`entry`](crate::entry) builds terms from them.
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum LiteralMarker {
     Curly,
@@ -6,459 +9,285 @@ pub enum LiteralMarker {
 }
 
 impl LiteralMarker {
-    fn from_start_seq(seq: &str) -> Option<LiteralMarker> {
-        match seq {
-            "<" => Some(Self::Angle),
-            "[" => Some(Self::Square),
-            "{" => Some(Self::Curly),
+    fn from_start(c: char) -> Option<LiteralMarker> {
+        match c {
+            '<' => Some(Self::Angle),
+            '[' => Some(Self::Square),
+            '{' => Some(Self::Curly),
             _ => None,
         }
     }
 
-    fn seq_start(&self) -> &'static str {
+    fn start(&self) -> char {
         match self {
-            Self::Angle => "<",
-            Self::Square => "[",
-            Self::Curly => "{",
+            Self::Angle => '<',
+            Self::Square => '[',
+            Self::Curly => '{',
         }
     }
 
-    fn seq_end(&self) -> &'static str {
+    fn end(&self) -> char {
         match self {
-            Self::Angle => ">",
-            Self::Square => "]",
-            Self::Curly => "}",
+            Self::Angle => '>',
+            Self::Square => ']',
+            Self::Curly => '}',
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Token<'a> {
-    Parens {
-        is_start: bool,
-    },
+    /// A parenthesis opening at the start of a word.
+    Open,
+    /// The parenthesis closing the last open one.
+    Close,
+    /// A slash between alternatives, as in "jdn./etw.".
     Slash,
+    /// The contents of brackets, as in "[ugs.]", "<ANC>" or "{m}". Square and angle
+    /// brackets nest, so that annotations can contain annotations.
     Literal {
         value: &'a str,
         marker: LiteralMarker,
     },
+    /// A word. Parentheses inside a word belong to it, as in "Uluguru-(Zwerg-)Galago" or
+    /// "Requiem(, nach Worten der heiligen Schrift)", and so do brackets which are never
+    /// closed, as in "<SFL-Haubitze".
     Text(&'a str),
+    /// The contents of quotes which end a word, without the quotes.
     Quoted(&'a str),
 }
 
-#[derive(thiserror::Error, Debug, Clone)]
+/// Why the term couldn't be split into tokens. Positions are byte offsets.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
 pub enum Error {
-    #[error("incomplete literal starting with \"{}\" at position {}", .marker.seq_start(), .at)]
-    IncompleteLiteral { marker: LiteralMarker, at: usize },
-    #[error("unexpected char at position {0}")]
-    Unexpected(usize),
+    #[error("unclosed curly brace at position {0}")]
+    UnclosedCurly(usize),
+    #[error("unclosed parenthesis in the word at position {0}")]
+    UnclosedWordParenthesis(usize),
+    /// A space or another special character comes before the closing parenthesis of a
+    /// word inside parentheses, so that it can't be told which parenthesis closes what.
+    /// `len` reaches up to and including that character.
+    #[error("special char before the closing parenthesis in the word at position {at}")]
+    SpecialCharInWord { at: usize, len: usize },
 }
 
-pub fn lex(input: &str) -> Tokens {
+pub fn lex(input: &str) -> Tokens<'_> {
     Tokens {
         input,
         at: 0,
-        space_behavior: SpaceBehavior::None,
+        depth: 0,
     }
 }
 
-const QUOTE_PAIRS: &[(&str, &str)] = &[("“", "”"), ("”", "”"), ("„", "”"), ("„", "“"), ("'", "'")];
+// Unlike the other quotes, straight single quotes are left out: they are mostly
+// apostrophes, as in "'ne Menge".
+const QUOTE_PAIRS: &[(char, char)] = &[('“', '”'), ('”', '”'), ('„', '“'), ('„', '”')];
 
+/// The tokens of a term, with the byte offsets they start at.
 #[derive(Debug, Clone)]
 pub struct Tokens<'a> {
     input: &'a str,
     at: usize,
-    space_behavior: SpaceBehavior,
+    /// The number of parentheses open.
+    depth: usize,
 }
 
 impl<'a> Tokens<'a> {
-    pub fn as_str(&'a self) -> &'a str {
+    /// The input not split into tokens yet.
+    pub fn as_str(&self) -> &'a str {
         &self.input[self.at..]
     }
-}
-
-impl<'a> Iterator for Tokens<'a> {
-    type Item = Result<Token<'a>, Error>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let c = next_char(&self.input[self.at..])?;
-
-            if c == "(" {
-                return match try_consume_text(self.input, self.at) {
-                    Ok(Some((word, new_at, beh))) => {
-                        self.at = new_at;
-                        self.space_behavior = beh;
-                        Some(Ok(Token::Text(word)))
-                    }
-                    Ok(None) => {
-                        self.at += c.len();
-                        self.space_behavior = SpaceBehavior::Maybe;
-                        Some(Ok(Token::Parens { is_start: c == "(" }))
-                    }
-                    Err(err) => Some(Err(err)),
-                };
-            }
-
-            if c == ")" {
-                self.at += c.len();
-                self.space_behavior = SpaceBehavior::Maybe;
-                return Some(Ok(Token::Parens { is_start: false }));
-            }
 
-            if c == "/" {
-                self.at += c.len();
-                self.space_behavior = SpaceBehavior::Maybe;
-                return Some(Ok(Token::Slash));
-            }
-
-            if c == " " {
-                match self.space_behavior {
-                    SpaceBehavior::None => return Some(Err(Error::Unexpected(self.at))),
-                    SpaceBehavior::Required => {
-                        self.at += 1;
-                        self.space_behavior = SpaceBehavior::Maybe;
-                    }
-                    SpaceBehavior::Maybe => self.at += 1,
-                }
-                continue;
-            }
-
-            if let Some(marker) = LiteralMarker::from_start_seq(c) {
-                let (token, new_at, beh) =
-                    match try_consume_literal(self.input, self.at + c.len(), marker) {
-                        Err(err) => match try_consume_text(self.input, self.at) {
-                            Err(_) | Ok(None) => return Some(Err(err)),
-                            Ok(Some((word, new_at, beh))) => (Token::Text(word), new_at, beh),
-                        },
-                        Ok((value, new_at, beh)) => (Token::Literal { value, marker }, new_at, beh),
-                    };
-                self.at = new_at;
-                self.space_behavior = beh;
-                return Some(Ok(token));
-            }
+    /// Whether the character ends a word. Closing parentheses only do inside
+    /// parentheses, elsewhere they belong to words like "(Ach was), echt?".
+    fn is_special(&self, c: char) -> bool {
+        matches!(c, '[' | '{' | '<' | '(' | '/' | ' ') || (c == ')' && self.depth > 0)
+    }
 
-            if matches!(self.space_behavior, SpaceBehavior::Required) {
-                return Some(Err(Error::Unexpected(self.at)));
-            }
+    fn token(&mut self, len: usize, token: Token<'a>) -> Option<Result<(usize, Token<'a>), Error>> {
+        let at = self.at;
+        self.at += len;
+        Some(Ok((at, token)))
+    }
 
-            if QUOTE_PAIRS.iter().any(|(start_quote, _)| *start_quote == c) {
-                if let Some((quoted, new_at, beh)) =
-                    consume_quoted(self.input, self.at + c.len(), c)
-                {
-                    self.at = new_at;
-                    self.space_behavior = beh;
-                    return Some(Ok(Token::Quoted(quoted)));
+    /// The brackets starting the rest and their contents, if they are closed.
+    fn literal(&self, marker: LiteralMarker) -> Option<&'a str> {
+        let rest = &self.as_str()[1..];
+        let mut nesting = 1;
+        for (i, c) in rest.char_indices() {
+            if c == marker.end() {
+                nesting -= 1;
+                if nesting == 0 {
+                    return Some(&rest[..i]);
                 }
+            } else if c == marker.start() && marker != LiteralMarker::Curly {
+                nesting += 1;
             }
-
-            return match try_consume_text(self.input, self.at) {
-                Ok(Some((word, new_at, beh))) => {
-                    self.at = new_at;
-                    self.space_behavior = beh;
-                    Some(Ok(Token::Text(word)))
-                }
-                Ok(None) => Some(Err(Error::Unexpected(self.at))),
-                Err(err) => Some(Err(err)),
-            };
         }
-    }
-}
-
-fn next_char(input: &str) -> Option<&str> {
-    if input.is_empty() {
-        return None;
+        None
     }
 
-    let mut end = 1;
-    while !input.is_char_boundary(end) {
-        end += 1;
+    /// The quotes starting the rest and their contents, if the closing quote ends a
+    /// word. Otherwise the quotes are part of a word.
+    fn quoted(&self) -> Option<(&'a str, usize)> {
+        let s = self.as_str();
+        let open = s.chars().next()?;
+        let rest = &s[open.len_utf8()..];
+
+        QUOTE_PAIRS
+            .iter()
+            .filter(|(o, _)| *o == open)
+            .find_map(|(_, close)| {
+                let end = rest.find(*close)?;
+                let after = &rest[end + close.len_utf8()..];
+                let ends_word = after.chars().next().is_none_or(|c| {
+                    matches!(c, ',' | '.' | ';' | ':' | '!' | '?') || self.is_special(c)
+                });
+                (end > 0 && ends_word).then(|| (&rest[..end], s.len() - after.len()))
+            })
     }
 
-    // SAFETY: Ensured above that end is a char boundary.
-    return Some(unsafe { input.get_unchecked(..end) });
-}
-
-type Output<'a> = (&'a str, usize, SpaceBehavior);
-
-fn try_consume_literal(input: &str, at: usize, marker: LiteralMarker) -> Result<Output, Error> {
-    debug_assert!(!input.is_empty());
-
-    let start = marker.seq_start();
-    let end = marker.seq_end();
-
-    let mut level = 1;
-    let mut idx_start = at;
-    let mut idx_end = idx_start + 1;
-
-    while level > 0 && idx_end <= input.len() {
-        let Some(c) = input.get(idx_start..idx_end) else {
-            idx_end += 1;
-            continue;
+    /// The length of the word starting the rest. A bracket starting it is part of it.
+    fn word(&self) -> Result<usize, Error> {
+        let s = self.as_str();
+        let first = s.chars().next().map_or(0, char::len_utf8);
+        let Some(i) = s[first..].find(|c| self.is_special(c)).map(|i| i + first) else {
+            return Ok(s.len());
         };
-
-        if c == start {
-            level += 1
-        } else if c == end {
-            level -= 1
+        if !s[i..].starts_with('(') {
+            return Ok(i);
         }
 
-        idx_start = idx_end;
-        idx_end = idx_start + 1;
-    }
-
-    if level == 0 {
-        Ok((
-            &input[at..idx_start - end.len()],
-            idx_start,
-            SpaceBehavior::Maybe,
-        ))
-    } else {
-        Err(Error::IncompleteLiteral { marker, at })
+        // A parenthesis inside the word, as in "Blei(II,IV)-oxid".
+        let close = if self.depth == 0 {
+            s[i + 1..]
+                .find(')')
+                .ok_or(Error::UnclosedWordParenthesis(self.at))?
+        } else {
+            let j = s[i + 1..]
+                .find(|c| self.is_special(c))
+                .ok_or(Error::UnclosedWordParenthesis(self.at))?;
+            let c = s[i + 1 + j..].chars().next().unwrap_or(' ');
+            if c != ')' {
+                return Err(Error::SpecialCharInWord {
+                    at: self.at,
+                    len: i + 1 + j + c.len_utf8(),
+                });
+            }
+            j
+        } + i
+            + 1;
+
+        let after = close + 1;
+        Ok(s[after..]
+            .find(|c| self.is_special(c))
+            .map_or(s.len(), |j| after + j))
     }
 }
 
-fn try_consume_text(input: &str, at: usize) -> Result<Option<Output>, Error> {
-    let mut idx_start = at;
-    let mut idx_end = idx_start + 1;
-    let mut level = 0;
-    let mut idx_prev_parens_open = None;
-    let mut idx_parens_open = None;
-    let mut idx_prev_parens_close = None;
-    let mut idx_parens_close = None;
-    let mut parens_count = 0;
-    let mut last_char = None;
-
-    while idx_end <= input.len() {
-        let Some(c) = input.get(idx_start..idx_end) else {
-            idx_end += 1;
-            continue;
-        };
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<(usize, Token<'a>), Error>;
 
-        last_char = Some(c);
+    fn next(&mut self) -> Option<Self::Item> {
+        let spaces = self.as_str().len() - self.as_str().trim_start_matches(' ').len();
+        self.at += spaces;
+        let c = self.as_str().chars().next()?;
 
         match c {
-            "(" => {
-                idx_prev_parens_open = idx_parens_open;
-                idx_parens_open = Some(idx_start);
-                parens_count += 1;
-                level += 1;
+            '(' => {
+                self.depth += 1;
+                return self.token(1, Token::Open);
             }
-            ")" => {
-                idx_prev_parens_close = idx_parens_close;
-                idx_parens_close = Some(idx_start);
-                level -= 1;
+            ')' if self.depth > 0 => {
+                self.depth -= 1;
+                return self.token(1, Token::Close);
             }
-            " " | "/" => break,
+            '/' => return self.token(1, Token::Slash),
             _ => {}
         }
 
-        if parens_count > 1 {
-            idx_parens_open = idx_prev_parens_open;
-            idx_parens_close = idx_prev_parens_close;
-            break;
+        if let Some(marker) = LiteralMarker::from_start(c) {
+            match self.literal(marker) {
+                Some(value) => {
+                    return self.token(value.len() + 2, Token::Literal { value, marker })
+                }
+                None if marker == LiteralMarker::Curly => {
+                    return Some(Err(Error::UnclosedCurly(self.at)))
+                }
+                // Unclosed brackets are part of a word.
+                None => {}
+            }
         }
 
-        if !(0..=1).contains(&level) {
-            break;
+        if let Some((quoted, len)) = self.quoted() {
+            return self.token(len, Token::Quoted(quoted));
         }
 
-        idx_start = idx_end;
-        idx_end = idx_start + 1;
-    }
-
-    let new_at;
-
-    if idx_end > input.len() {
-        idx_end -= 1;
-        new_at = idx_end;
-    } else if let Some(c) = last_char {
-        match c {
-            " " => new_at = idx_end,
-            "/" => new_at = idx_start,
-            "(" => {
-                new_at = idx_parens_open.unwrap();
-                return if at == new_at {
-                    Ok(None)
-                } else {
-                    Ok(Some((&input[at..new_at], new_at, SpaceBehavior::None)))
-                };
-            }
-            ")" => {
-                new_at = idx_parens_close.unwrap();
-                return Ok(Some((&input[at..new_at], new_at, SpaceBehavior::Required)));
+        match self.word() {
+            Ok(len) => {
+                let word = &self.as_str()[..len];
+                self.token(len, Token::Text(word))
             }
-            _ => unreachable!("stopped at char {idx_start} \"{c}\", {input}"),
-        };
-    } else {
-        unreachable!("called with fully consumed input, {input}");
-    }
-
-    let word = &input[at..idx_start];
-
-    let (idx_parens_open, idx_parens_close) = match (idx_parens_open, idx_parens_close) {
-        (Some(a), Some(b)) => (a, b),
-        (None, None) => return Ok(Some((word, new_at, SpaceBehavior::Maybe))),
-        (Some(i), None) => {
-            if i == at {
-                return Ok(None);
+            Err(e) => {
+                self.at = self.input.len();
+                Some(Err(e))
             }
-
-            let word = &input[at..i];
-            return Ok(Some((word, i, SpaceBehavior::None)));
         }
-        (None, Some(_)) => unreachable!(),
-    };
-
-    let starts_with_paren = idx_parens_open == at;
-    let ends_with_paren = idx_parens_close == idx_start - 1;
-
-    if starts_with_paren && ends_with_paren {
-        return Ok(None);
-    }
-
-    fn is_word_char(c: char) -> bool {
-        c.is_alphanumeric() || c == '-'
     }
-
-    let inside_parens = &input[idx_parens_open + 1..idx_parens_close];
-    let inside_has_spaces = inside_parens.contains(' ');
-    let inside_has_only_non_word_chars = inside_parens.chars().all(|c| !is_word_char(c));
-    let outside_has_non_word_chars = input[at..idx_parens_open].chars().any(|c| !is_word_char(c))
-        || input[idx_parens_close + 1..idx_start]
-            .chars()
-            .any(|c| !is_word_char(c));
-
-    if inside_has_spaces || inside_has_only_non_word_chars || outside_has_non_word_chars {
-        return if starts_with_paren && !outside_has_non_word_chars {
-            Err(Error::Unexpected(idx_parens_open + 1))
-        } else if at < idx_parens_open {
-            Ok(Some((
-                &input[at..idx_parens_open],
-                idx_parens_open,
-                SpaceBehavior::None,
-            )))
-        } else {
-            Ok(None)
-        };
-    }
-
-    Ok(Some((
-        word,
-        new_at,
-        if ends_with_paren {
-            SpaceBehavior::Maybe
-        } else {
-            SpaceBehavior::None
-        },
-    )))
-}
-
-fn consume_quoted<'a>(input: &'a str, at: usize, start_quote: &'a str) -> Option<Output<'a>> {
-    QUOTE_PAIRS
-        .iter()
-        .filter_map(|(s, e)| (*s == start_quote).then_some(e))
-        .find_map(|e| input[at..].find(e).zip(Some(e.len())))
-        .map(|(i, l)| (&input[at..at + i], at + i + l, SpaceBehavior::Maybe))
-}
-
-#[derive(Debug, Clone)]
-enum SpaceBehavior {
-    None,
-    Maybe,
-    Required,
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{Error, LiteralMarker as Marker, Token};
+
     #[test]
     fn lex_success() {
-        type Token = super::Token<'static>;
-        use super::LiteralMarker as Marker;
+        let literal = |marker, value| Token::Literal { value, marker };
 
         let data: &[(&str, &[Token])] = &[
             (
                 "Magnesioferrit {m} [ein Spinell]",
                 &[
                     Token::Text("Magnesioferrit"),
-                    Token::Literal {
-                        marker: Marker::Curly,
-                        value: "m",
-                    },
-                    Token::Literal {
-                        marker: Marker::Square,
-                        value: "ein Spinell",
-                    },
+                    literal(Marker::Curly, "m"),
+                    literal(Marker::Square, "ein Spinell"),
                 ],
             ),
             (
                 "Uluguru-(Zwerg-)Galago {m}",
                 &[
                     Token::Text("Uluguru-(Zwerg-)Galago"),
-                    Token::Literal {
-                        marker: Marker::Curly,
-                        value: "m",
-                    },
+                    literal(Marker::Curly, "m"),
                 ],
             ),
             (
                 "(an etw. [Dat.]) herumbasteln [ugs.]",
                 &[
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("an"),
                     Token::Text("etw."),
-                    Token::Literal {
-                        value: "Dat.",
-                        marker: Marker::Square,
-                    },
-                    Token::Parens { is_start: false },
+                    literal(Marker::Square, "Dat."),
+                    Token::Close,
                     Token::Text("herumbasteln"),
-                    Token::Literal {
-                        value: "ugs.",
-                        marker: Marker::Square,
-                    },
+                    literal(Marker::Square, "ugs."),
                 ],
             ),
             (
                 "Vanadoandrosit-(Ce) {m}",
                 &[
                     Token::Text("Vanadoandrosit-(Ce)"),
-                    Token::Literal {
-                        value: "m",
-                        marker: Marker::Curly,
-                    },
+                    literal(Marker::Curly, "m"),
                 ],
             ),
             (
                 "Requiem(, nach Worten der heiligen Schrift)",
-                &[
-                    Token::Text("Requiem"),
-                    Token::Parens { is_start: true },
-                    Token::Text(","),
-                    Token::Text("nach"),
-                    Token::Text("Worten"),
-                    Token::Text("der"),
-                    Token::Text("heiligen"),
-                    Token::Text("Schrift"),
-                    Token::Parens { is_start: false },
-                ],
-            ),
-            (
-                "(A(B))",
-                &[
-                    Token::Parens { is_start: true },
-                    Token::Text("A(B)"),
-                    Token::Parens { is_start: false },
-                ],
+                &[Token::Text("Requiem(, nach Worten der heiligen Schrift)")],
             ),
+            ("(A(B))", &[Token::Open, Token::Text("A(B)"), Token::Close]),
             ("<SFL-Haubitze", &[Token::Text("<SFL-Haubitze")]),
             (
                 "(<SFL-Haubitze)",
-                &[
-                    Token::Parens { is_start: true },
-                    Token::Text("<SFL-Haubitze"),
-                    Token::Parens { is_start: false },
-                ],
+                &[Token::Open, Token::Text("<SFL-Haubitze"), Token::Close],
             ),
             (
                 "unter Spannung von > 50 V",
@@ -475,26 +304,23 @@ mod tests {
                 "vicanite-(Ce) [Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5|(AsO4|BO3|Si3B3O18|SiO4)3]]",
                 &[
                     Token::Text("vicanite-(Ce)"),
-                    Token::Literal {
-                        value: "Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5|(AsO4|BO3|Si3B3O18|SiO4)3]",
-                        marker: Marker::Square,
-                    },
+                    literal(
+                        Marker::Square,
+                        "Na0.5(Ce,Ca,Th)15Fe [F9|(AsO3)0.5|(AsO4|BO3|Si3B3O18|SiO4)3]",
+                    ),
                 ],
             ),
             (
                 "sich [Akk.] (auf jdn./etw.) aufstützen",
                 &[
                     Token::Text("sich"),
-                    Token::Literal {
-                        value: "Akk.",
-                        marker: Marker::Square,
-                    },
-                    Token::Parens { is_start: true },
+                    literal(Marker::Square, "Akk."),
+                    Token::Open,
                     Token::Text("auf"),
                     Token::Text("jdn."),
                     Token::Slash,
                     Token::Text("etw."),
-                    Token::Parens { is_start: false },
+                    Token::Close,
                     Token::Text("aufstützen"),
                 ],
             ),
@@ -505,48 +331,39 @@ mod tests {
                     Token::Slash,
                     Token::Text("böswillige"),
                     Token::Text("Beschädigung"),
-                    Token::Literal {
-                        value: "f",
-                        marker: Marker::Curly,
-                    },
+                    literal(Marker::Curly, "f"),
                 ],
             ),
             (
                 "(Afrikanische) Bodenagame {f}",
                 &[
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("Afrikanische"),
-                    Token::Parens { is_start: false },
+                    Token::Close,
                     Token::Text("Bodenagame"),
-                    Token::Literal {
-                        value: "f",
-                        marker: Marker::Curly,
-                    },
+                    literal(Marker::Curly, "f"),
                 ],
             ),
             (
                 "(Ach, du) heilige Scheiße! [vulg.]",
                 &[
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("Ach,"),
                     Token::Text("du"),
-                    Token::Parens { is_start: false },
+                    Token::Close,
                     Token::Text("heilige"),
                     Token::Text("Scheiße!"),
-                    Token::Literal {
-                        value: "vulg.",
-                        marker: Marker::Square,
-                    },
+                    literal(Marker::Square, "vulg."),
                 ],
             ),
             (
                 "(am / zu) Anfang des Winters",
                 &[
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("am"),
                     Token::Slash,
                     Token::Text("zu"),
-                    Token::Parens { is_start: false },
+                    Token::Close,
                     Token::Text("Anfang"),
                     Token::Text("des"),
                     Token::Text("Winters"),
@@ -555,13 +372,10 @@ mod tests {
             (
                 "(ganz) unten(,) am Grund",
                 &[
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("ganz"),
-                    Token::Parens { is_start: false },
-                    Token::Text("unten"),
-                    Token::Parens { is_start: true },
-                    Token::Text(","),
-                    Token::Parens { is_start: false },
+                    Token::Close,
+                    Token::Text("unten(,)"),
                     Token::Text("am"),
                     Token::Text("Grund"),
                 ],
@@ -569,10 +383,10 @@ mod tests {
             (
                 "(Ach was), echt?",
                 &[
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("Ach"),
                     Token::Text("was"),
-                    Token::Parens { is_start: false },
+                    Token::Close,
                     Token::Text(","),
                     Token::Text("echt?"),
                 ],
@@ -582,48 +396,63 @@ mod tests {
                 "Leute {pl}, die",
                 &[
                     Token::Text("Leute"),
-                    Token::Literal {
-                        value: "pl",
-                        marker: Marker::Curly,
-                    },
+                    literal(Marker::Curly, "pl"),
                     Token::Text(","),
                     Token::Text("die"),
                 ],
             ),
             (
                 "Blei(II,IV)-oxid {n}",
-                &[
-                    Token::Text("Blei(II,IV)-oxid"),
-                    Token::Literal {
-                        value: "n",
-                        marker: Marker::Curly,
-                    },
-                ],
+                &[Token::Text("Blei(II,IV)-oxid"), literal(Marker::Curly, "n")],
             ),
             (
                 "Jiminy (cricket)!  [Am.]  [coll.]",
                 &[
                     Token::Text("Jiminy"),
-                    Token::Parens { is_start: true },
+                    Token::Open,
                     Token::Text("cricket"),
-                    Token::Parens { is_start: false },
+                    Token::Close,
                     Token::Text("!"),
-                    Token::Literal {
-                        value: "Am.",
-                        marker: Marker::Square,
-                    },
-                    Token::Literal {
-                        value: "coll.",
-                        marker: Marker::Square,
-                    },
+                    literal(Marker::Square, "Am."),
+                    literal(Marker::Square, "coll."),
+                ],
+            ),
+            (
+                "Aktion {f} „Brot für die Welt“, 'ne Menge",
+                &[
+                    Token::Text("Aktion"),
+                    literal(Marker::Curly, "f"),
+                    Token::Quoted("Brot für die Welt"),
+                    Token::Text(","),
+                    Token::Text("'ne"),
+                    Token::Text("Menge"),
                 ],
             ),
         ];
 
         for (input, expected) in data {
-            eprintln!("Testing \"{input}\"...");
-            let output = super::lex(input).collect::<Result<Vec<_>, _>>().unwrap();
-            assert_eq!(&output[..], *expected);
+            let output = super::lex(input)
+                .map(|t| t.map(|(_, token)| token))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(&output[..], *expected, "{}", input);
         }
+
+        let positions: Vec<_> = super::lex("Hund {m} (groß)")
+            .map(|t| t.unwrap().0)
+            .collect();
+        assert_eq!(positions, [0, 5, 9, 10, 15]);
+    }
+
+    #[test]
+    fn lex_errors() {
+        let error = |input| super::lex(input).find_map(Result::err).unwrap();
+
+        assert_eq!(error("Hund {m"), Error::UnclosedCurly(5));
+        assert_eq!(error("der Hund(e {m}"), Error::UnclosedWordParenthesis(4));
+        assert_eq!(
+            error("(Hund(e {m})"),
+            Error::SpecialCharInWord { at: 1, len: 7 }
+        );
     }
 }
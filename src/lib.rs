@@ -1,5 +1,42 @@
+pub mod backup;
+pub mod block;
+pub mod card;
+pub mod cli;
+pub mod config;
+pub mod console;
+#[cfg(unix)]
+pub mod daemon;
+pub mod dictionary;
+pub mod editor;
 pub mod entry;
+pub mod filter;
+pub mod group;
+pub mod index;
+pub mod inflection;
+pub mod json;
+pub mod known;
+pub mod loader;
+pub mod lookups;
+pub mod paths;
+pub mod present;
+#[cfg(feature = "pronunciation")]
+pub mod pronunciation;
 pub mod query;
+pub mod rank;
+pub mod search;
+pub mod stats;
+pub mod stopwords;
+pub mod store;
+pub mod text;
+pub mod tui;
+pub mod update;
 pub mod util;
+pub mod valency;
+pub mod vocab;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod web;
 
 pub mod lexer;
+
+pub use dictionary::Dictionary;
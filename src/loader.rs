@@ -0,0 +1,129 @@
+use std::{
+    borrow::Cow,
+    fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::index::{self, Index, Unavailable};
+
+/// How a dictionary file is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Recognizes the compression by the magic number at the start of the data.
+    pub fn detect(data: &[u8]) -> Self {
+        match data {
+            [0x1f, 0x8b, ..] => Self::Gzip,
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+}
+
+/// The raw data of a dictionary in the dict.cc export format, either the one embedded
+/// into the binary or a file given by the user.
+#[derive(Debug, Clone)]
+pub struct DictionarySource {
+    data: Cow<'static, [u8]>,
+    compression: Compression,
+    path: Option<PathBuf>,
+}
+
+impl DictionarySource {
+    pub fn embedded(data: &'static [u8]) -> Self {
+        Self {
+            compression: Compression::detect(data),
+            data: Cow::Borrowed(data),
+            path: None,
+        }
+    }
+
+    /// Takes a plain, gzip or zstd compressed dictionary handed over in memory.
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self {
+            compression: Compression::detect(&data),
+            data: Cow::Owned(data),
+            path: None,
+        }
+    }
+
+    /// Reads a plain, gzip or zstd compressed dictionary file.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let data = fs::read(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to read \"{}\": {}", path.display(), e),
+            )
+        })?;
+
+        Ok(Self {
+            compression: Compression::detect(&data),
+            data: Cow::Owned(data),
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// The file the dictionary was read from, `None` for the embedded one and those
+    /// handed over in memory.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Identifies the dictionary contents, see [`index::fingerprint`].
+    pub fn fingerprint(&self) -> u64 {
+        index::fingerprint(&self.data)
+    }
+
+    /// Opens the keyword index of the dictionary in `cache_dir`, built with
+    /// [`DictionarySource::build_index`].
+    pub fn open_index(&self, cache_dir: &Path) -> Result<Index, Unavailable> {
+        match Index::open(&index::index_dir(cache_dir, self.fingerprint())) {
+            Ok(Some(index)) => Ok(index),
+            Ok(None) if !index::outdated_dirs(cache_dir, self.fingerprint()).is_empty() => {
+                Err(Unavailable::Outdated)
+            }
+            Ok(None) => Err(Unavailable::Missing),
+            Err(e) => Err(Unavailable::Damaged(e)),
+        }
+    }
+
+    /// Builds the keyword index of the dictionary in `cache_dir`, replacing a damaged one
+    /// and removing those of older versions.
+    pub fn build_index(&self, cache_dir: &Path) -> io::Result<Index> {
+        for outdated in index::outdated_dirs(cache_dir, self.fingerprint()) {
+            fs::remove_dir_all(outdated)?;
+        }
+        let dir = index::index_dir(cache_dir, self.fingerprint());
+        match fs::remove_dir_all(&dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        Index::build(&dir, self.reader()?)
+    }
+
+    /// Returns a reader over the decompressed dictionary text.
+    pub fn reader(&self) -> io::Result<Box<dyn BufRead + '_>> {
+        let data = &self.data[..];
+
+        Ok(match self.compression {
+            Compression::None => Box::new(data),
+            Compression::Gzip => {
+                Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(data)))
+            }
+            Compression::Zstd => Box::new(BufReader::new(
+                zstd::stream::read::Decoder::with_buffer(data)?,
+            )),
+        })
+    }
+}
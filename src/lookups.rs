@@ -0,0 +1,53 @@
+//! A log of the German words the user looked up, stored as lines of
+//! "<unix timestamp>\t<word>".
+
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::store;
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Records a lookup of the word at the current time.
+pub fn append(path: &Path, word: &str) -> io::Result<()> {
+    store::append_line(path, &format!("{}\t{}", now(), word.trim()))
+}
+
+/// The distinct words looked up at or after the given timestamp, most recent first.
+/// A missing log yields no words.
+pub fn since(path: &Path, timestamp: u64) -> io::Result<Vec<String>> {
+    let log = match fs::read_to_string(path) {
+        Ok(log) => log,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut words: Vec<String> = vec![];
+    for line in log.lines().rev() {
+        let Some((time, word)) = line.split_once('\t') else {
+            continue;
+        };
+        match time.parse::<u64>() {
+            Ok(time) if time >= timestamp => {}
+            _ => continue,
+        }
+        if !words
+            .iter()
+            .any(|w| w.to_lowercase() == word.to_lowercase())
+        {
+            words.push(word.to_string());
+        }
+    }
+
+    Ok(words)
+}
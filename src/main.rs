@@ -1,132 +1,1960 @@
 use anyhow::anyhow;
 use std::{
-    collections::HashMap,
-    env,
-    io::{self, BufRead, BufReader, Write},
+    collections::{HashMap, HashSet},
+    env, fmt, fs,
+    io::{self, BufRead, IsTerminal, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{Once, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use dict_cc_lookup::{
-    entry::Term,
+    backup::Backup,
+    card::{self, Card},
+    cli,
+    config::Config,
+    console,
+    dictionary::{self, Deadline, LineReader, Lookup},
+    editor::LineEditor,
+    entry::{Entry, EntryId, Origin, PlaceholderStyle, RenderOptions, Term},
+    filter::Filter,
+    group,
+    index::{self, Index},
+    inflection::{self, Lemma},
+    json::{self, CardJson, EntryJson, GenderJson},
+    known::KnownWords,
     lexer,
-    query::{self, Language},
-    util,
+    loader::DictionarySource,
+    lookups,
+    paths::Paths,
+    present::{self, Details, Layout},
+    query::{self, Language, LanguageMode, NormalizeOptions, Verbosity},
+    search,
+    stats::Stats,
+    stopwords::Stopwords,
+    store::{self, Recovery},
+    text, tui, update, util, valency,
+    vocab::{CardFormat, Vocab},
+    web::server,
+    Dictionary,
 };
 
+const EMBEDDED_DICT: &[u8] = include_bytes!("dict.txt.zst");
+
 fn main() -> anyhow::Result<()> {
-    let dict = include_bytes!("dict.txt.zst");
-    let buf = BufReader::new(zstd::stream::read::Decoder::with_buffer(&dict[..])?);
+    console::init();
+
+    let mut args = env::args().skip(1).collect::<Vec<String>>();
+    #[cfg(unix)]
+    if forward_to_daemon(&args)? {
+        return Ok(());
+    }
+    let options = load_options(&mut args)?;
+    if let Some(color) = options.color {
+        console::set_ansi(color);
+    }
+    let res: Result<query::Query, _> = args.try_into();
+
+    let dict = match &options.dict {
+        Some(path) => DictionarySource::from_file(path)?,
+        None => installed_dictionary().unwrap_or_else(|| DictionarySource::embedded(EMBEDDED_DICT)),
+    };
+    let dict = &dict;
+    let buf = dict.reader()?;
+
+    match res {
+        Ok(query) => match query {
+            query::Query::Gender(word) => gender_command(&word, dict, &options),
+            query::Query::ReverseGender(word) => reverse_gender_command(&word, dict, &options),
+            query::Query::GenderBatch(path) => gender_batch_command(&path, buf),
+            query::Query::Batch(path) => batch_command(path.as_deref(), dict, &options),
+            query::Query::Meaning {
+                language,
+                components,
+                verbose,
+            } if !components.is_empty() => {
+                let word = &components.join(" ");
+                let language = match options.language {
+                    Some(LanguageMode::Fixed(language)) => language,
+                    Some(LanguageMode::Auto) => detect_language(word, dict),
+                    None => language,
+                };
+                match open_index(dict) {
+                    Some(mut index) => {
+                        let rd = LineReader::indexed(index.candidates(word, language)?);
+                        meaning_command(word, rd, dict, language, verbose, &options)
+                    }
+                    None => {
+                        let deadline = Deadline::after(options.timeout);
+                        let rd = LineReader::new(buf).with_deadline(deadline.clone());
+                        meaning_command(word, rd, dict, language, verbose, &options)?;
+                        if deadline.hit() {
+                            eprintln!("truncated: the search timed out, results may be missing");
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            query::Query::Fuzzy { language, word } => {
+                fuzzy_command(&word, language, dict, &options)
+            }
+            query::Query::Interactive {
+                language: query::Language::German,
+            } => interactive_command(dict, &options),
+            query::Query::Browse => browse_command(buf, &options),
+            query::Query::Known(action) => known_command(action),
+            query::Query::Vocab(action) => vocab_command(action, &options),
+            query::Query::List(mut list) => {
+                // The options take `--limit` for all queries.
+                list.limit = options.limit.unwrap_or(list.limit);
+                list_command(&list, dict)
+            }
+            query::Query::Near(word) => near_command(&word, dict),
+            query::Query::Related { word, limit } => related_command(&word, limit, dict),
+            query::Query::Phrases(word) => phrases_command(&word, dict, &options),
+            query::Query::Stats { analyses } => stats_command(analyses, buf, &options),
+            query::Query::Compare(first, second) => {
+                compare_command(&first, &second, dict, &options)
+            }
+            query::Query::Review => review_command(&options),
+            query::Query::ReviewStats => review_stats_command(),
+            query::Query::ReviewDue(output) => review_due_command(output),
+            query::Query::QuizRecent { days } => quiz_recent_command(days, dict, &options),
+            query::Query::Session { text, minutes } => {
+                session_command(&text, minutes, dict, &options)
+            }
+            query::Query::Paths => paths_command(),
+            query::Query::Completions(shell) => {
+                print!("{}", cli::script(shell, env!("CARGO_PKG_NAME")));
+                Ok(())
+            }
+            query::Query::CompleteWords => complete_words_command(),
+            query::Query::Explain(args) => explain_command(args, &options),
+            query::Query::Backup(action) => backup_command(action),
+            #[cfg(unix)]
+            query::Query::Daemon(action) => daemon_command(action, buf),
+            query::Query::Show(query::ShowTarget::Id(id)) => {
+                show_command(id, LineReader::new(buf), &options)
+            }
+            query::Query::Show(query::ShowTarget::Word(word)) => {
+                show_word_command(&word, dict, &options)
+            }
+            query::Query::Serve(addr) => serve_command(addr.as_deref(), buf, &options),
+            query::Query::BuildIndex => build_index(dict).map(|_| ()),
+            query::Query::Update(source) => update_command(&source),
+            query::Query::Export { filters, out } => export_command(filters, &out, dict),
+            query::Query::Merge { inputs, out } => merge_command(&inputs, &out),
+            _ => Err(anyhow!("unsupported query")),
+        },
+        Err(_) => {
+            if cfg!(debug_assertions) {
+                lex_command(buf, &options)
+            } else {
+                interactive_command(dict, &options)
+            }
+        }
+    }
+}
+
+/// Removes the flags from the arguments and completes them with the config file.
+fn load_options(args: &mut Vec<String>) -> anyhow::Result<query::Options> {
+    let mut options = query::Options::extract(args)?;
+    if let Some(paths) = Paths::new() {
+        let config = Config::load(&paths.config_file())?;
+        options.weights = config.ranking;
+        options.block = config.block;
+        options.ignore_diacritics |= config.ignore_diacritics;
+        options.saved_grammar = config.saved_grammar;
+        // Setting the paths was how these were turned on before the config had them.
+        options.record_sessions =
+            config.history_sessions || env::var_os("DICT_CC_HISTORY").is_some();
+        options.seed_known_words =
+            config.history_known_words || env::var_os("DICT_CC_KNOWN_WORDS").is_some();
+
+        // Flags override the defaults of the config.
+        if let Some(format) = options.format.or(config.format) {
+            options.set_format(format);
+        }
+        options.limit = options.limit.or(config.limit);
+        options.color = options.color.or(config.color);
+        options.placeholders = options.placeholders.or(config.placeholders);
+        options.verbosity = options.verbosity.or(config.verbosity);
+        options.variants = options.variants.or(config.variants);
+        options.dict = options.dict.take().or(config.dictionary);
+        // The default language only replaces the German of lookups without a specifier.
+        if args.len() == 1 {
+            options.language = options.language.or(config.language);
+        }
+    }
+    util::set_ignore_diacritics(options.ignore_diacritics);
+    for name in &options.show {
+        if !options.block.iter().any(|r| &r.name == name) {
+            return Err(anyhow!("there is no blocking rule \"{}\"", name));
+        }
+    }
+    options.block.retain(|r| !options.show.contains(&r.name));
+    if options.pronunciation && !cfg!(feature = "pronunciation") {
+        return Err(anyhow!(
+            "--pron needs a build with the \"pronunciation\" feature"
+        ));
+    }
+
+    Ok(options)
+}
+
+/// Identifies this build for the daemon: the version, the embedded dictionary and when
+/// the executable was built or installed.
+#[cfg(unix)]
+fn build_id() -> String {
+    let modified = env::current_exe()
+        .and_then(fs::metadata)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format!(
+        "{}-{}-{}",
+        env!("CARGO_PKG_VERSION"),
+        EMBEDDED_DICT.len(),
+        modified
+    )
+}
+
+/// Lets the daemon answer the command line if it runs, starting it for the next time
+/// if it doesn't and the config says so. Returns whether the daemon answered.
+#[cfg(unix)]
+fn forward_to_daemon(args: &[String]) -> anyhow::Result<bool> {
+    use dict_cc_lookup::daemon::{self, Reply, Request};
+    use std::process::{Command, Stdio};
+
+    let Some(paths) = Paths::new() else {
+        return Ok(false);
+    };
+    if args.first().is_some_and(|a| a == "daemon") {
+        return Ok(false);
+    }
+
+    let request = Request::Query {
+        build: build_id(),
+        args: args.to_vec(),
+        ansi: console::supports_ansi(),
+    };
+    match daemon::forward(&paths.socket(), &request) {
+        Ok(Reply::Answered(output)) => {
+            io::stdout().write_all(&output)?;
+            return Ok(true);
+        }
+        Ok(Reply::Failed(message, output)) => {
+            io::stdout().write_all(&output)?;
+            return Err(anyhow!(message));
+        }
+        Ok(Reply::Declined) => return Ok(false),
+        Ok(Reply::Outdated) | Err(_) => {}
+    }
+
+    let autostart = Config::load(&paths.config_file()).is_ok_and(|c| c.daemon_autostart);
+    if autostart {
+        // This lookup doesn't wait for the daemon to load the dictionary.
+        Command::new(env::current_exe()?)
+            .arg("daemon")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+    }
+    Ok(false)
+}
+
+#[cfg(unix)]
+fn daemon_command(action: query::DaemonAction, rd: impl BufRead) -> anyhow::Result<()> {
+    use dict_cc_lookup::daemon::{self, Answer, Request, Resident};
+    use std::os::unix::net::UnixListener;
+
+    let socket = paths()?.socket();
+    if action == query::DaemonAction::Stop {
+        daemon::forward(&socket, &Request::Stop)
+            .map_err(|_| anyhow!("the daemon isn't running"))?;
+        eprintln!("stopped the daemon");
+        return Ok(());
+    }
+
+    let Some(_lock) = daemon::lock(&socket)? else {
+        return Err(anyhow!("the daemon is already running"));
+    };
+    // Holding the lock, any socket is left behind by a daemon which didn't exit cleanly.
+    match fs::remove_file(&socket) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    let listener = UnixListener::bind(&socket)
+        .map_err(|e| anyhow!("failed to listen on {}: {}", socket.display(), e))?;
+    // Clients connecting while the dictionary loads wait for it.
+    let resident = Resident::new(Dictionary::load(rd)?);
+    eprintln!("listening on {}", socket.display());
+
+    let result = daemon::serve(
+        &listener,
+        &build_id(),
+        |args, ansi, out| {
+            let mut args = args.to_vec();
+            let options = load_options(&mut args)?;
+            // The daemon only has the embedded dictionary and can't ask the client's
+            // terminal anything.
+            if options.dict.is_some()
+                || !custom_dictionary_paths().is_empty()
+                || options.ndjson
+                || options.page
+                || options.language == Some(LanguageMode::Auto)
+            {
+                return Ok(Answer::Declined);
+            }
+            console::set_ansi(options.color.unwrap_or(ansi));
+
+            match args.try_into() {
+                Ok(query::Query::Meaning {
+                    language,
+                    components,
+                    verbose: false,
+                }) if !components.is_empty() => {
+                    let word = components.join(" ");
+                    let language = match options.language {
+                        Some(LanguageMode::Fixed(language)) => language,
+                        _ => language,
+                    };
+                    let dict = Dictionary::from_entries(resident.candidates(&word, language));
+                    let found = write_meanings(&mut *out, &dict, &word, language, &options)?;
+                    if found == 0 && language == Language::German && !options.json {
+                        let lemmas = inflection::lemmas(&word).into_iter().map(|lemma| {
+                            let entries = resident.lookup(&lemma.word, language);
+                            (lemma, Dictionary::from_entries(entries))
+                        });
+                        write_lemma_meanings(out, lemmas, &options)?;
+                    }
+                }
+                Ok(query::Query::Gender(word)) => {
+                    let dict = Dictionary::from_entries(resident.lookup(&word, Language::German));
+                    let plurals = dict
+                        .plural_candidates(&word)
+                        .iter()
+                        .filter_map(|c| c.split_whitespace().last())
+                        .flat_map(|last| resident.lookup(last, Language::English))
+                        .collect();
+                    let plurals = Dictionary::from_entries(plurals);
+                    write_genders(out, &dict, &plurals, &word, &options)?;
+                }
+                _ => return Ok(Answer::Declined),
+            }
+            Ok(Answer::Handled)
+        },
+        |e| eprintln!("failed to answer request: {}", e),
+    );
+
+    fs::remove_file(&socket)?;
+    Ok(result?)
+}
+
+fn paths() -> anyhow::Result<Paths> {
+    let paths = Paths::new().ok_or_else(|| anyhow!("failed to determine the home directory"))?;
+    let vocab_file = Config::load(&paths.config_file())?.vocab_file;
+    Ok(paths.with_vocab_file(vocab_file))
+}
+
+fn serve_command(
+    addr: Option<&str>,
+    rd: impl BufRead,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let addr = addr.unwrap_or(server::DEFAULT_ADDRESS);
+    let dict = Dictionary::load(rd)?;
+    let listener =
+        TcpListener::bind(addr).map_err(|e| anyhow!("failed to listen on {}: {}", addr, e))?;
+
+    eprintln!("listening on http://{}", listener.local_addr()?);
+    server::serve(&listener, &dict, options, |e| {
+        eprintln!("failed to answer request: {}", e)
+    })?;
+
+    Ok(())
+}
+
+/// Prints the saved German words for completion scripts, one per line. Phrases are left
+/// out, shells would split them.
+fn complete_words_command() -> anyhow::Result<()> {
+    let vocab = Vocab::load(&paths()?.vocab())?;
+    let mut stdout = io::stdout().lock();
+    for entry in vocab.entries() {
+        if !entry.german.contains(char::is_whitespace) {
+            writeln!(stdout, "{}", entry.german)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints which command the arguments stand for and the query parsed from them. Flags
+/// are taken out before, like for every query.
+fn explain_command(args: Vec<String>, options: &query::Options) -> anyhow::Result<()> {
+    let Some(first) = args.first() else {
+        return Err(anyhow!("usage: explain ARGS..."));
+    };
+    match cli::command_of(first).filter(|c| args.len() > 1 || c.standalone) {
+        Some(command) => {
+            println!("command:   {} ({})", first, command.about);
+            println!("usage:     {} {}", command.names[0], command.usage);
+        }
+        None => println!("command:   none, the arguments are looked up"),
+    }
+
+    match query::Query::try_from(args) {
+        Ok(query) => {
+            println!("query:     {:?}", query);
+            if let query::Query::Meaning { .. } = query {
+                match options.language {
+                    Some(LanguageMode::Fixed(language)) => {
+                        println!("language:  {:?}, as --lang or the config says", language)
+                    }
+                    Some(LanguageMode::Auto) => {
+                        println!("language:  detected, as --lang or the config says")
+                    }
+                    None => {}
+                }
+            }
+        }
+        Err(e) => println!("error:     {}", e),
+    }
+    Ok(())
+}
+
+fn paths_command() -> anyhow::Result<()> {
+    for (name, path) in paths()?.list() {
+        println!("{: <16}{}", name, path.display());
+    }
+
+    Ok(())
+}
+
+/// Opens the keyword index of the dictionary. Returns `None` if there is no usable
+/// index, in which case the dictionary has to be scanned. The index is then built in the
+/// background for the next lookups, or right away if the config says to wait for it.
+fn open_index(dict: &DictionarySource) -> Option<Index> {
+    // Commands open the index several times, they should only say once that it's missing.
+    static FALLBACK: Once = Once::new();
+
+    let paths = Paths::new()?;
+    let problem = match dict.open_index(paths.cache_dir()) {
+        Ok(index) => return Some(index),
+        Err(problem) => problem,
+    };
+
+    if Config::load(&paths.config_file()).is_ok_and(|c| c.wait_for_index) {
+        eprintln!("{}, building it...", problem);
+        return build_index(dict)
+            .inspect_err(|e| eprintln!("failed to build the index: {}", e))
+            .ok();
+    }
+
+    FALLBACK.call_once(|| {
+        if index::is_building(&index::index_dir(paths.cache_dir(), dict.fingerprint())) {
+            eprintln!("the dictionary index is still being built, searching without it");
+            return;
+        }
+        eprintln!("{}, searching without it while it is built", problem);
+        if let Err(e) = spawn_index_build(dict) {
+            eprintln!("failed to start building the index: {}", e);
+        }
+    });
+    None
+}
+
+/// Builds the index of the dictionary in the cache directory.
+fn build_index(dict: &DictionarySource) -> anyhow::Result<Index> {
+    Ok(dict.build_index(paths()?.cache_dir())?)
+}
+
+/// The dictionary installed with `update`, if there is one.
+fn installed_dictionary() -> Option<DictionarySource> {
+    let path = Paths::new()?.dictionary();
+    match DictionarySource::from_file(&path) {
+        Ok(source) => Some(source),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("using the embedded dictionary: {}", e);
+            None
+        }
+    }
+}
+
+fn update_command(source: &str) -> anyhow::Result<()> {
+    eprintln!("reading {}...", source);
+    let text = update::extract(&update::fetch(source)?)?;
+    let summary = update::validate(&text)?;
+
+    let path = paths()?.dictionary();
+    update::install(&text[..], &path)?;
+    eprintln!(
+        "installed {} entries to {} ({} lines skipped)",
+        summary.entries,
+        path.display(),
+        summary.skipped
+    );
+
+    // A running daemon still has the old dictionary.
+    #[cfg(unix)]
+    {
+        use dict_cc_lookup::daemon::{self, Request};
+        if daemon::forward(&paths()?.socket(), &Request::Stop).is_ok() {
+            eprintln!("stopped the daemon");
+        }
+    }
+
+    eprintln!("building the index...");
+    build_index(&DictionarySource::from_file(&path)?)?;
+
+    Ok(())
+}
+
+/// Runs `index build` in a process of its own, which outlives this one.
+fn spawn_index_build(dict: &DictionarySource) -> io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut command = Command::new(env::current_exe()?);
+    if let Some(path) = dict.path() {
+        command.arg("--dict").arg(path);
+    }
+    command
+        .args(["index", "build"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Detects the query language, consulting the index if there is one.
+fn detect_language(input: &str, dict: &DictionarySource) -> Language {
+    // Plenty of English words occur in a few German entries as loanwords or in names,
+    // so only words occurring in many entries count as German.
+    const MIN_GERMAN_FREQUENCY: usize = 20;
+
+    let index = open_index(dict);
+    let (language, _) = query::detect_language_with(input, |word| {
+        index
+            .as_ref()
+            .filter(|index| index.frequency(word, Language::German) >= MIN_GERMAN_FREQUENCY)
+            .map(|_| Language::German)
+    });
+    language
+}
+
+fn gender_command(
+    word: &str,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let mut index = open_index(source);
+    let dict = Dictionary::open(source, index.as_mut(), word, Language::German)?;
+    if dict.genders(word).is_empty() {
+        return Err(anyhow!("not found"));
+    }
+
+    let plurals = dict.open_plurals(source, index.as_mut(), word)?;
+    write_genders(io::stdout().lock(), &dict, &plurals, word, options)
+}
+
+/// Writes the genders of the German noun with its translations and plurals, the latter
+/// looked up in `plurals`.
+fn write_genders(
+    mut wr: impl Write,
+    dict: &Dictionary,
+    plurals: &Dictionary,
+    word: &str,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let nouns = dict.nouns(plurals, word);
+    if nouns.is_empty() {
+        return Err(anyhow!("not found"));
+    }
+
+    if options.json {
+        let genders = json::genders(dict, plurals, word);
+        writeln!(wr, "{}", serde_json::to_string(&genders)?)?;
+        return Ok(());
+    }
+
+    for noun in &nouns {
+        writeln!(wr, "{}", present::noun(noun))?;
+    }
+    Ok(())
+}
+
+/// Prints the German nouns exactly translating the English word, with their articles.
+fn reverse_gender_command(
+    word: &str,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let dict = Dictionary::open(source, open_index(source).as_mut(), word, Language::English)?;
+    let nouns = dict.german_nouns(word, &options.weights);
+    if nouns.is_empty() {
+        return Err(anyhow!("not found"));
+    }
+
+    if options.json {
+        let json: Vec<_> = nouns
+            .iter()
+            .map(|e| {
+                let keywords: Vec<_> = e.german.keywords().collect();
+                GenderJson {
+                    translations: vec![e.english.to_string()],
+                    ..GenderJson::new(&keywords.join(" "), &e.german)
+                }
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&json)?);
+        return Ok(());
+    }
+
+    for entry in &nouns {
+        println!("{}", present::with_article(&entry.german));
+    }
+
+    Ok(())
+}
+
+fn gender_batch_command(path: &Path, rd: impl BufRead) -> anyhow::Result<()> {
+    let input = if path == Path::new("-") {
+        io::read_to_string(io::stdin())?
+    } else {
+        fs::read_to_string(path)?
+    };
+    let stopwords = load_stopwords()?;
+    let words: Vec<String> = input
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !stopwords.contains(l))
+        .filter_map(query::to_upper)
+        .collect();
+    // Words in all caps are wanted in the dictionary's casing.
+    let wanted: HashSet<String> = words
+        .iter()
+        .flat_map(|w| util::casing_variants(w))
+        .collect();
+
+    // Only the lines starting with a wanted word and the plural entries, which are
+    // paired with the singulars through their translations, are parsed.
+    let dict = Dictionary::load_filtered(LineReader::new(rd), |line| {
+        let german = line.split('\t').next().unwrap_or_default();
+        german.contains("{pl")
+            || german
+                .split_whitespace()
+                .next()
+                .is_some_and(|w| wanted.contains(w))
+    })?;
+
+    let mut stdout = io::stdout().lock();
+    for row in present::gender_rows(&dict, &input, &stopwords) {
+        writeln!(stdout, "{}", row)?;
+    }
+
+    Ok(())
+}
+
+/// Looks up every line of the input, scanning each dictionary once for all of them. The
+/// lines are written with their translations as tab separated values, one line each so
+/// that they line up with the input, or as JSON.
+fn batch_command(
+    path: Option<&Path>,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let input = match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read \"{}\": {}", path.display(), e))?,
+        None => io::read_to_string(io::stdin())?,
+    };
+    let words: Vec<&str> = input
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    // A reading list is in one language.
+    let language = match options.language {
+        Some(LanguageMode::Fixed(language)) => language,
+        Some(LanguageMode::Auto) => detect_language(&words.join(" "), source),
+        None => Language::German,
+    };
+    let normalize = NormalizeOptions::for_language(language);
+    let queries: Vec<String> = words
+        .iter()
+        .map(|w| query::normalize(w, &normalize))
+        .collect();
+
+    let mut dicts = Dictionary::load_batch(LineReader::new(source.reader()?), &queries, language)?;
+    for custom in custom_dictionaries() {
+        let rd = LineReader::new(custom.reader()?).with_origin(Origin::Custom);
+        for (dict, custom) in dicts
+            .iter_mut()
+            .zip(Dictionary::load_batch(rd, &queries, language)?)
+        {
+            dict.append(custom);
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    let mut results = vec![];
+    for ((word, query), dict) in words.iter().zip(&queries).zip(&dicts) {
+        let (_, matches) = dict.meanings(query, language, options);
+        let entries: Vec<_> = matches
+            .into_iter()
+            .take(options.limit.unwrap_or(usize::MAX))
+            .map(|m| m.entry)
+            .collect();
+
+        let result = json::BatchResult {
+            query: word.to_string(),
+            results: entries.iter().map(|&e| e.into()).collect(),
+        };
+        if options.ndjson {
+            writeln!(stdout, "{}", serde_json::to_string(&result)?)?;
+        } else if options.json {
+            results.push(result);
+        } else {
+            let translations = entries.iter().map(|e| match language {
+                Language::German => &e.english,
+                Language::English => &e.german,
+            });
+            let translations = group::collapse(translations, options.render_options());
+            writeln!(stdout, "{}\t{}", word, translations.join("; "))?;
+        }
+    }
+    if options.json {
+        writeln!(stdout, "{}", serde_json::to_string(&results)?)?;
+    }
+
+    Ok(())
+}
+
+fn known_command(action: query::KnownAction) -> anyhow::Result<()> {
+    let path = paths()?.known_words();
+    let path = path.as_path();
+    let mut known = KnownWords::load(path)?;
+
+    match action {
+        query::KnownAction::List => {
+            for word in known.iter() {
+                println!("{}", word);
+            }
+        }
+        query::KnownAction::Add(words) => {
+            for word in &words {
+                known.insert(word);
+            }
+            known.save(path)?;
+        }
+        query::KnownAction::Remove(words) => {
+            for word in &words {
+                known.remove(word);
+            }
+            known.save(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn log_lookup(word: &str) {
+    if let Some(path) = Paths::new().map(|p| p.lookups()) {
+        if let Err(e) = lookups::append(&path, word) {
+            eprintln!("failed to log lookup: {}", e);
+        }
+    }
+}
+
+/// Asks for the translations of the recently looked up words which aren't known or
+/// saved yet.
+fn quiz_recent_command(
+    days: u64,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let paths = paths()?;
+    let since = lookups::now().saturating_sub(days * 24 * 60 * 60);
+    let known = KnownWords::load(&paths.known_words())?;
+    let vocab = Vocab::load(&paths.vocab())?;
+    let mut words: Vec<_> = lookups::since(&paths.lookups(), since)?
+        .into_iter()
+        .filter(|w| !known.contains(w) && !vocab.contains_word(w))
+        .collect();
+    if words.is_empty() {
+        println!(
+            "no words looked up in the last {} days left to practice",
+            days
+        );
+        return Ok(());
+    }
+    shuffle(&mut words);
+
+    let mut lookup = Lookup::new(source, open_index(source))?;
+    let mut editor = LineEditor::new(None);
+    let (mut asked, mut correct) = (0, 0);
+
+    println!("Translate into English (empty line to skip, Ctrl-D to stop):");
+    for word in &words {
+        let entries = lookup.exact_matches(word, Language::German, &options.weights)?;
+        if entries.is_empty() {
+            continue;
+        }
+
+        let Some(answer) = editor.read_line(&format!("{}: ", entries[0].german))? else {
+            break;
+        };
+        let answer = query::normalize(&answer, &NormalizeOptions::for_language(Language::English));
+        asked += 1;
+
+        let mut solutions: Vec<_> = entries.iter().map(|e| e.english.to_string()).collect();
+        let mut seen = HashSet::new();
+        solutions.retain(|s| seen.insert(s.clone()));
+        let solutions = solutions.join(", ");
+        if !answer.is_empty()
+            && entries
+                .iter()
+                .any(|e| dictionary::is_exact(&e.english, &answer, Language::English))
+        {
+            correct += 1;
+            println!("correct: {}", solutions);
+        } else {
+            println!("solution: {}", solutions);
+        }
+    }
+
+    println!("{} of {} correct", correct, asked);
+
+    Ok(())
+}
+
+/// Reads a text sentence by sentence, glossing the words which aren't known yet. The
+/// glossed words can be saved, which puts them up for review like the words saved in
+/// interactive mode, or marked as known, so that they aren't glossed anymore.
+fn session_command(
+    path: &Path,
+    minutes: Option<u64>,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+    let known_path = paths()?.known_words();
+    let mut known = KnownWords::load(&known_path)?;
+    let stopwords = load_stopwords()?;
+    let end = minutes.map(|m| Instant::now() + Duration::from_secs(m * 60));
+
+    let mut lookup = Lookup::new(source, open_index(source))?;
+    let mut editor = LineEditor::new(None);
+    let mut saved_words = HashMap::new();
+
+    let sentences = text::sentences(&text);
+    println!(
+        "Save glossed words by their numbers, mark them as known with \"k\" and the \
+         numbers, go on with an empty line, stop with Ctrl-D:"
+    );
+    'sentences: for (i, sentence) in sentences.iter().enumerate() {
+        if end.is_some_and(|end| Instant::now() >= end) {
+            println!("time is up");
+            break;
+        }
+
+        let mut seen = HashSet::new();
+        let mut glosses: Vec<(&str, Vec<Entry>)> = vec![];
+        for word in text::words(sentence) {
+            if known.contains(word) || stopwords.contains(word) || !seen.insert(word.to_lowercase())
+            {
+                continue;
+            }
+            let entries = lookup.exact_matches(word, Language::German, &options.weights)?;
+            if !entries.is_empty() {
+                glosses.push((word, entries));
+            }
+        }
+
+        println!("\n[{}/{}] {}", i + 1, sentences.len(), sentence);
+        if glosses.is_empty() {
+            continue;
+        }
+        let render = options.render_options();
+        for (n, (_, entries)) in glosses.iter().enumerate() {
+            println!("{: >3}. {}", n, present::gloss(entries, render));
+        }
+
+        loop {
+            let Some(line) = editor.read_line("> ")? else {
+                break 'sentences;
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+
+            let (mark_known, numbers) = match line.strip_prefix('k') {
+                Some(numbers) => (true, numbers),
+                None => (false, line),
+            };
+            let Some(selection) = query::parse_selection(numbers, glosses.len()) else {
+                println!("expected the numbers of glossed words");
+                continue;
+            };
+            for n in selection {
+                match glosses.get(n) {
+                    Some((word, _)) if mark_known => {
+                        known.insert(word);
+                    }
+                    Some((_, entries)) => save_entry(&mut saved_words, entries[0].clone()),
+                    None => println!("there is no word number {}", n),
+                }
+            }
+        }
+    }
+
+    known.save(&known_path)?;
+    println!();
+    finish_session(io::stdout().lock(), saved_words, options)?;
+
+    Ok(())
+}
+
+/// Shuffles the words using the clock as the source of randomness, which is good
+/// enough to vary the order between quizzes.
+fn shuffle<T>(items: &mut [T]) {
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+        | 1;
+    for i in (1..items.len()).rev() {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+fn vocab_command(action: query::VocabAction, options: &query::Options) -> anyhow::Result<()> {
+    let path = paths()?.vocab();
+    let mut vocab = load_vocab(&path)?;
+
+    match action {
+        query::VocabAction::List => {
+            for entry in vocab.entries() {
+                println!("{} = {}", entry.german, entry.english.join(", "));
+            }
+        }
+        query::VocabAction::Clear => {
+            let count = vocab.entries().len();
+            vocab.clear();
+            vocab.save(&path)?;
+            println!("removed {} words", count);
+        }
+        query::VocabAction::Export { out, per_sense } => {
+            let file = io::BufWriter::new(fs::File::create(&out)?);
+            let count = vocab.write_cards(
+                file,
+                CardFormat::from_path(&out),
+                options.saved_grammar,
+                per_sense,
+                options.placeholders.unwrap_or_default(),
+            )?;
+            eprintln!("exported {} cards to {}", count, out.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn backup_command(action: query::BackupAction) -> anyhow::Result<()> {
+    let paths = paths()?;
+
+    match action {
+        query::BackupAction::Create(file) => {
+            let backup = Backup::create(&paths)?;
+            backup.save(&file)?;
+            eprintln!(
+                "backed up {} to {}",
+                backup.names().collect::<Vec<_>>().join(", "),
+                file.display()
+            );
+        }
+        query::BackupAction::Restore(file) => {
+            for path in Backup::load(&file)?.restore(&paths)? {
+                eprintln!("restored {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the German headwords matching the conditions, one per line.
+fn list_command(list: &query::WordList, source: &DictionarySource) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut seen = HashSet::new();
+
+    // The index lists the keywords, so that only the lines of matching ones are read.
+    let Some(mut index) = open_index(source) else {
+        let dict = Dictionary::load(source.reader()?)?;
+        for entry in dict.entries() {
+            let mut keywords = entry.german.keywords();
+            let (Some(keyword), None) = (keywords.next(), keywords.next()) else {
+                continue;
+            };
+            if seen.len() < list.limit
+                && list.keeps_keyword(&keyword.to_lowercase())
+                && list.keeps(entry, keyword)
+                && seen.insert(entry.german.to_string())
+            {
+                writeln!(stdout, "{}", entry.german)?;
+            }
+        }
+        return Ok(());
+    };
+
+    let keywords: Vec<_> = index
+        .keywords(Language::German)
+        .into_iter()
+        .map(|(k, _)| k)
+        .filter(|k| list.keeps_keyword(k))
+        .collect();
+    for keyword in &keywords {
+        let dict = Dictionary::open(source, Some(&mut index), keyword, Language::German)?;
+        for entry in dict.entries() {
+            if seen.len() == list.limit {
+                return Ok(());
+            }
+            if list.keeps(entry, keyword) && seen.insert(entry.german.to_string()) {
+                writeln!(stdout, "{}", entry.german)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the figures as text, or as JSON if asked to. The analyses are only written as
+/// JSON, there are too many figures to read them anyway.
+fn stats_command(
+    analyses: bool,
+    buf: impl BufRead,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let stats = Stats::new(&Dictionary::load(buf)?, analyses);
+    let mut stdout = io::stdout().lock();
+    if analyses || options.json {
+        serde_json::to_writer_pretty(&mut stdout, &stats)?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+
+    writeln!(stdout, "{} entries", stats.entries)?;
+    for (gender, count) in &stats.genders {
+        writeln!(stdout, "{:>8} {}", count, gender)?;
+    }
+    let mut classes: Vec<_> = stats.word_classes.iter().collect();
+    classes.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    for (class, count) in classes {
+        writeln!(stdout, "{:>8} {}", count, class)?;
+    }
+
+    Ok(())
+}
+
+/// How many headwords `near` shows on either side of the word.
+const NEIGHBORS: usize = 8;
+
+fn near_command(word: &str, source: &DictionarySource) -> anyhow::Result<()> {
+    let headwords: Vec<String> = match open_index(source) {
+        // The index only has the lowercased keywords, their spellings are in the entries
+        // of the few closest ones.
+        Some(mut index) => {
+            let keywords = index.keywords(Language::German);
+            let headwords = keywords.iter().map(|(k, _)| k.as_str());
+            let near = search::neighbors(
+                word,
+                headwords.filter(|k| search::is_headword(k)),
+                NEIGHBORS,
+            );
+            let closest: Vec<String> = near
+                .before
+                .into_iter()
+                .chain(near.found)
+                .chain(near.after)
+                .map(String::from)
+                .collect();
+
+            let mut headwords = HashSet::new();
+            for keyword in &closest {
+                let dict = Dictionary::open(source, Some(&mut index), keyword, Language::German)?;
+                for entry in dict.entries() {
+                    headwords.extend(
+                        entry
+                            .german
+                            .keywords()
+                            .filter(|k| k.to_lowercase() == *keyword)
+                            .map(String::from),
+                    );
+                }
+            }
+            headwords.into_iter().collect()
+        }
+        None => {
+            let dict = Dictionary::load(source.reader()?)?;
+            let headwords: HashSet<_> = dict
+                .entries()
+                .iter()
+                .flat_map(|e| e.german.keywords())
+                .filter(|k| search::is_headword(k))
+                .collect();
+            headwords.into_iter().map(String::from).collect()
+        }
+    };
+
+    let near = search::neighbors(word, headwords.iter().map(String::as_str), NEIGHBORS);
+    let mut stdout = io::stdout().lock();
+    for headword in near.before {
+        writeln!(stdout, "  {}", headword)?;
+    }
+    match near.found {
+        Some(headword) if console::supports_ansi() => {
+            writeln!(stdout, "> \x1b[1m{}\x1b[0m", headword)?
+        }
+        Some(headword) => writeln!(stdout, "> {}", headword)?,
+        None => writeln!(stdout, "> ({} is not in the dictionary)", word)?,
+    }
+    for headword in near.after {
+        writeln!(stdout, "  {}", headword)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the phrases and idioms containing the word or phrase, best first, to show how
+/// it is used.
+fn phrases_command(
+    word: &str,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let dict = Dictionary::open(source, open_index(source).as_mut(), word, Language::German)?;
+    let matches: Vec<_> = dict
+        .phrases(word, &options.weights)
+        .into_iter()
+        .filter(|m| options.keeps(m.entry))
+        .collect();
+    if matches.is_empty() {
+        return Err(anyhow!("not found"));
+    }
+
+    let mut stdout = io::stdout().lock();
+    if options.json {
+        let entries: Vec<_> = matches.iter().map(|m| EntryJson::from(m.entry)).collect();
+        writeln!(stdout, "{}", serde_json::to_string(&entries)?)?;
+        return Ok(());
+    }
+
+    let differences = group::differences(matches.iter().map(|m| m.entry));
+    let layout = layout(matches.iter().map(|m| m.entry), options);
+    for (m, differences) in matches.iter().zip(&differences) {
+        write_entry(&mut stdout, &layout, m.entry, differences, options)?;
+        if options.explain {
+            writeln!(stdout, "    score {}", m.score)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the headwords containing the word as a component, like "Hausaufgabe" and
+/// "Krankenhaus" for "Haus", grouped by where in them the word is.
+fn related_command(word: &str, limit: usize, source: &DictionarySource) -> anyhow::Result<()> {
+    let lowercase = word.to_lowercase();
+    let headings = [
+        format!("{}…", word),
+        format!("…{}", lowercase),
+        format!("…{}…", lowercase),
+    ];
+    let mut groups: Vec<Vec<String>> = vec![];
+
+    match open_index(source) {
+        // The index only has the keywords, whether they are headwords is in the entries.
+        Some(mut index) => {
+            let keywords: Vec<_> = index
+                .keywords(Language::German)
+                .into_iter()
+                .filter(|(k, _)| search::is_headword(k))
+                .collect();
+            let related = search::related(word, keywords.iter().map(|(k, f)| (k.as_str(), *f)));
+            for group in [related.starting, related.ending, related.inside] {
+                let mut lines = vec![];
+                for keyword in group {
+                    if lines.len() == limit {
+                        break;
+                    }
+                    let dict =
+                        Dictionary::open(source, Some(&mut index), keyword, Language::German)?;
+                    let entries: Vec<_> = dict
+                        .entries()
+                        .iter()
+                        .filter(|e| e.headword().is_some_and(|h| h.to_lowercase() == keyword))
+                        .collect();
+                    lines.extend(present::related_lines(&entries));
+                }
+                lines.truncate(limit);
+                groups.push(lines);
+            }
+        }
+        None => {
+            let dict = Dictionary::load(source.reader()?)?;
+            let mut headwords = HashMap::<String, Vec<&Entry>>::new();
+            for entry in dict.entries() {
+                if let Some(headword) = entry.headword().filter(|h| search::is_headword(h)) {
+                    headwords
+                        .entry(headword.to_string())
+                        .or_default()
+                        .push(entry);
+                }
+            }
+            let related =
+                search::related(word, headwords.iter().map(|(k, e)| (k.as_str(), e.len())));
+            for group in [related.starting, related.ending, related.inside] {
+                groups.push(
+                    group
+                        .into_iter()
+                        .flat_map(|k| present::related_lines(&headwords[k]))
+                        .take(limit)
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    if groups.iter().all(Vec::is_empty) {
+        return Err(anyhow!("not found"));
+    }
+    let mut stdout = io::stdout().lock();
+    for (heading, lines) in headings.iter().zip(groups) {
+        if lines.is_empty() {
+            continue;
+        }
+        writeln!(stdout, "{}", heading)?;
+        for line in lines {
+            writeln!(stdout, "  {}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn compare_command(
+    first: &str,
+    second: &str,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let mut index = open_index(source);
+    let mut entries = |word: &str| -> anyhow::Result<Vec<Entry>> {
+        let dict = Dictionary::open(source, index.as_mut(), word, Language::German)?;
+        let entries: Vec<_> = dict
+            .exact_matches(word, Language::German, &options.weights)
+            .into_iter()
+            .cloned()
+            .collect();
+        if entries.is_empty() {
+            return Err(anyhow!("\"{}\" not found", word));
+        }
+        Ok(entries)
+    };
+    let comparison = group::compare(&entries(first)?, &entries(second)?);
+
+    let bold = |s: &str| {
+        if console::supports_ansi() {
+            format!("\x1b[1m{}\x1b[0m", s)
+        } else {
+            s.to_string()
+        }
+    };
+    println!("< {}\n> {}\n", first, second);
+    for translation in &comparison.shared {
+        println!("= {}", bold(translation));
+    }
+    for translation in &comparison.only_first {
+        println!("< {}", translation);
+    }
+    for translation in &comparison.only_second {
+        println!("> {}", translation);
+    }
+
+    Ok(())
+}
+
+/// Adds the given words to the known words, so that they aren't glossed anymore.
+fn seed_known_words<'a>(words: impl IntoIterator<Item = &'a str>) {
+    let Some(path) = Paths::new().map(|p| p.known_words()) else {
+        return;
+    };
+    let res = KnownWords::load(&path).and_then(|mut known| {
+        for word in words {
+            known.insert(word);
+        }
+        known.save(&path)
+    });
+    if let Err(e) = res {
+        eprintln!("failed to update known words: {}", e);
+    }
+}
+
+/// Quizzes the vocabulary cards which are due, asking for the German term.
+fn review_command(options: &query::Options) -> anyhow::Result<()> {
+    let path = paths()?.vocab();
+    let mut vocab = load_vocab(&path)?;
+    let mut due: Vec<_> = vocab
+        .due(lookups::now())
+        .map(|e| e.german.clone())
+        .collect();
+    if due.is_empty() {
+        println!("no words are due for review");
+        return Ok(());
+    }
+    shuffle(&mut due);
+
+    let mut editor = LineEditor::new(None);
+    let (mut asked, mut correct) = (0, 0);
+    let mut reviewed = Vec::new();
+
+    println!("Translate into German, with the article for nouns (Ctrl-D to stop):");
+    for german in &due {
+        let Some(entry) = vocab.get(german) else {
+            continue;
+        };
+        let (front, back) = entry.card(options.placeholders.unwrap_or_default());
+        let Some(answer) = editor.read_line(&format!("{}: ", back))? else {
+            break;
+        };
+        asked += 1;
+
+        let right = entry.check(&answer);
+        vocab.answer(german, right, lookups::now());
+        reviewed.extend(Term::parse(german));
+        if right {
+            correct += 1;
+            println!("correct");
+        } else {
+            println!("solution: {}", front);
+        }
+    }
+
+    vocab.save(&path)?;
+    if options.seed_known_words {
+        seed_known_words(reviewed.iter().flat_map(Term::keywords));
+    }
+    println!("{} of {} correct", correct, asked);
+
+    Ok(())
+}
+
+fn review_stats_command() -> anyhow::Result<()> {
+    const DAYS: u64 = 28;
+
+    let vocab = load_vocab(&paths()?.vocab())?;
+    let now = lookups::now();
+    let stats = vocab.stats(now, DAYS);
+    let accuracy = match stats.reviewed {
+        0 => "-".to_string(),
+        n => format!("{:.0}%", f64::from(stats.correct) * 100.0 / f64::from(n)),
+    };
+    let boxes: Vec<_> = stats.boxes.iter().map(|n| n.to_string()).collect();
+
+    println!("last {} days  {}", DAYS, present::sparkline(&stats.recent));
+    println!(
+        "reviews       {} ({} today)",
+        stats.reviewed,
+        stats.recent.last().unwrap_or(&0)
+    );
+    println!("accuracy      {}", accuracy);
+    println!(
+        "streak        {} days (longest {})",
+        stats.streak, stats.longest_streak
+    );
+    println!(
+        "cards         {} ({} due)",
+        vocab.entries().len(),
+        vocab.due(now).count()
+    );
+    println!("boxes         {}", boxes.join(" / "));
+
+    Ok(())
+}
+
+fn review_due_command(output: query::DueOutput) -> anyhow::Result<()> {
+    let vocab = load_vocab(&paths()?.vocab())?;
+    let due: Vec<_> = vocab.due(lookups::now()).collect();
+
+    match output {
+        query::DueOutput::List => {
+            for entry in &due {
+                println!("{} = {}", entry.german, entry.english.join(", "));
+            }
+        }
+        query::DueOutput::Count => println!("{}", due.len()),
+        query::DueOutput::Notify if due.is_empty() => {}
+        query::DueOutput::Notify => {
+            let message = format!("{} words are due for review", due.len());
+            if !notify(&message) {
+                println!("{}", message);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Shows a desktop notification. Returns false if that isn't possible.
+fn notify(message: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title \"dict-cc-lookup\"",
+            message
+        ));
+        command
+    } else {
+        let mut command = std::process::Command::new("notify-send");
+        command.arg("dict-cc-lookup").arg(message);
+        command
+    };
+
+    command.status().is_ok_and(|s| s.success())
+}
 
-    let res: Result<query::Query, _> = env::args().skip(1).collect::<Vec<String>>().try_into();
+fn load_stopwords() -> anyhow::Result<Stopwords> {
+    match Paths::new().map(|p| p.stopwords()).filter(|p| p.exists()) {
+        Some(path) => {
+            Stopwords::from_file(&path).map_err(|e| anyhow!("failed to read stopwords file: {}", e))
+        }
+        None => Ok(Stopwords::default()),
+    }
+}
 
-    match res {
-        Ok(query) => match query {
-            query::Query::Gender(word) => gender_command(&word, buf),
-            query::Query::Meaning {
-                language,
-                components,
-                verbose: false,
-            } if components.len() == 1 => {
-                meaning_command(&components[0], buf, language == Language::English)
+fn meaning_command(
+    word: &str,
+    rd: LineReader<impl BufRead>,
+    source: &DictionarySource,
+    language: Language,
+    verbose: bool,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let custom = || {
+        custom_dictionaries().iter().map(|source| -> io::Result<_> {
+            Ok(LineReader::new(source.reader()?).with_origin(Origin::Custom))
+        })
+    };
+
+    if options.ndjson {
+        let keep = |e: &Entry| options.keeps(e);
+        let limit = options.limit.unwrap_or(usize::MAX);
+        let mut written =
+            json::stream_matches(rd, word, language, keep, limit, io::stdout().lock())?;
+        for rd in custom() {
+            let limit = limit - written;
+            written += json::stream_matches(rd?, word, language, keep, limit, io::stdout().lock())?;
+        }
+        return Ok(());
+    }
+    if verbose && !options.json {
+        let mut frames = vec![];
+        let mut written = verbose_meaning_command(word, rd, language, options, 0, &mut frames)?;
+        for rd in custom() {
+            written = verbose_meaning_command(word, rd?, language, options, written, &mut frames)?;
+        }
+        if let Some(valency) = valency::valency(word, &frames) {
+            present::write_valency(io::stdout().lock(), word, &valency)?;
+        }
+        return Ok(());
+    }
+
+    let mut dict = Dictionary::load_candidates(rd, word, language)?;
+    for rd in custom() {
+        dict.append(Dictionary::load_candidates(rd?, word, language)?);
+    }
+    let mut stdout = io::stdout().lock();
+    let found = write_meanings(&mut stdout, &dict, word, language, options)?;
+    if found == 0 && language == Language::German && !options.json {
+        let lemmas = inflection::lemmas(word);
+        let words: Vec<_> = lemmas.iter().map(|l| l.word.clone()).collect();
+        let dicts = Dictionary::open_batch(source, open_index(source).as_mut(), &words, language)?;
+        write_lemma_meanings(stdout, lemmas.into_iter().zip(dicts), options)?;
+    }
+    Ok(())
+}
+
+/// Writes the meanings of the first dictionary form of an inflected German word which
+/// has any, given the candidates with their dictionaries, labelled "from: gehen".
+fn write_lemma_meanings(
+    mut wr: impl Write,
+    lemmas: impl IntoIterator<Item = (Lemma, Dictionary)>,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let found = lemmas.into_iter().find(|(lemma, dict)| {
+        dict.lookup(&lemma.word, Language::German)
+            .iter()
+            .any(|e| lemma.fits(e))
+    });
+    if let Some((lemma, dict)) = found {
+        writeln!(wr, "from: {}", lemma.word)?;
+        write_meanings(wr, &dict, &lemma.word, Language::German, options)?;
+    }
+    Ok(())
+}
+
+/// The files in the dictionaries directory, which lookups search besides the main
+/// dictionary.
+fn custom_dictionary_paths() -> Vec<PathBuf> {
+    let Some(dir) = Paths::new().map(|p| p.dictionaries_dir()) else {
+        return vec![];
+    };
+    let Ok(files) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut paths: Vec<_> = files
+        .filter_map(|f| f.ok())
+        .map(|f| f.path())
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The custom dictionaries, read once. Files which can't be read are skipped.
+fn custom_dictionaries() -> &'static [DictionarySource] {
+    static CUSTOM: OnceLock<Vec<DictionarySource>> = OnceLock::new();
+    CUSTOM.get_or_init(|| {
+        custom_dictionary_paths()
+            .iter()
+            .filter_map(|path| match DictionarySource::from_file(path) {
+                Ok(source) => Some(source),
+                Err(e) => {
+                    eprintln!("skipping custom dictionary: {}", e);
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+/// Writes the entries of the dictionary exactly matching the word, best first. Returns
+/// how many there are.
+fn write_meanings(
+    mut wr: impl Write,
+    dict: &Dictionary,
+    word: &str,
+    language: Language,
+    options: &query::Options,
+) -> anyhow::Result<usize> {
+    let (word, matches) = dict.meanings(word, language, options);
+    let limit = options.limit.unwrap_or(usize::MAX);
+
+    if options.json {
+        let entries: Vec<_> = matches
+            .iter()
+            .take(limit)
+            .map(|m| EntryJson::from(m.entry))
+            .collect();
+        writeln!(wr, "{}", serde_json::to_string(&entries)?)?;
+        return Ok(entries.len());
+    }
+
+    if language == Language::German && !matches.is_empty() {
+        log_lookup(&word);
+    }
+
+    match options.verbosity_for(matches.len()) {
+        Verbosity::Card => {
+            for m in matches.iter().take(limit) {
+                details(options).write(&mut wr, m.entry, None)?;
             }
-            query::Query::Interactive {
-                language: query::Language::German,
-            } => interactive_command(buf),
-            _ => Err(anyhow!("unsupported query")),
-        },
-        Err(_) => {
-            if cfg!(debug_assertions) {
-                lex_command(buf)
-            } else {
-                interactive_command(buf)
+            return Ok(matches.len());
+        }
+        Verbosity::Summary => {
+            let entries: Vec<_> = matches.iter().map(|m| m.entry).collect();
+            present::write_summary(wr, &entries, language, options.render_options())?;
+            return Ok(matches.len());
+        }
+        Verbosity::Auto | Verbosity::List => {}
+    }
+    // A line is left for the prompt.
+    let page = options
+        .page
+        .then(console::size)
+        .flatten()
+        .map(|(_, rows)| rows.saturating_sub(1).max(1));
+
+    let differences = group::differences(matches.iter().map(|m| m.entry));
+    let layout = layout(matches.iter().take(limit).map(|m| m.entry), options)
+        .with_sources(!custom_dictionaries().is_empty());
+    let (mut shown, mut lines) = (0, 0);
+    for (m, differences) in matches.iter().zip(&differences).take(limit) {
+        if page.is_some_and(|rows| lines >= rows) {
+            wr.flush()?;
+            if !console::wait_for_more()? {
+                break;
             }
+            lines = 0;
+        }
+
+        write_entry(&mut wr, &layout, m.entry, differences, options)?;
+        lines += 1;
+        if options.explain {
+            writeln!(wr, "    score {}", m.score)?;
+            lines += 1;
         }
+        shown += 1;
+    }
+    if (options.limit.is_some() || options.page) && !matches.is_empty() {
+        writeln!(wr, "{} of {} results", shown, matches.len())?;
     }
+
+    Ok(matches.len())
 }
 
-fn gender_command(word: &str, mut rd: impl BufRead) -> anyhow::Result<()> {
-    let mut buf = String::with_capacity(512);
+/// Looks up the keyword closest to the possibly misspelled word.
+fn fuzzy_command(
+    word: &str,
+    language: Language,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    const SUGGESTIONS: usize = 6;
 
-    loop {
-        buf.clear();
-        if rd.read_line(&mut buf)? == 0 {
-            return Err(anyhow!("not found"));
+    let (suggestions, dict) = match open_index(source) {
+        Some(mut index) => {
+            let keywords = index.keywords(language);
+            let suggestions = search::fuzzy(
+                word,
+                keywords.iter().map(|(k, f)| (k.as_str(), *f)),
+                SUGGESTIONS,
+            );
+            let dict = match suggestions.first() {
+                Some(best) => Dictionary::open(source, Some(&mut index), &best.keyword, language)?,
+                None => Dictionary::default(),
+            };
+            (suggestions, dict)
         }
+        None => {
+            let dict = Dictionary::load(source.reader()?)?;
+            let frequencies = search::keyword_frequencies(&dict, language);
+            let keywords = frequencies.iter().map(|(k, f)| (k.as_str(), *f));
+            (search::fuzzy(word, keywords, SUGGESTIONS), dict)
+        }
+    };
+
+    let (best, others) = suggestions
+        .split_first()
+        .ok_or_else(|| anyhow!("not found"))?;
+
+    if best.distance > 0 {
+        println!("showing results for \"{}\"", best.keyword);
+    }
+    let entries = dict.exact_matches(&best.keyword, language, &options.weights);
+    let differences = group::differences(entries.iter().copied());
+    let layout = layout(entries.iter().copied(), options);
+    for (entry, differences) in entries.iter().zip(&differences) {
+        print_entry(&layout, entry, differences, options);
+    }
+    if !others.is_empty() {
+        let others: Vec<_> = others.iter().map(|s| s.keyword.as_str()).collect();
+        println!("other suggestions: {}", others.join(", "));
+    }
+
+    Ok(())
+}
 
-        let Some(input) = buf.split('\t').next() else {
-            continue
+/// Like the terse meaning output, but also shows the raw dictionary line and everything
+/// the terse output leaves out. Returns the number of entries written, counting on from
+/// the ones `written` before, which count towards the limit. German verb frames of the
+/// word are collected into `frames` for its valency, regardless of the limit.
+fn verbose_meaning_command(
+    word: &str,
+    rd: LineReader<impl BufRead>,
+    language: Language,
+    options: &query::Options,
+    mut written: usize,
+    frames: &mut Vec<Entry>,
+) -> anyhow::Result<usize> {
+    let mut stdout = io::stdout().lock();
+    let mut lines = Dictionary::lines(rd, |line| dictionary::may_contain(line, word, language));
+
+    loop {
+        let full = options.limit.is_some_and(|l| written >= l);
+        if full && language != Language::German {
+            return Ok(written);
+        }
+        let Some(found) = lines.next() else {
+            return Ok(written);
         };
 
-        let mut parts = input.split_ascii_whitespace();
-        if !parts.any(|v| v == word) {
+        let (entry, line) = found?;
+        if !options.keeps(&entry) {
+            continue;
+        }
+        if language == Language::German && valency::is_frame(&entry, word) {
+            frames.push(entry.clone());
+        }
+        if full || !dictionary::is_exact(entry.term(language), word, language) {
             continue;
         }
 
-        let (gender_text, is_plural) = match input.split_ascii_whitespace().next_back().unwrap() {
-            "{m}" => ("der", false),
-            "{f}" => ("die", false),
-            "{n}" => ("das", false),
-            "{pl}" | "{pl.}" => ("die", true),
-            _ => continue,
-        };
+        details(options).write(&mut stdout, &entry, Some(&line))?;
+        written += 1;
+    }
+}
 
-        print!(
-            "{} {}{}",
-            gender_text,
-            word,
-            if is_plural { " (pl)" } else { "" }
-        );
+/// Prints the entries with the given ID. Usually there is one, unless the dictionary
+/// has the same line several times.
+fn show_command(
+    id: EntryId,
+    rd: LineReader<impl BufRead>,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut entries = vec![];
+    let mut found = 0;
 
+    for line in Dictionary::lines(rd, |_| true) {
+        let (entry, line) = line?;
+        if entry.id() != id {
+            continue;
+        }
+
+        if options.json {
+            entries.push(EntryJson::from(&entry));
+        } else {
+            details(options).write(&mut stdout, &entry, Some(&line))?;
+        }
+        found += 1;
+    }
+
+    if found == 0 {
+        return Err(anyhow!("there is no entry with the id {}", id));
+    }
+    if options.json {
+        writeln!(stdout, "{}", serde_json::to_string(&entries)?)?;
+    }
+
+    Ok(())
+}
+
+/// Prints the dictionary page of the German word: a card per sense, with the
+/// translations grouped by register and placeholders spelled out.
+fn show_word_command(
+    word: &str,
+    source: &DictionarySource,
+    options: &query::Options,
+) -> anyhow::Result<()> {
+    let mut index = open_index(source);
+    let dict = Dictionary::open(source, index.as_mut(), word, Language::German)?;
+    let cards = card::cards(&dict, word);
+    if cards.is_empty() {
+        return Err(anyhow!("not found"));
+    }
+
+    let plurals = dict.open_plurals(source, index.as_mut(), word)?;
+    let plural_form = |card: &Card| {
+        card.gender
+            .and_then(|_| plurals.plural_of(word, &card.entries().collect::<Vec<_>>()))
+            .and_then(|t| t.primary_keyword().map(String::from))
+    };
+
+    let mut stdout = io::stdout().lock();
+    if options.json {
+        let cards: Vec<_> = cards
+            .iter()
+            .map(|c| CardJson::new(c, plural_form(c)))
+            .collect();
+        writeln!(stdout, "{}", serde_json::to_string(&cards)?)?;
         return Ok(());
     }
+
+    let render = RenderOptions {
+        placeholders: PlaceholderStyle::Expanded,
+        ..options.render_options()
+    };
+    for card in &cards {
+        present::write_card(&mut stdout, card, plural_form(card), render)?;
+    }
+    Ok(())
+}
+
+/// How verbose output shows entries.
+fn details(options: &query::Options) -> Details {
+    Details {
+        render: RenderOptions {
+            case_style: options.case_style,
+            ..options.render_options()
+        },
+        debug_refs: options.debug_refs,
+        sources: !custom_dictionaries().is_empty(),
+    }
+}
+
+/// Lines up the results, colored if the terminal supports it.
+fn layout<'a>(entries: impl IntoIterator<Item = &'a Entry>, options: &query::Options) -> Layout {
+    Layout::new(entries, options.render_options(), console::supports_ansi())
+}
+
+/// Prints the entry with the tags telling it apart from similar results.
+fn print_entry(layout: &Layout, entry: &Entry, differences: &[String], options: &query::Options) {
+    // Like println!, failing to write to stdout is a bug.
+    write_entry(io::stdout().lock(), layout, entry, differences, options)
+        .expect("failed to print entry");
+}
+
+fn write_entry(
+    mut wr: impl Write,
+    layout: &Layout,
+    entry: &Entry,
+    differences: &[String],
+    options: &query::Options,
+) -> io::Result<()> {
+    let mut text = layout.line(entry, differences);
+    if options.pronunciation {
+        if let Some(hint) = pronunciation_hint(&entry.english) {
+            text = format!("{}  /{}/", text, hint);
+        }
+    }
+    if options.debug_refs {
+        writeln!(wr, "{}  ({}, id {})", text, entry.source, entry.id())
+    } else {
+        writeln!(wr, "{}", text)
+    }
 }
 
-fn meaning_command(word: &str, mut rd: impl BufRead, match_english: bool) -> anyhow::Result<()> {
+#[cfg(feature = "pronunciation")]
+fn pronunciation_hint(term: &Term) -> Option<String> {
+    use dict_cc_lookup::pronunciation::{self, Pronunciations};
+    static PRONUNCIATIONS: OnceLock<Pronunciations> = OnceLock::new();
+    PRONUNCIATIONS
+        .get_or_init(|| {
+            let full = Paths::new().and_then(|p| fs::read_to_string(p.pronunciations()).ok());
+            Pronunciations::parse(full.as_deref().unwrap_or(pronunciation::BUNDLED))
+        })
+        .hint(term)
+}
+
+#[cfg(not(feature = "pronunciation"))]
+fn pronunciation_hint(_: &Term) -> Option<String> {
+    None
+}
+
+fn is_zstd_path(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "zst")
+}
+
+fn create_dictionary_file(path: &Path) -> anyhow::Result<Box<dyn Write>> {
+    let file = io::BufWriter::new(fs::File::create(path)?);
+    Ok(if is_zstd_path(path) {
+        Box::new(zstd::stream::write::Encoder::new(file, 0)?.auto_finish())
+    } else {
+        Box::new(file)
+    })
+}
+
+fn export_command(
+    mut filters: Vec<Filter>,
+    out: &Path,
+    source: &DictionarySource,
+) -> anyhow::Result<()> {
+    // Words are ranked by how many lines they occur in, which the index knows.
+    if filters.iter().any(|f| matches!(f, Filter::Top { .. })) {
+        let frequencies = match open_index(source) {
+            Some(index) => index.keywords(Language::German),
+            None => index::count_keywords(source.reader()?, Language::German)?,
+        };
+        for filter in &mut filters {
+            filter.rank(&frequencies);
+        }
+    }
+
+    let mut rd = source.reader()?;
+    let mut wr = create_dictionary_file(out)?;
     let mut buf = String::with_capacity(512);
+    let mut count = 0;
 
     loop {
         buf.clear();
         if rd.read_line(&mut buf)? == 0 {
-            return Ok(());
+            break;
         }
 
-        let mut components = buf.split('\t');
-        let Some(german_input) = components.next() else {
-            continue
-        };
-        let Some(english_input) = components.next() else {
-            continue
-        };
-
-        let maybe_match = (!match_english && util::case_fold_contains(german_input, word))
-            || (match_english && util::case_fold_contains(english_input, word));
-        if !maybe_match {
-            continue;
+        // Keep the header, so the result is still recognizable as a dict.cc export.
+        let is_header = buf.starts_with('#') || buf.trim().is_empty();
+        if is_header {
+            wr.write_all(buf.as_bytes())?;
+        } else if filters.iter().all(|f| f.matches(&buf)) {
+            wr.write_all(buf.as_bytes())?;
+            count += 1;
         }
+    }
 
-        let german = Term::parse(german_input)?;
-        let english = Term::parse(english_input)?;
-        let exact_match = (!match_english && german.match_exact(word))
-            || (match_english && english.match_exact(word));
-        if !exact_match {
-            continue;
-        }
+    wr.flush()?;
+    eprintln!("exported {} lines", count);
 
-        let grammar_info = components.next().unwrap();
+    Ok(())
+}
 
-        println!(
-            "{} = {}{}",
-            german,
-            english,
-            if grammar_info.is_empty() {
-                "".to_string()
+fn merge_command(inputs: &[PathBuf], out: &Path) -> anyhow::Result<()> {
+    let mut wr = create_dictionary_file(out)?;
+    let mut seen = HashSet::<String>::new();
+    let mut buf = String::with_capacity(512);
+    let (mut count, mut duplicates) = (0, 0);
+
+    for (i, path) in inputs.iter().enumerate() {
+        let source = DictionarySource::from_file(path)?;
+        let mut rd = source.reader()?;
+
+        loop {
+            buf.clear();
+            if rd.read_line(&mut buf)? == 0 {
+                break;
+            }
+
+            // Only the first dictionary's header is kept.
+            if buf.starts_with('#') || buf.trim().is_empty() {
+                if i == 0 {
+                    wr.write_all(buf.as_bytes())?;
+                }
+                continue;
+            }
+
+            if !buf.ends_with('\n') {
+                buf.push('\n');
+            }
+
+            if seen.insert(dictionary::normalize_line(&buf)) {
+                wr.write_all(buf.as_bytes())?;
+                count += 1;
             } else {
-                format!("  [{}]", grammar_info)
+                duplicates += 1;
             }
-        );
+        }
     }
+
+    wr.flush()?;
+    eprintln!("merged {} lines, dropped {} duplicates", count, duplicates);
+
+    Ok(())
 }
 
-fn lex_command(mut rd: impl BufRead) -> anyhow::Result<()> {
+fn lex_command(rd: impl BufRead, options: &query::Options) -> anyhow::Result<()> {
+    let mut rd = LineReader::new(rd);
     let mut buf = String::with_capacity(512);
     let mut i = 0;
 
@@ -141,159 +1969,369 @@ fn lex_command(mut rd: impl BufRead) -> anyhow::Result<()> {
             continue;
         }
 
+        if options.debug_refs {
+            println!("{}", rd.source());
+        }
         println!("{buf:?}");
         buf.split('\t')
             .take(2)
-            .map(|v| (v, lexer::lex(v)))
-            .for_each(|(v, tokens)| {
-                println!(
-                    "{:?}",
-                    tokens
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| match e {
-                            lexer::Error::Unexpected(at) =>
-                                format!("unexpected value at \"{}\"", &v[at..]),
-                            e => e.to_string(),
-                        })
-                        .unwrap()
-                )
+            .for_each(|v| match lexer::lex(v).collect::<Result<Vec<_>, _>>() {
+                Ok(tokens) => println!("{tokens:?}"),
+                Err(e) => println!("{e} in {v:?}"),
             });
         println!();
     }
 }
 
-#[derive(Debug, Clone)]
-struct Entry {
-    german: Term,
-    english: Term,
-    grammar_info: String,
-}
-
-fn interactive_command(mut rd: impl BufRead) -> anyhow::Result<()> {
+fn interactive_command(source: &DictionarySource, options: &query::Options) -> anyhow::Result<()> {
     println!("dict.cc in command line");
 
-    let mut buf = String::with_capacity(512);
-    let mut entries = Vec::<Entry>::new();
+    // Every search reads only the lines which may match, so nothing is loaded up front.
+    let mut index = open_index(source);
+    let mut buf;
+
+    println!("Input German words (save matches with #<numbers> or =<part of translation>, add review cards for them right away with +<numbers>, narrow them down with :refine <word or tag>):");
+
+    // Only typed lines are worth recalling, not piped ones.
+    let mut editor = LineEditor::new(
+        Paths::new()
+            .map(|p| p.input_history())
+            .filter(|_| io::stdin().is_terminal()),
+    );
+
+    let mut matches = Vec::<Entry>::new();
+    let mut saved_words = HashMap::<Term, SavedWord>::new();
+    let mut stats = SessionStats::default();
+    // Only built once a search comes up empty, as it takes a moment.
+    let mut german_keywords = None;
 
     loop {
-        buf.clear();
-        if rd.read_line(&mut buf)? == 0 {
-            break;
+        let line = editor.read_line("> ")?;
+        // Only lock stdout after reading, the editor writes to it as well.
+        let mut stdout = io::stdout().lock();
+
+        let Some(line) = line else {
+            stats.saved = saved_words.len();
+            finish_session(&mut stdout, saved_words, options)?;
+
+            writeln!(stdout, "{}", stats)?;
+            if let Some(path) = Paths::new()
+                .map(|p| p.history())
+                .filter(|_| options.record_sessions)
+            {
+                if let Err(e) = stats.append_to(&path) {
+                    eprintln!("failed to write session history: {}", e);
+                }
+            }
+
+            return Ok(());
+        };
+        buf = line;
+
+        if buf.trim().is_empty() {
+            continue;
         }
 
-        let mut components = buf.split('\t');
-        let Ok(german) = components
-            .next()
-            .ok_or_else(|| anyhow!("no german component"))
-            .and_then(Term::parse) else {
-                continue
+        // A lone "+" adds a card for the best match.
+        if let Some(numbers) = buf.trim().strip_prefix('+') {
+            let selection = match numbers.trim() {
+                "" => Some(vec![0]),
+                numbers => query::parse_selection(&format!("#{}", numbers), matches.len()),
             };
-        let Ok(english) = components
-            .next()
-            .ok_or_else(|| anyhow!("no english component"))
-            .and_then(Term::parse) else {
-                continue
+            let Some(selection) = selection else {
+                writeln!(stdout, "usage: +[<numbers>]")?;
+                continue;
             };
-        let Some(grammar_info) = components.next().map(|s| s.to_string()) else {
-            continue
-        };
+            for i in selection {
+                match matches.get(i) {
+                    Some(entry) => {
+                        writeln!(stdout, "{}", card_notice(entry, options.render_options()))?
+                    }
+                    None => writeln!(stdout, "there is no match number {}", i)?,
+                }
+            }
 
-        entries.push(Entry {
-            german,
-            english,
-            grammar_info,
-        });
-    }
+            continue;
+        }
 
-    println!("Input German words:");
+        if let Some(selection) = query::parse_selection(&buf, matches.len()) {
+            for i in selection {
+                match matches.get(i) {
+                    Some(entry) => save_entry(&mut saved_words, entry.clone()),
+                    None => writeln!(stdout, "there is no match number {}", i)?,
+                }
+            }
 
-    let mut stdin = io::stdin().lock();
-    let mut stdout = io::stdout().lock();
+            continue;
+        }
 
-    let mut matches = Vec::<Entry>::new();
-    let mut saved_words = HashMap::<Term, Vec<Term>>::new();
+        if let Some(needles) = buf.trim().strip_prefix(":refine") {
+            let needles: Vec<_> = needles.split_whitespace().collect();
+            if needles.is_empty() {
+                writeln!(stdout, "usage: :refine <word or tag>...")?;
+                continue;
+            }
 
-    loop {
-        write!(stdout, "> ")?;
-        stdout.flush()?;
+            matches.retain(|e| needles.iter().all(|n| dictionary::refines(e, n)));
+            stats.viewed += matches.len();
+            print_matches(&mut stdout, &matches, options)?;
 
-        buf.clear();
-        if stdin.read_line(&mut buf)? == 0 {
-            writeln!(stdout)?;
+            continue;
+        }
 
-            let mut saved = saved_words
-                .into_iter()
-                .map(|(german, mut english)| {
-                    english.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-                    let eng = english
-                        .into_iter()
-                        .map(|e| e.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    (german, eng)
-                })
-                .collect::<Vec<_>>();
+        if let Some(needle) = buf.trim().strip_prefix('=') {
+            let needle = needle.trim();
+            match dictionary::with_translation(&matches, needle)[..] {
+                [entry] => save_entry(&mut saved_words, entry.clone()),
+                [] => writeln!(stdout, "no translation contains \"{}\"", needle)?,
+                _ => writeln!(stdout, "\"{}\" matches more than one translation", needle)?,
+            }
 
-            saved.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            continue;
+        }
 
-            let mut out = String::new();
+        let input = query::normalize(&buf, &NormalizeOptions::for_language(Language::German));
+        matches = Dictionary::open_exact_matches(
+            source,
+            index.as_mut(),
+            &input,
+            Language::German,
+            &options.weights,
+        )?;
+        // Only German words are logged, for quizzing them later.
+        let german = !matches.is_empty();
+
+        if matches.is_empty() {
+            let input = query::normalize(&buf, &NormalizeOptions::for_language(Language::English));
+            matches = Dictionary::open_exact_matches(
+                source,
+                index.as_mut(),
+                &input,
+                Language::English,
+                &options.weights,
+            )?;
 
-            for (german, english) in saved {
-                out.push_str(&format!("{} = {}\n", german, english));
+            if !matches.is_empty() {
+                writeln!(stdout, "No German matches, showing English matches:")?;
             }
+        }
 
-            if !out.is_empty() {
-                write!(stdout, "{}", out)?;
-                if let Err(e) = cli_clipboard::set_contents(out) {
-                    eprintln!("failed to copy to clipboard: {}", e);
-                }
+        if matches.is_empty() {
+            // The index lists the keywords, only without it the dictionary is read.
+            if german_keywords.is_none() {
+                german_keywords = Some(match &index {
+                    Some(index) => index.keywords(Language::German),
+                    None => {
+                        let dict = Dictionary::load(source.reader()?)?;
+                        let frequencies = search::keyword_frequencies(&dict, Language::German);
+                        frequencies.into_iter().collect()
+                    }
+                });
+            }
+            let frequencies = german_keywords.as_ref().unwrap();
+            let input = query::normalize(&buf, &NormalizeOptions::for_language(Language::German));
+            let keywords = frequencies.iter().map(|(k, f)| (k.as_str(), *f));
+            let mut suggestions = vec![];
+            for s in search::fuzzy(&input, keywords, 5) {
+                // The index only has the lowercased keywords.
+                let spelling = match &mut index {
+                    Some(index) => {
+                        Dictionary::open(source, Some(index), &s.keyword, Language::German)?
+                            .keyword_spelling(&s.keyword, Language::German)
+                    }
+                    None => None,
+                };
+                suggestions.push(spelling.unwrap_or(s.keyword));
+            }
+            if !suggestions.is_empty() {
+                writeln!(
+                    stdout,
+                    "No matches, did you mean: {}?",
+                    suggestions.join(", ")
+                )?;
             }
+        }
 
-            return Ok(());
+        if german {
+            log_lookup(&input);
         }
 
-        if buf
-            .chars()
-            .all(|c| c.is_ascii_digit() || c.is_ascii_whitespace())
-        {
-            for entry in buf
-                .split_ascii_whitespace()
-                .filter_map(|s| s.parse::<usize>().ok())
-                .filter_map(|i| matches.get(i).cloned())
-            {
-                if let Some(terms) = saved_words.get_mut(&entry.german) {
-                    if !terms.contains(&entry.english) {
-                        terms.push(entry.english);
-                    }
-                } else {
-                    saved_words.insert(entry.german, vec![entry.english]);
-                }
+        stats.searched += 1;
+        stats.viewed += matches.len();
+        if matches.is_empty() {
+            stats.misses += 1;
+        }
+
+        print_matches(&mut stdout, &matches, options)?;
+    }
+}
+
+/// Adds a card for the entry to the vocabulary right away, so that the review trainer
+/// asks for it, and says how that went.
+fn card_notice(entry: &Entry, render: RenderOptions) -> String {
+    let add = || -> anyhow::Result<bool> {
+        let path = paths()?.vocab();
+        let mut vocab = load_vocab(&path)?;
+        let added = vocab.add_entry(entry);
+        vocab.save(&path)?;
+        Ok(added)
+    };
+    let card = format!(
+        "{} = {}",
+        entry.german.render(render),
+        entry.english.render(render)
+    );
+    match add() {
+        Ok(true) => format!("added a review card: {}", card),
+        Ok(false) => format!("there already is a review card: {}", card),
+        Err(e) => format!("failed to add a review card: {}", e),
+    }
+}
+
+/// Loads the vocabulary, recovering it if the file is corrupt.
+fn load_vocab(path: &Path) -> io::Result<Vocab> {
+    let (vocab, recovery) = Vocab::load_or_recover(path)?;
+    match recovery {
+        Some(Recovery::Restored { corrupt }) => eprintln!(
+            "{} was corrupt and has been restored from its backup, the corrupt file was moved to {}",
+            path.display(),
+            corrupt.display()
+        ),
+        Some(Recovery::Reset { corrupt }) => eprintln!(
+            "{} was corrupt and there is no usable backup, starting over; the corrupt file was moved to {}",
+            path.display(),
+            corrupt.display()
+        ),
+        None => {}
+    }
+    Ok(vocab)
+}
+
+/// Adds the saved words to the vocabulary, and to the known words if the config asks
+/// for it, then prints them and copies them to the clipboard, see
+/// [`present::saved_words`].
+fn finish_session(
+    mut stdout: impl Write,
+    saved_words: HashMap<Term, SavedWord>,
+    options: &query::Options,
+) -> io::Result<()> {
+    if saved_words.is_empty() {
+        return Ok(());
+    }
+    let render = options.render_options();
+
+    if let Ok(path) = paths().map(|p| p.vocab()) {
+        let res = load_vocab(&path).and_then(|mut vocab| {
+            for entry in saved_words.values().flat_map(|s| &s.senses) {
+                vocab.add_entry(entry);
             }
+            vocab.save(&path)
+        });
+        if let Err(e) = res {
+            eprintln!("failed to update the vocabulary: {}", e);
+        }
+    }
 
-            continue;
+    if options.seed_known_words {
+        seed_known_words(saved_words.keys().flat_map(Term::keywords));
+    }
+
+    let saved = saved_words
+        .into_iter()
+        .map(|(german, s)| (german, s.senses));
+    let out = present::saved_words(saved, render, options.saved_grammar);
+    if !out.is_empty() {
+        write!(stdout, "{}", out)?;
+        if let Err(e) = cli_clipboard::set_contents(out) {
+            eprintln!("failed to copy to clipboard: {}", e);
         }
+    }
 
-        matches = entries
-            .iter()
-            .filter(|e| e.german.match_exact(buf.trim()))
-            .cloned()
-            .collect();
+    Ok(())
+}
 
-        for (i, entry) in matches.iter().enumerate() {
-            writeln!(
-                stdout,
-                "{: >3}. {} = {}{}",
-                i,
-                entry.german,
-                entry.english,
-                if entry.grammar_info.is_empty() {
-                    "".to_string()
-                } else {
-                    format!("  [{}]", entry.grammar_info)
-                }
-            )?;
+fn browse_command(rd: impl BufRead, options: &query::Options) -> anyhow::Result<()> {
+    eprintln!("loading the dictionary...");
+    let dict = Dictionary::load(rd)?;
+
+    let mut saved_words = HashMap::new();
+    let render = options.render_options();
+    let add_card = |entry: &Entry| card_notice(entry, render);
+    for entry in tui::run(&dict, &options.weights, add_card)? {
+        save_entry(&mut saved_words, entry);
+    }
+    finish_session(io::stdout().lock(), saved_words, options)?;
+
+    Ok(())
+}
+
+fn print_matches(
+    mut wr: impl Write,
+    matches: &[Entry],
+    options: &query::Options,
+) -> io::Result<()> {
+    let differences = group::differences(matches);
+    let layout = layout(matches, options);
+    for (i, (entry, differences)) in matches.iter().zip(&differences).enumerate() {
+        write!(wr, "{: >3}. {}", i, layout.line(entry, differences))?;
+        if options.pronunciation {
+            if let Some(hint) = pronunciation_hint(&entry.english) {
+                write!(wr, "  /{}/", hint)?;
+            }
+        }
+        if options.debug_refs {
+            write!(wr, "  ({}, id {})", entry.source, entry.id())?;
         }
+        writeln!(wr)?;
+    }
+
+    wr.flush()
+}
+
+/// The entries saved for a German term, one for every sense.
+#[derive(Debug, Default)]
+struct SavedWord {
+    senses: Vec<Entry>,
+}
+
+fn save_entry(saved_words: &mut HashMap<Term, SavedWord>, entry: Entry) {
+    let saved = saved_words.entry(entry.german.clone()).or_default();
+    if !saved.senses.iter().any(|e| e.id() == entry.id()) {
+        saved.senses.push(entry);
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionStats {
+    searched: usize,
+    viewed: usize,
+    saved: usize,
+    misses: usize,
+}
+
+impl SessionStats {
+    fn append_to(&self, path: &Path) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        store::append_line(
+            path,
+            &format!(
+                "{}\t{}\t{}\t{}\t{}",
+                timestamp, self.searched, self.viewed, self.saved, self.misses
+            ),
+        )
+    }
+}
 
-        stdout.flush()?;
+impl fmt::Display for SessionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "searched {} words, viewed {} matches, saved {} words, {} misses",
+            self.searched, self.viewed, self.saved, self.misses
+        )
     }
 }
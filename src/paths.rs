@@ -0,0 +1,154 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// The platform specific locations of the files the program reads and writes, e.g.
+/// `~/.config/dict-cc-lookup` on Linux, `~/Library/Application Support/dict-cc-lookup`
+/// on macOS and `%APPDATA%\dict-cc-lookup` on Windows.
+#[derive(Debug, Clone)]
+pub struct Paths {
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
+    vocab_file: Option<PathBuf>,
+}
+
+impl Paths {
+    /// Returns `None` if the home directory can't be determined.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Option<Self> {
+        let dirs = directories::ProjectDirs::from("", "", "dict-cc-lookup")?;
+
+        Some(Self {
+            config_dir: dirs.config_dir().to_path_buf(),
+            data_dir: dirs.data_dir().to_path_buf(),
+            cache_dir: dirs.cache_dir().to_path_buf(),
+            vocab_file: None,
+        })
+    }
+
+    /// Web pages have no home directory.
+    #[cfg(target_arch = "wasm32")]
+    pub fn new() -> Option<Self> {
+        None
+    }
+
+    /// Keeps all files in subdirectories of `root`, for tests and portable setups.
+    pub fn in_dir(root: &Path) -> Self {
+        Self {
+            config_dir: root.join("config"),
+            data_dir: root.join("data"),
+            cache_dir: root.join("cache"),
+            vocab_file: None,
+        }
+    }
+
+    /// Keeps the saved vocabulary at `path` instead of in the data directory, as set in
+    /// the configuration file. `DICT_CC_VOCAB` still takes precedence.
+    pub fn with_vocab_file(mut self, path: Option<PathBuf>) -> Self {
+        self.vocab_file = path;
+        self
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Holds the dictionary indexes, which can be rebuilt at any time.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// The configuration file, overridable with `DICT_CC_CONFIG`.
+    pub fn config_file(&self) -> PathBuf {
+        env_or("DICT_CC_CONFIG", || self.config_dir.join("config.toml"))
+    }
+
+    /// The user's stopwords list, overridable with `DICT_CC_STOPWORDS`.
+    pub fn stopwords(&self) -> PathBuf {
+        env_or("DICT_CC_STOPWORDS", || {
+            self.config_dir.join("stopwords.txt")
+        })
+    }
+
+    /// The words the user already knows, overridable with `DICT_CC_KNOWN_WORDS`.
+    pub fn known_words(&self) -> PathBuf {
+        env_or("DICT_CC_KNOWN_WORDS", || self.data_dir.join("known.txt"))
+    }
+
+    /// The saved vocabulary, overridable with `DICT_CC_VOCAB`.
+    pub fn vocab(&self) -> PathBuf {
+        env_or("DICT_CC_VOCAB", || {
+            self.vocab_file
+                .clone()
+                .unwrap_or_else(|| self.data_dir.join("vocab.json"))
+        })
+    }
+
+    /// The interactive session history, overridable with `DICT_CC_HISTORY`.
+    pub fn history(&self) -> PathBuf {
+        env_or("DICT_CC_HISTORY", || self.data_dir.join("history.tsv"))
+    }
+
+    /// The lines entered in interactive mode, overridable with `DICT_CC_INPUT_HISTORY`.
+    pub fn input_history(&self) -> PathBuf {
+        env_or("DICT_CC_INPUT_HISTORY", || {
+            self.data_dir.join("input_history.txt")
+        })
+    }
+
+    /// The log of looked up words, overridable with `DICT_CC_LOOKUPS`.
+    pub fn lookups(&self) -> PathBuf {
+        env_or("DICT_CC_LOOKUPS", || self.data_dir.join("lookups.tsv"))
+    }
+
+    /// A pronunciation dictionary in CMUdict format, used instead of the bundled excerpt
+    /// if present. Overridable with `DICT_CC_CMUDICT`.
+    pub fn pronunciations(&self) -> PathBuf {
+        env_or("DICT_CC_CMUDICT", || self.data_dir.join("cmudict.txt"))
+    }
+
+    /// The socket the daemon listens on, overridable with `DICT_CC_SOCKET`.
+    pub fn socket(&self) -> PathBuf {
+        env_or("DICT_CC_SOCKET", || self.cache_dir.join("daemon.sock"))
+    }
+
+    /// The dictionary installed with `update`, used instead of the embedded one.
+    pub fn dictionary(&self) -> PathBuf {
+        self.data_dir.join("dictionary.txt.zst")
+    }
+
+    /// Where imported dictionaries are kept.
+    pub fn dictionaries_dir(&self) -> PathBuf {
+        self.data_dir.join("dictionaries")
+    }
+
+    /// All paths with a short name, for display.
+    pub fn list(&self) -> Vec<(&'static str, PathBuf)> {
+        vec![
+            ("config", self.config_dir.clone()),
+            ("data", self.data_dir.clone()),
+            ("cache", self.cache_dir.clone()),
+            ("config file", self.config_file()),
+            ("stopwords", self.stopwords()),
+            ("known words", self.known_words()),
+            ("vocab", self.vocab()),
+            ("history", self.history()),
+            ("input history", self.input_history()),
+            ("lookups", self.lookups()),
+            ("pronunciations", self.pronunciations()),
+            ("socket", self.socket()),
+            ("dictionary", self.dictionary()),
+            ("dictionaries", self.dictionaries_dir()),
+        ]
+    }
+}
+
+fn env_or(var: &str, default: impl FnOnce() -> PathBuf) -> PathBuf {
+    env::var_os(var).map(PathBuf::from).unwrap_or_else(default)
+}
@@ -0,0 +1,606 @@
+//! Lays out lookup results for reading: the "=" of all results lines up, and with
+//! colors, the articles of nouns are colored by gender and annotations are dimmed, so
+//! that the words themselves stand out. Also renders the other views of the dictionary
+//! the CLI shows, like noun genders, cards and summaries.
+
+use std::io::{self, Write};
+
+use crate::{
+    card::Card,
+    dictionary::Noun,
+    entry::{AnnotationKind, Case, Entry, Gender, RenderOptions, Term, WordClass},
+    group,
+    query::{self, Language},
+    stopwords::Stopwords,
+    valency::Valency,
+    Dictionary,
+};
+
+/// How many translations lines summarizing a word show.
+const TRANSLATIONS: usize = 3;
+
+/// German terms longer than this don't push the "=" of all other results further right.
+const MAX_COLUMN: usize = 40;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+
+/// The layout of a list of results.
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    render: RenderOptions,
+    color: bool,
+    /// The width the German terms are padded to.
+    column: usize,
+    /// Whether results are labelled with the dictionary they come from.
+    sources: bool,
+}
+
+impl Layout {
+    /// Lines up the given results, which are rendered with `render`.
+    pub fn new<'a>(
+        entries: impl IntoIterator<Item = &'a Entry>,
+        render: RenderOptions,
+        color: bool,
+    ) -> Self {
+        let column = entries
+            .into_iter()
+            .map(|e| e.german.render(render).chars().count())
+            .filter(|&width| width <= MAX_COLUMN)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            render,
+            color,
+            column,
+            sources: false,
+        }
+    }
+
+    /// Labels the results with the dictionary they come from, for when there are
+    /// several.
+    pub fn with_sources(mut self, sources: bool) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// The result line of the entry, followed by the tags telling it apart from similar
+    /// results, which are bold with colors.
+    pub fn line(&self, entry: &Entry, differences: &[String]) -> String {
+        let german = entry.german.render(self.render);
+        let padding = self.column.saturating_sub(german.chars().count());
+        let english = entry.english.render(self.render);
+        let classes: Vec<_> = entry.word_classes.iter().map(|c| c.to_string()).collect();
+
+        let mut out = if self.color {
+            // Dimming looks for brackets, so it comes before any escape sequence.
+            let german = dim_brackets(&german);
+            let german = match entry.german.gender() {
+                Some(gender) => color_article(&german, gender),
+                None => german,
+            };
+            format!(
+                "{}{} = {}",
+                german,
+                " ".repeat(padding),
+                dim_brackets(&english)
+            )
+        } else {
+            format!("{}{} = {}", german, " ".repeat(padding), english)
+        };
+
+        if !classes.is_empty() {
+            let classes = format!("[{}]", classes.join(" "));
+            out.push_str("  ");
+            out.push_str(&self.style(DIM, &classes));
+        }
+        if !differences.is_empty() {
+            out.push_str("  ");
+            out.push_str(&self.style(BOLD, &differences.join(" ")));
+        }
+        if self.sources {
+            let source = format!("({})", entry.source.origin.as_str());
+            out.push_str("  ");
+            out.push_str(&self.style(DIM, &source));
+        }
+
+        out
+    }
+
+    fn style(&self, style: &str, s: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, s, RESET)
+        } else {
+            s.to_string()
+        }
+    }
+}
+
+/// Writes entries with everything their dictionary lines say about them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Details {
+    pub render: RenderOptions,
+    /// Whether to show where in the dictionary the entries are.
+    pub debug_refs: bool,
+    /// Whether to show which dictionary the entries come from, for when there are
+    /// several.
+    pub sources: bool,
+}
+
+impl Details {
+    /// Writes the entry, and its dictionary line if it is at hand.
+    pub fn write(&self, mut wr: impl Write, entry: &Entry, line: Option<&str>) -> io::Result<()> {
+        let join = |values: Vec<String>| {
+            if values.is_empty() {
+                "-".to_string()
+            } else {
+                values.join(" ")
+            }
+        };
+        let annotations =
+            |term: &Term| join(term.annotations().iter().map(|a| a.to_string()).collect());
+
+        writeln!(
+            wr,
+            "{} = {}",
+            entry.german.render(self.render),
+            entry.english.render(self.render)
+        )?;
+        writeln!(wr, "    id:          {}", entry.id())?;
+        if let Some(line) = line {
+            writeln!(
+                wr,
+                "    line:        {:?}",
+                line.trim_end_matches(['\n', '\r'])
+            )?;
+        }
+        if self.debug_refs {
+            writeln!(wr, "    source:      {}", entry.source)?;
+        }
+        if self.sources {
+            writeln!(wr, "    dictionary:  {}", entry.source.origin.as_str())?;
+        }
+        writeln!(wr, "    German:      {}", annotations(&entry.german))?;
+        writeln!(wr, "    English:     {}", annotations(&entry.english))?;
+        writeln!(
+            wr,
+            "    word class:  {}",
+            join(entry.word_classes.iter().map(|c| c.to_string()).collect())
+        )?;
+        writeln!(
+            wr,
+            "    subjects:    {}",
+            join(entry.subjects.iter().map(|s| s.to_string()).collect())
+        )?;
+        writeln!(wr)
+    }
+}
+
+/// The rows of a gender batch, one per input line so that they line up with the input:
+/// the word capitalized like a noun, its articles and its plural, tab separated. Words
+/// which are no nouns get empty columns, stopwords too, without being looked up. Blank
+/// lines stay blank.
+pub fn gender_rows(dict: &Dictionary, input: &str, stopwords: &Stopwords) -> Vec<String> {
+    input
+        .lines()
+        .map(str::trim)
+        .map(|line| {
+            let Some(word) = query::to_upper(line) else {
+                return String::new();
+            };
+            if stopwords.contains(line) {
+                return format!("{}\t\t", line);
+            }
+            let (article, plural) = dict.article_and_plural(&word).unwrap_or_default();
+            format!("{}\t{}\t{}", word, article, plural)
+        })
+        .collect()
+}
+
+/// The line describing a gender of a German noun, as in "der Hund: dog, hound; pl. die
+/// Hunde".
+pub fn noun(noun: &Noun) -> String {
+    let translations = noun.translations[..noun.translations.len().min(TRANSLATIONS)].join(", ");
+    match (noun.gender, &noun.plural) {
+        (Some(gender), Some(plural)) => format!(
+            "{} {}: {}; pl. die {}",
+            gender, noun.headword, translations, plural
+        ),
+        (Some(gender), None) => format!("{} {}: {}", gender, noun.headword, translations),
+        (None, _) => format!("die {} (pl): {}", noun.headword, translations),
+    }
+}
+
+/// The German term of the best of the entries with the first few distinct translations,
+/// to gloss a word: "der Hund: dog, hound".
+pub fn gloss(entries: &[Entry], render: RenderOptions) -> String {
+    let mut translations: Vec<String> = vec![];
+    for entry in entries {
+        let translation = entry.english.render(render);
+        if !translations.contains(&translation) {
+            translations.push(translation);
+        }
+    }
+    translations.truncate(TRANSLATIONS);
+
+    match entries.first() {
+        Some(best) => format!(
+            "{}: {}",
+            best.german.render(render),
+            translations.join(", ")
+        ),
+        None => String::new(),
+    }
+}
+
+/// The German term of a noun entry with its article, "die" for plurals.
+pub fn with_article(term: &Term) -> String {
+    let article = term.gender().map_or("die".to_string(), |g| g.to_string());
+    format!("{} {}", article, term.to_dictcc_string())
+}
+
+/// Writes the results grouped by word class with their first few translations, for
+/// when there are too many to read through.
+pub fn write_summary(
+    mut wr: impl Write,
+    entries: &[&Entry],
+    language: Language,
+    render: RenderOptions,
+) -> io::Result<()> {
+    const CLASS_TRANSLATIONS: usize = 5;
+
+    let summary = group::summarize(
+        entries.iter().copied(),
+        language,
+        render,
+        CLASS_TRANSLATIONS,
+    );
+    for class in summary {
+        let name = class.class.as_ref().map_or("other", |c| c.as_str());
+        writeln!(
+            wr,
+            "{} ({}): {}",
+            name,
+            class.count,
+            class.translations.join(", ")
+        )?;
+    }
+    writeln!(
+        wr,
+        "{} results; add words or --subject to narrow them down, or list them with --limit",
+        entries.len()
+    )
+}
+
+/// Writes which cases the verb governs, whether it is reflexive and which prepositions
+/// it takes, after the entries of `dv`.
+pub fn write_valency(mut wr: impl Write, verb: &str, valency: &Valency) -> io::Result<()> {
+    let join = |values: Vec<String>| {
+        if values.is_empty() {
+            "-".to_string()
+        } else {
+            values.join(", ")
+        }
+    };
+    let cases = |cases: &[(Case, usize)]| {
+        join(
+            cases
+                .iter()
+                .map(|(case, n)| format!("{} ({})", case.name(), n))
+                .collect(),
+        )
+    };
+    let prepositions = valency
+        .prepositions
+        .iter()
+        .map(|((preposition, case), n)| match case {
+            Some(case) => format!("{} + {} ({})", preposition, case.name(), n),
+            None => format!("{} ({})", preposition, n),
+        })
+        .collect();
+
+    let entries = match valency.entries {
+        1 => "1 entry".to_string(),
+        n => format!("{} entries", n),
+    };
+    writeln!(wr, "valency of \"{}\", from {}:", verb, entries)?;
+    writeln!(wr, "    cases:         {}", cases(&valency.cases))?;
+    writeln!(wr, "    reflexive:     {}", cases(&valency.reflexive))?;
+    writeln!(wr, "    prepositions:  {}", join(prepositions))
+}
+
+/// Writes the card of a sense of a German word, with its plural if there is one.
+pub fn write_card(
+    mut wr: impl Write,
+    card: &Card,
+    plural_form: Option<String>,
+    render: RenderOptions,
+) -> io::Result<()> {
+    let mut heading = match (card.gender, card.plural_only) {
+        (Some(gender), _) => format!("{} {}", gender, card.headword),
+        (None, true) => format!("die {} (pl.)", card.headword),
+        (None, false) => card.headword.to_string(),
+    };
+    if let Some(class) = card.word_class {
+        heading.push_str(&format!(" [{}]", class));
+    }
+    writeln!(wr, "{}", heading)?;
+    if let Some(plural) = plural_form {
+        writeln!(wr, "    plural:      die {}", plural)?;
+    }
+
+    let mut lines: Vec<String> = vec![];
+    for (register, entries) in &card.registers {
+        for entry in entries {
+            // The German term only tells something if it has placeholders, as in
+            // "jdm. helfen". The annotations of both terms are what tells the senses
+            // apart, as in "See {f} [Meer]".
+            let german = &entry.german;
+            let mut line = entry.english.render(render);
+            if !german.placeholders().is_empty() {
+                line = format!("{} = {}", german.render(render), line);
+            }
+            if let Some(register) = register {
+                line = format!("[{}] {}", register, line);
+            }
+            for annotation in german
+                .annotations()
+                .into_iter()
+                .chain(entry.english.annotations())
+            {
+                if annotation.kind == AnnotationKind::Explanation
+                    && Some(annotation.value) != *register
+                {
+                    line.push_str(&format!(" {}", annotation));
+                }
+            }
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+    }
+    for line in lines {
+        writeln!(wr, "    {}", line)?;
+    }
+
+    if !card.subjects.is_empty() {
+        let subjects: Vec<_> = card.subjects.iter().map(|s| format!("[{}]", s)).collect();
+        writeln!(wr, "    subjects:    {}", subjects.join(" "))?;
+    }
+    if !card.references.is_empty() {
+        writeln!(wr, "    compare:     {}", card.references.join(", "))?;
+    }
+    for (i, entry) in card.see_also.iter().enumerate() {
+        let label = if i == 0 { "see also:" } else { "" };
+        writeln!(
+            wr,
+            "    {:<12} {} = {}",
+            label,
+            entry.german.render(render),
+            entry.english.render(render)
+        )?;
+    }
+    writeln!(wr)
+}
+
+/// A line per headword of the entries, with its gender and first few translations. The
+/// entries for the headword alone come first.
+pub fn related_lines(entries: &[&Entry]) -> Vec<String> {
+    let mut headwords: Vec<(&str, Vec<&Entry>)> = vec![];
+    for &entry in entries {
+        let Some(headword) = entry.headword() else {
+            continue;
+        };
+        match headwords.iter_mut().find(|(h, _)| *h == headword) {
+            Some((_, entries)) => entries.push(entry),
+            None => headwords.push((headword, vec![entry])),
+        }
+    }
+
+    headwords
+        .into_iter()
+        .map(|(headword, mut entries)| {
+            entries.sort_by_key(|e| !e.german.match_exact(headword));
+            let mut translations: Vec<String> = vec![];
+            for entry in &entries {
+                let translation = entry.english.to_string();
+                if !translation.is_empty() && !translations.contains(&translation) {
+                    translations.push(translation);
+                }
+            }
+            translations.truncate(TRANSLATIONS);
+
+            match entries.iter().find_map(|e| e.german.gender()) {
+                Some(gender) => format!("{} {}: {}", gender, headword, translations.join(", ")),
+                None => format!("{}: {}", headword, translations.join(", ")),
+            }
+        })
+        .collect()
+}
+
+/// The words saved in a session as they are listed at its end, a line per German term
+/// with all translations saved for it. Terms differing only in annotations share a line.
+/// With `grammar`, the word classes follow the German terms.
+pub fn saved_words(
+    saved: impl IntoIterator<Item = (Term, Vec<Entry>)>,
+    render: RenderOptions,
+    grammar: bool,
+) -> String {
+    let mut saved: Vec<_> = saved.into_iter().collect();
+    saved.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut lines: Vec<(String, Vec<Term>, Vec<Entry>)> = vec![];
+    for (german, senses) in saved {
+        let text = german.render(render);
+        match lines.iter_mut().find(|(t, ..)| *t == text) {
+            Some((_, germans, merged)) => {
+                germans.push(german);
+                merged.extend(senses);
+            }
+            None => lines.push((text, vec![german], senses)),
+        }
+    }
+
+    let mut out = String::new();
+    for (_, germans, senses) in lines {
+        let mut english: Vec<_> = senses.iter().map(|e| e.english.clone()).collect();
+        english.sort();
+        english.dedup();
+        let english = group::collapse(&english, render).join(", ");
+        let mut classes = vec![];
+        for class in senses
+            .iter()
+            .flat_map(|e| &e.word_classes)
+            .map(WordClass::as_str)
+        {
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        let german = group::collapse(&germans, render).join(", ");
+        if grammar && !classes.is_empty() {
+            out.push_str(&format!(
+                "{} [{}] = {}\n",
+                german,
+                classes.join(", "),
+                english
+            ));
+        } else {
+            out.push_str(&format!("{} = {}\n", german, english));
+        }
+    }
+    out
+}
+
+/// Draws the values as bars, scaled to the largest. Zero is left blank.
+pub fn sparkline(values: &[u32]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = values.iter().copied().max().unwrap_or_default().max(1);
+    values
+        .iter()
+        .map(|&v| match v {
+            0 => ' ',
+            v => BARS[((v * BARS.len() as u32).div_ceil(max) - 1) as usize],
+        })
+        .collect()
+}
+
+/// Blue for masculine, red for feminine and green for neuter nouns, the colors many
+/// textbooks use.
+fn gender_color(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Masculine => "\x1b[34m",
+        Gender::Feminine => "\x1b[31m",
+        Gender::Neutral => "\x1b[32m",
+    }
+}
+
+/// Colors the article rendered terms of nouns start with.
+fn color_article(term: &str, gender: Gender) -> String {
+    let article = gender.to_string();
+    match term.strip_prefix(&article) {
+        Some(rest) => format!("{}{}{}{}", gender_color(gender), article, RESET, rest),
+        None => term.to_string(),
+    }
+}
+
+/// Dims the bracketed annotations of a rendered term, like the "[nur plural]" of plurals.
+fn dim_brackets(term: &str) -> String {
+    term.replace('[', &format!("{}[", DIM))
+        .replace(']', &format!("]{}", RESET))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gender_rows, noun, Layout};
+    use crate::{entry::RenderOptions, stopwords::Stopwords, Dictionary};
+
+    #[test]
+    fn aligns_and_colors() {
+        let dict = Dictionary::load(
+            "Hund {m}\tdog\tnoun\t\n\
+             Hunde {pl}\tdogs\tnoun\t\n\
+             bellen\tto bark\tverb\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let entries = dict.entries();
+
+        let plain = Layout::new(entries, RenderOptions::default(), false);
+        let lines: Vec<_> = entries.iter().map(|e| plain.line(e, &[])).collect();
+        assert_eq!(
+            lines,
+            [
+                "der Hund           = dog  [noun]",
+                "Hunde [nur plural] = dogs  [noun]",
+                "bellen             = to bark  [verb]",
+            ]
+        );
+
+        let sourced =
+            Layout::new(&entries[2..], RenderOptions::default(), false).with_sources(true);
+        assert_eq!(
+            sourced.line(&entries[2], &[]),
+            "bellen = to bark  [verb]  (main)"
+        );
+
+        let colored = Layout::new(&entries[..1], RenderOptions::default(), true);
+        assert_eq!(
+            colored.line(&entries[0], &["[zool.]".to_string()]),
+            "\x1b[34mder\x1b[0m Hund = dog  \x1b[2m[noun]\x1b[0m  \x1b[1m[zool.]\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn nouns() {
+        let dict = Dictionary::load(
+            "Hund {m}\tdog\tnoun\t\n\
+             Hund {m}\thound\tnoun\t\n\
+             Hunde {pl}\tdogs\tnoun\t\n\
+             Leute {pl}\tpeople\tnoun\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let lines: Vec<_> = ["Hund", "Leute"]
+            .iter()
+            .flat_map(|word| dict.nouns(&dict, word))
+            .map(|n| noun(&n))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "der Hund: dog, hound; pl. die Hunde",
+                "die Leute (pl): people"
+            ]
+        );
+    }
+
+    #[test]
+    fn gender_rows_line_up() {
+        let dict = Dictionary::load(
+            "Ich {n}\tego\tnoun\t[psych.]\n\
+             Hund {m}\tdog\tnoun\t\n\
+             Hunde {pl}\tdogs\tnoun\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let input = "hund\nIch\n\nlaufen\nHund\n";
+
+        let rows = gender_rows(&dict, input, &Stopwords::parse("ich"));
+        assert_eq!(rows.len(), input.lines().count());
+        assert_eq!(
+            rows,
+            [
+                "Hund\tder\tHunde",
+                "Ich\t\t",
+                "",
+                "Laufen\t\t",
+                "Hund\tder\tHunde"
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Pronunciation hints for English words, respelled from the ARPAbet transcriptions of
+//! a CMUdict style dictionary: "water" becomes "WAW-tur", with the stressed syllable in
+//! capitals.
+//!
+//! An excerpt of CMUdict is bundled. The full dictionary can be put at
+//! [`Paths::pronunciations`](crate::paths::Paths::pronunciations).
+
+use std::collections::HashMap;
+
+use crate::entry::Term;
+
+/// The bundled excerpt of CMUdict.
+pub const BUNDLED: &str = include_str!("cmudict.txt");
+
+pub struct Pronunciations {
+    /// Uppercase words to their phonemes, e.g. "DOG" to ["D", "AO1", "G"].
+    words: HashMap<String, Vec<String>>,
+}
+
+impl Pronunciations {
+    /// Parses the CMUdict format: a word and its phonemes per line, separated by
+    /// whitespace. Alternative pronunciations like "USE(1)" and ";;;" comments are
+    /// skipped.
+    pub fn parse(text: &str) -> Self {
+        let words = text
+            .lines()
+            .filter(|l| !l.starts_with(";;;"))
+            .filter_map(|l| {
+                let mut parts = l.split_whitespace();
+                let word = parts.next()?;
+                if word.ends_with(')') {
+                    return None;
+                }
+                Some((word.to_string(), parts.map(String::from).collect()))
+            })
+            .collect();
+
+        Self { words }
+    }
+
+    /// The respelling of a single word.
+    pub fn respell(&self, word: &str) -> Option<String> {
+        let phonemes = self.words.get(&word.to_uppercase())?;
+        Some(respell(phonemes))
+    }
+
+    /// The respelling of all words of an English term, without the "to" of infinitives
+    /// and placeholders like "sth.". `None` if a word is missing.
+    pub fn hint(&self, term: &Term) -> Option<String> {
+        let mut keywords: Vec<_> = term.keywords().filter(|k| !k.ends_with('.')).collect();
+        if keywords.len() > 1 && keywords[0] == "to" {
+            keywords.remove(0);
+        }
+        if keywords.is_empty() {
+            return None;
+        }
+
+        let words = keywords
+            .iter()
+            .map(|k| self.respell(k.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')))
+            .collect::<Option<Vec<_>>>()?;
+        Some(words.join(" "))
+    }
+}
+
+/// Splits the phonemes into syllables, one per vowel, and spells them out. A single
+/// consonant between vowels starts the next syllable, of several the first one closes
+/// the previous.
+fn respell(phonemes: &[String]) -> String {
+    let is_vowel = |p: &str| p.ends_with(|c: char| c.is_ascii_digit());
+
+    let vowels: Vec<_> = (0..phonemes.len())
+        .filter(|&i| is_vowel(&phonemes[i]))
+        .collect();
+    let mut starts = vec![0];
+    for pair in vowels.windows(2) {
+        let consonants = pair[1] - pair[0] - 1;
+        starts.push(if consonants > 1 {
+            pair[0] + 2
+        } else {
+            pair[0] + 1
+        });
+    }
+    starts.push(phonemes.len());
+
+    starts
+        .windows(2)
+        .map(|range| {
+            let syllable = &phonemes[range[0]..range[1]];
+            let stressed = syllable.iter().any(|p| p.ends_with('1'));
+            let mut text = String::new();
+            for (i, phoneme) in syllable.iter().enumerate() {
+                text.push_str(spell(phoneme, i == 0));
+            }
+            if stressed {
+                text.to_uppercase()
+            } else {
+                text
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn spell(phoneme: &str, first: bool) -> &'static str {
+    match phoneme.trim_end_matches(|c: char| c.is_ascii_digit()) {
+        "AA" => "ah",
+        "AE" => "a",
+        "AH" => "uh",
+        "AO" => "aw",
+        "AW" => "ow",
+        "AY" if first => "eye",
+        "AY" => "y",
+        "EH" => "e",
+        "ER" => "ur",
+        "EY" => "ay",
+        "IH" => "i",
+        "IY" => "ee",
+        "OW" => "oh",
+        "OY" => "oy",
+        "UH" => "uu",
+        "UW" => "oo",
+        "B" => "b",
+        "CH" => "ch",
+        "D" => "d",
+        "DH" => "dh",
+        "F" => "f",
+        "G" => "g",
+        "HH" => "h",
+        "JH" => "j",
+        "K" => "k",
+        "L" => "l",
+        "M" => "m",
+        "N" => "n",
+        "NG" => "ng",
+        "P" => "p",
+        "R" => "r",
+        "S" => "s",
+        "SH" => "sh",
+        "T" => "t",
+        "TH" => "th",
+        "V" => "v",
+        "W" => "w",
+        "Y" => "y",
+        "Z" => "z",
+        "ZH" => "zh",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pronunciations, BUNDLED};
+    use crate::entry::Term;
+
+    #[test]
+    fn respell() {
+        let p = Pronunciations::parse(BUNDLED);
+
+        assert_eq!(p.respell("dog").as_deref(), Some("DAWG"));
+        assert_eq!(p.respell("Water").as_deref(), Some("WAW-tur"));
+        assert_eq!(p.respell("island").as_deref(), Some("EYE-luhnd"));
+        assert_eq!(p.respell("dictionary").as_deref(), Some("DIK-shuh-ne-ree"));
+        assert_eq!(p.respell("use").as_deref(), Some("YOOS"));
+
+        let term = |s| Term::parse(s).unwrap();
+        assert_eq!(p.hint(&term("to lend sb. sth.")).as_deref(), Some("LEND"));
+        assert_eq!(p.hint(&term("puppy dog")).as_deref(), Some("PUH-pee DAWG"));
+        assert_eq!(p.hint(&term("barkless dog")), None);
+    }
+}
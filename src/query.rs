@@ -1,24 +1,728 @@
-use std::ops::Deref;
+use std::{ops::Deref, path::PathBuf, time::Duration};
 
 use anyhow::anyhow;
+use unicode_normalization::UnicodeNormalization;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use crate::{
+    block::Rule,
+    cli::{self, Shell},
+    entry::{
+        CaseStyle, Entry, EntryId, Gender, Origin, PlaceholderStyle, RenderOptions, Subject,
+        VariantStyle, WordClass,
+    },
+    filter::Filter,
+    rank::Weights,
+    util,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Language {
     German,
     English,
 }
 
+impl Language {
+    /// Parses the codes lookups over HTTP and in web pages take, "de" and "en".
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "de" => Some(Self::German),
+            "en" => Some(Self::English),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Query {
     Gender(String),
+    GenderBatch(PathBuf),
+    /// The German nouns translating an English noun, with their gender.
+    ReverseGender(String),
+    /// Looks up every line of the file, or of stdin if there is none.
+    Batch(Option<PathBuf>),
     Meaning {
         language: Language,
         components: Vec<String>,
         verbose: bool,
     },
+    Fuzzy {
+        language: Language,
+        word: String,
+    },
     Interactive {
         language: Language,
     },
+    /// The full screen browser.
+    Browse,
+    Known(KnownAction),
+    Export {
+        filters: Vec<Filter>,
+        out: PathBuf,
+    },
+    Merge {
+        inputs: Vec<PathBuf>,
+        out: PathBuf,
+    },
+    Paths,
+    Vocab(VocabAction),
+    /// German headwords matching the given conditions.
+    List(WordList),
+    /// The German headwords sorting around the given word.
+    Near(String),
+    /// The German phrases and idioms containing the word or phrase.
+    Phrases(String),
+    /// The German headwords containing the word as a component, at most `limit` of
+    /// each kind.
+    Related {
+        word: String,
+        limit: usize,
+    },
+    /// Figures about the dictionary, with the analyses of its words if asked for.
+    Stats {
+        analyses: bool,
+    },
+    /// The translations of two German words side by side.
+    Compare(String, String),
+    /// A flashcard quiz over the vocabulary cards due for review.
+    Review,
+    /// Show the progress in the flashcard quizzes.
+    ReviewStats,
+    /// Show the vocabulary cards due for review.
+    ReviewDue(DueOutput),
+    Backup(BackupAction),
+    /// The entry with the given ID, or the dictionary page of a German word.
+    Show(ShowTarget),
+    /// Keep the dictionary in memory and answer lookups of other invocations.
+    Daemon(DaemonAction),
+    /// Answer lookups over HTTP, on the given address or the default one.
+    Serve(Option<String>),
+    /// Build the keyword index of the dictionary, replacing any existing one.
+    BuildIndex,
+    /// Install the dict.cc export at the given path or URL in place of the embedded
+    /// dictionary.
+    Update(String),
+    /// Reading a text sentence by sentence with glosses of the unknown words, for at
+    /// most the given number of minutes.
+    Session {
+        text: PathBuf,
+        minutes: Option<u64>,
+    },
+    /// A quiz over the words looked up in the last `days` days.
+    QuizRecent {
+        days: u64,
+    },
+    /// The completion script for the shell.
+    Completions(Shell),
+    /// The saved words, for completion scripts.
+    CompleteWords,
+    /// How the arguments would be interpreted.
+    Explain(Vec<String>),
+}
+
+/// The conditions of a word list for studying patterns, e.g. the neuter nouns ending
+/// in "nis".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordList {
+    pub gender: Option<Gender>,
+    /// Lowercase.
+    pub prefix: Option<String>,
+    /// Lowercase.
+    pub suffix: Option<String>,
+    pub class: Option<WordClass>,
+    pub limit: usize,
+}
+
+impl WordList {
+    /// Whether a lowercased keyword can be in the list. Cheaper than [`WordList::keeps`].
+    pub fn keeps_keyword(&self, keyword: &str) -> bool {
+        self.prefix
+            .as_ref()
+            .is_none_or(|p| keyword.starts_with(p.as_str()))
+            && self
+                .suffix
+                .as_ref()
+                .is_none_or(|s| keyword.ends_with(s.as_str()))
+    }
+
+    /// Whether the entry is for the given headword and matches the conditions.
+    pub fn keeps(&self, entry: &Entry, keyword: &str) -> bool {
+        entry.german.match_exact(keyword)
+            && self.gender.is_none_or(|g| entry.german.gender() == Some(g))
+            && self
+                .class
+                .as_ref()
+                .is_none_or(|c| entry.word_classes.contains(c))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueOutput {
+    /// The due cards.
+    List,
+    /// Only their number, for shell prompts.
+    Count,
+    /// A desktop notification if any are due, for timers.
+    Notify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonAction {
+    Run,
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShowTarget {
+    Id(EntryId),
+    /// A card per sense of the word.
+    Word(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupAction {
+    /// Bundle all user files into the given file.
+    Create(PathBuf),
+    /// Put the files bundled in the given file back in place.
+    Restore(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VocabAction {
+    List,
+    Clear,
+    /// Write the vocabulary as flashcards to the given file, one for every saved sense
+    /// with `per_sense`.
+    Export {
+        out: PathBuf,
+        per_sense: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum KnownAction {
+    List,
+    Add(Vec<String>),
+    Remove(Vec<String>),
+}
+
+/// How results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// Newline delimited JSON, streamed while the dictionary is scanned.
+    Ndjson,
+}
+
+impl TryFrom<&str> for OutputFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            _ => Err(anyhow!(
+                "unknown format \"{}\", expected text, json or ndjson",
+                value
+            )),
+        }
+    }
+}
+
+/// How much lookups show of their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// One of the others, picked by the number of results.
+    #[default]
+    Auto,
+    /// Everything about every result, like the `dv` and `ev` queries.
+    Card,
+    /// A line per result.
+    List,
+    /// The results grouped by word class, with a few translations each.
+    Summary,
+}
+
+impl Verbosity {
+    /// From this many results on, [`Verbosity::Auto`] only summarizes them.
+    pub const SUMMARY_FROM: usize = 100;
+
+    /// Resolves [`Verbosity::Auto`] for the number of results: a single result gets a
+    /// card, a few a list and many a summary.
+    pub fn resolve(self, results: usize) -> Self {
+        match self {
+            Self::Auto if results == 1 => Self::Card,
+            Self::Auto if results >= Self::SUMMARY_FROM => Self::Summary,
+            Self::Auto => Self::List,
+            verbosity => verbosity,
+        }
+    }
+}
+
+impl TryFrom<&str> for Verbosity {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "auto" => Ok(Self::Auto),
+            "card" => Ok(Self::Card),
+            "list" => Ok(Self::List),
+            "summary" => Ok(Self::Summary),
+            _ => Err(anyhow!(
+                "unknown verbosity \"{}\", expected auto, card, list or summary",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageMode {
+    Auto,
+    Fixed(Language),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub debug_refs: bool,
+    /// Show how each result's score is made up.
+    pub explain: bool,
+    /// Print results as JSON instead of human readable lines.
+    pub json: bool,
+    /// Stream results as newline delimited JSON while the dictionary is scanned.
+    pub ndjson: bool,
+    /// The format asked for with `--format`, `--json` or `--ndjson`, which
+    /// [`Options::set_format`] has to be called with when it changes.
+    pub format: Option<OutputFormat>,
+    /// Whether to color the output, if asked for with `--color` or `--no-color`.
+    pub color: Option<bool>,
+    /// A dictionary file to use instead of the embedded one.
+    pub dict: Option<PathBuf>,
+    /// How verbose output writes the cases placeholders require.
+    pub case_style: CaseStyle,
+    /// Whether placeholders are written out, if asked for with `--placeholders`.
+    pub placeholders: Option<PlaceholderStyle>,
+    /// How much lookups show, if asked for with `--verbosity`.
+    pub verbosity: Option<Verbosity>,
+    /// Whether variants are written out as headwords of their own, if asked for with
+    /// `--variants`.
+    pub variants: Option<VariantStyle>,
+    /// Overrides the language implied by the query specifier.
+    pub language: Option<LanguageMode>,
+    /// Only show entries tagged with this subject.
+    pub subject: Option<Subject>,
+    /// Only show entries from this dictionary.
+    pub source: Option<Origin>,
+    /// Append pronunciation hints to English terms.
+    pub pronunciation: bool,
+    /// Match "uber" and "ueber" to "über", "Grosse" to "Große" and so on.
+    pub ignore_diacritics: bool,
+    /// Take the capitalization of the query as given, instead of the dictionary's. It
+    /// decides whether German nouns or other words are ranked first.
+    pub match_case: bool,
+    /// The ranking weights, taken from the config file.
+    pub weights: Weights,
+    /// The names of blocking rules lifted with `--show`.
+    pub show: Vec<String>,
+    /// The blocking rules in effect, taken from the config file.
+    pub block: Vec<Rule>,
+    /// Show the word classes of saved words, taken from the config file.
+    pub saved_grammar: bool,
+    /// Append the statistics of interactive sessions to the history file, if the config
+    /// file asks for it or `DICT_CC_HISTORY` is set.
+    pub record_sessions: bool,
+    /// Add the words saved in interactive mode and the reviewed cards to the known words,
+    /// if the config file asks for it or `DICT_CC_KNOWN_WORDS` is set.
+    pub seed_known_words: bool,
+    /// How long scans of the dictionary may take before returning what they found.
+    pub timeout: Option<Duration>,
+    /// Show at most this many results, or list at most this many words.
+    pub limit: Option<usize>,
+    /// Show the results a terminal page at a time.
+    pub page: bool,
+}
+
+impl Options {
+    /// Removes the recognized flags from the arguments, leaving only the query.
+    pub fn extract(args: &mut Vec<String>) -> anyhow::Result<Self> {
+        let mut options = Self::default();
+        let mut rest = Vec::with_capacity(args.len());
+        let mut it = args.drain(..);
+
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "--debug-refs" => options.debug_refs = true,
+                "--explain" => options.explain = true,
+                "--json" => options.set_format(OutputFormat::Json),
+                "--ndjson" => options.set_format(OutputFormat::Ndjson),
+                "--format" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --format"))?;
+                    options.set_format(value.as_str().try_into()?);
+                }
+                "--color" => options.color = Some(true),
+                "--no-color" => options.color = Some(false),
+                "--pron" => options.pronunciation = true,
+                "--ignore-diacritics" => options.ignore_diacritics = true,
+                "--match-case" => options.match_case = true,
+                "--page" => options.page = true,
+                "--limit" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --limit"))?;
+                    options.limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| anyhow!("invalid limit \"{}\"", value))?,
+                    );
+                }
+                "--cases" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --cases"))?;
+                    options.case_style = value.as_str().try_into()?;
+                }
+                "--placeholders" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --placeholders"))?;
+                    options.placeholders = Some(value.as_str().try_into()?);
+                }
+                "--variants" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --variants"))?;
+                    options.variants = Some(value.as_str().try_into()?);
+                }
+                "--verbosity" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --verbosity"))?;
+                    options.verbosity = Some(value.as_str().try_into()?);
+                }
+                "--dict" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --dict"))?;
+                    options.dict = Some(value.into());
+                }
+                "--show" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --show"))?;
+                    options.show.push(value);
+                }
+                "--subject" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --subject"))?;
+                    options.subject = Some(value.as_str().into());
+                }
+                "--source" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --source"))?;
+                    options.source = Some(value.as_str().try_into()?);
+                }
+                "--timeout" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --timeout"))?;
+                    options.timeout = Some(parse_duration(&value).ok_or_else(|| {
+                        anyhow!("invalid timeout \"{}\", expected e.g. 2s or 500ms", value)
+                    })?);
+                }
+                "--lang" => {
+                    let value = it
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for --lang"))?;
+                    options.language = Some(match value.as_str() {
+                        "auto" => LanguageMode::Auto,
+                        "de" => LanguageMode::Fixed(Language::German),
+                        "en" => LanguageMode::Fixed(Language::English),
+                        _ => return Err(anyhow!("unknown language \"{}\"", value)),
+                    });
+                }
+                _ => rest.push(arg),
+            }
+        }
+
+        drop(it);
+        *args = rest;
+
+        Ok(options)
+    }
+
+    /// Sets the output format along with the JSON flags derived from it.
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = Some(format);
+        self.json = format == OutputFormat::Json;
+        self.ndjson = format == OutputFormat::Ndjson;
+    }
+
+    /// How terms are rendered in lookup, interactive and export output.
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            placeholders: self.placeholders.unwrap_or_default(),
+            variants: self.variants.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// How much a lookup with the given number of results shows. Asking for a number of
+    /// results or pages asks for a list.
+    pub fn verbosity_for(&self, results: usize) -> Verbosity {
+        match self.verbosity.unwrap_or_default() {
+            Verbosity::Auto if self.limit.is_some() || self.page || self.explain => Verbosity::List,
+            verbosity => verbosity.resolve(results),
+        }
+    }
+
+    /// Whether the entry passes the filters given on the command line.
+    pub fn keeps(&self, entry: &Entry) -> bool {
+        self.subject
+            .as_ref()
+            .is_none_or(|s| entry.subjects.iter().any(|v| v.matches(s.as_str())))
+            && self.source.is_none_or(|o| entry.source.origin == o)
+            && !self.block.iter().any(|r| r.blocks(entry))
+    }
+}
+
+/// Parses a selection of results, as in interactive mode. Selections start with '#', as
+/// in "#1 3 5". Plain numbers are accepted as well as long as they all refer to shown
+/// results, so that searching for numbers still works otherwise.
+pub fn parse_selection(input: &str, result_count: usize) -> Option<Vec<usize>> {
+    let input = input.trim();
+    let (numbers, explicit) = match input.strip_prefix('#') {
+        Some(rest) => (rest, true),
+        None => (input, false),
+    };
+
+    let selection = numbers
+        .split_ascii_whitespace()
+        .map(|n| n.trim_start_matches('#').parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    let is_selection = explicit || selection.iter().all(|i| *i < result_count);
+    (is_selection && !selection.is_empty()).then_some(selection)
+}
+
+/// Parses durations like "500ms", "2s" or "1m".
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit_at = s.find(|c: char| !c.is_ascii_digit())?;
+    let n: u64 = s[..unit_at].parse().ok()?;
+    match &s[unit_at..] {
+        "ms" => Some(Duration::from_millis(n)),
+        "s" => Some(Duration::from_secs(n)),
+        "m" => Some(Duration::from_secs(n * 60)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Compose characters into their canonical form, so "a" followed by a combining
+    /// diaeresis equals "ä".
+    pub nfc: bool,
+    pub case_fold: bool,
+    /// Replace umlauts and "ß" with their ASCII spellings ("ae", "ss", ...).
+    pub transliterate_umlauts: bool,
+    /// Map spelled out or abbreviated placeholders ("jemandem", "sth") to dict.cc notation.
+    /// Only applies to phrases, as single words like "etwas" are headwords of their own.
+    pub map_placeholders: bool,
+    /// Remove the leading "to" of English infinitives.
+    pub strip_to: bool,
+}
+
+impl NormalizeOptions {
+    /// The normalization the CLI applies to queries in the given language.
+    pub fn for_language(language: Language) -> Self {
+        Self {
+            nfc: true,
+            case_fold: false,
+            transliterate_umlauts: false,
+            map_placeholders: true,
+            strip_to: language == Language::English,
+        }
+    }
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            nfc: true,
+            case_fold: false,
+            transliterate_umlauts: false,
+            map_placeholders: false,
+            strip_to: false,
+        }
+    }
+}
+
+/// Normalizes a query according to the given options. Whitespace is always collapsed.
+pub fn normalize(input: &str, options: &NormalizeOptions) -> String {
+    let input = util::strip_invisible(input);
+    let mut s: String = if options.nfc {
+        input.nfc().collect()
+    } else {
+        input.to_string()
+    };
+
+    if options.case_fold {
+        s = s.to_lowercase();
+    }
+
+    if options.transliterate_umlauts {
+        s = s
+            .chars()
+            .fold(String::with_capacity(s.len()), |mut out, c| {
+                match c {
+                    'ä' => out.push_str("ae"),
+                    'ö' => out.push_str("oe"),
+                    'ü' => out.push_str("ue"),
+                    'Ä' => out.push_str("Ae"),
+                    'Ö' => out.push_str("Oe"),
+                    'Ü' => out.push_str("Ue"),
+                    'ß' => out.push_str("ss"),
+                    'ẞ' => out.push_str("SS"),
+                    c => out.push(c),
+                }
+                out
+            });
+    }
+
+    let mut words: Vec<&str> = s.split_whitespace().collect();
+
+    if options.strip_to && words.len() > 1 && words[0].eq_ignore_ascii_case("to") {
+        words.remove(0);
+    }
+
+    if options.map_placeholders && words.len() > 1 {
+        for word in words.iter_mut() {
+            if let Some(placeholder) = map_placeholder(word) {
+                *word = placeholder;
+            }
+        }
+    }
+
+    words.join(" ")
+}
+
+fn map_placeholder(word: &str) -> Option<&'static str> {
+    Some(match word.to_lowercase().as_str() {
+        "etw" | "etwas" => "etw.",
+        "jd" | "jemand" => "jd.",
+        "jdn" | "jemanden" => "jdn.",
+        "jdm" | "jemandem" => "jdm.",
+        "jds" | "jemandes" => "jds.",
+        "sth" | "something" => "sth.",
+        "sb" | "somebody" | "someone" => "sb.",
+        _ => return None,
+    })
+}
+
+/// Guesses whether the input is German or English from its character n-grams, returning
+/// the language together with a confidence between 0.5 and 1.
+pub fn detect_language(input: &str) -> (Language, f32) {
+    detect_language_with(input, |_| None)
+}
+
+/// Like [`detect_language`], but additionally consults `known` for every word, which
+/// should return the language of words found in a dictionary index. Dictionary
+/// membership outweighs the n-gram evidence.
+pub fn detect_language_with(
+    input: &str,
+    known: impl Fn(&str) -> Option<Language>,
+) -> (Language, f32) {
+    // Positive weights hint at German, negative ones at English. "^" and "$" mark the
+    // start and end of a word.
+    const NGRAMS: &[(&str, f32)] = &[
+        ("ä", 3.0),
+        ("ö", 3.0),
+        ("ü", 3.0),
+        ("ß", 3.0),
+        ("sch", 1.5),
+        ("cht", 1.5),
+        ("ung$", 1.5),
+        ("ei", 0.8),
+        ("ie", 0.5),
+        ("tz", 1.0),
+        ("pf", 1.0),
+        ("chen$", 1.5),
+        ("lich", 1.2),
+        ("keit", 1.5),
+        ("heit", 1.5),
+        ("en$", 0.7),
+        ("^ge", 0.7),
+        ("^z", 0.8),
+        ("kk", 0.5),
+        ("th", -1.5),
+        ("^wh", -1.5),
+        ("sh", -1.2),
+        ("ing$", -1.5),
+        ("tion$", -0.5),
+        ("ea", -1.0),
+        ("ou", -1.0),
+        ("oo", -0.8),
+        ("y$", -1.0),
+        ("ly$", -1.0),
+        ("^c", -0.5),
+        ("w$", -1.0),
+        ("ck$", -0.3),
+        ("^the$", -3.0),
+        ("^to$", -2.0),
+        ("^of$", -2.0),
+        ("^der$", 3.0),
+        ("^die$", 1.5),
+        ("^das$", 3.0),
+        ("^und$", 3.0),
+        ("^ins$", 2.0),
+        ("^im$", 2.0),
+        ("^ein$", 2.0),
+        ("^eine$", 2.0),
+        ("^mit$", 2.0),
+        ("^auf$", 2.0),
+        ("^zu$", 2.0),
+        ("^and$", -3.0),
+        ("^into$", -3.0),
+        ("^with$", -3.0),
+        ("^a$", -2.0),
+    ];
+
+    let is_phrase = input.split_whitespace().nth(1).is_some();
+    let mut score = 0.0;
+
+    for word in input.split_whitespace() {
+        match known(word) {
+            Some(Language::German) => score += 4.0,
+            Some(Language::English) => score -= 4.0,
+            None => {}
+        }
+
+        // Nouns are capitalized in German, so capitalized words inside a phrase are a hint,
+        // unless the phrase is a headline in all caps.
+        if is_phrase && word.starts_with(char::is_uppercase) && !util::is_all_caps(input) {
+            score += 0.5;
+        }
+
+        let word = format!("^{}$", word.to_lowercase());
+        score += NGRAMS
+            .iter()
+            .filter(|(ngram, _)| word.contains(ngram))
+            .map(|(_, weight)| weight)
+            .sum::<f32>();
+    }
+
+    let confidence = 1.0 / (1.0 + (-score.abs()).exp());
+    if score >= 0.0 {
+        (Language::German, confidence)
+    } else {
+        (Language::English, confidence)
+    }
 }
 
 impl TryFrom<Vec<String>> for Query {
@@ -30,10 +734,12 @@ impl TryFrom<Vec<String>> for Query {
         }
 
         let maybe_specifier = value.remove(0);
-        if value.is_empty() && maybe_specifier.to_lowercase() != "i" {
+        let is_command = cli::command_of(&maybe_specifier).is_some_and(|c| c.standalone);
+        if value.is_empty() && !is_command {
+            let options = NormalizeOptions::for_language(Language::German);
             return Ok(Query::Meaning {
                 language: Language::German,
-                components: maybe_specifier
+                components: normalize(&maybe_specifier, &options)
                     .split_whitespace()
                     .map(String::from)
                     .collect(),
@@ -42,11 +748,31 @@ impl TryFrom<Vec<String>> for Query {
         }
 
         let (language, verbose) = match maybe_specifier.to_lowercase().deref() {
-            "g" => {
+            "g" | "gender" => {
+                if value[0] == "--batch" {
+                    let path = value.get(1).ok_or_else(|| anyhow!("missing batch file"))?;
+                    return Ok(Query::GenderBatch(path.into()));
+                }
+
                 let word =
                     to_upper(value.first().unwrap()).ok_or_else(|| anyhow!("empty input"))?;
                 return Ok(Query::Gender(word));
             }
+            "ge" => {
+                let word = normalize(
+                    &value.join(" "),
+                    &NormalizeOptions::for_language(Language::English),
+                );
+                return Ok(Query::ReverseGender(word));
+            }
+            "df" | "ef" => {
+                let language = match maybe_specifier.to_lowercase().as_str() {
+                    "df" => Language::German,
+                    _ => Language::English,
+                };
+                let word = normalize(&value.join(" "), &NormalizeOptions::for_language(language));
+                return Ok(Query::Fuzzy { language, word });
+            }
             "d" => (Language::German, false),
             "dv" => (Language::German, true),
             "e" => (Language::English, false),
@@ -56,14 +782,278 @@ impl TryFrom<Vec<String>> for Query {
                     language: Language::German,
                 })
             }
+            "export" => {
+                let mut filters = vec![];
+                let mut out = None;
+                let mut args = value.iter();
+                while let Some(arg) = args.next() {
+                    let mut flag_value = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("missing value for \"{}\"", arg))
+                    };
+                    match arg.as_str() {
+                        "--filter" => filters.push(flag_value()?.as_str().try_into()?),
+                        "--out" => out = Some(flag_value()?.into()),
+                        _ => return Err(anyhow!("unexpected export argument \"{}\"", arg)),
+                    }
+                }
+                let out = out.ok_or_else(|| anyhow!("missing --out for export"))?;
+                return Ok(Query::Export { filters, out });
+            }
+            "merge" => {
+                let mut inputs = vec![];
+                let mut out = None;
+                let mut args = value.iter();
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--out" => {
+                            let path = args
+                                .next()
+                                .ok_or_else(|| anyhow!("missing value for \"--out\""))?;
+                            out = Some(path.into());
+                        }
+                        _ => inputs.push(arg.into()),
+                    }
+                }
+                if inputs.len() < 2 {
+                    return Err(anyhow!("merge needs at least two dictionaries"));
+                }
+                let out = out.ok_or_else(|| anyhow!("missing --out for merge"))?;
+                return Ok(Query::Merge { inputs, out });
+            }
+            "paths" => return Ok(Query::Paths),
+            "b" => {
+                return match &value[..] {
+                    [] => Ok(Query::Batch(None)),
+                    [path] if path == "-" => Ok(Query::Batch(None)),
+                    [path] => Ok(Query::Batch(Some(path.into()))),
+                    _ => Err(anyhow!("usage: b [FILE]")),
+                }
+            }
+            "serve" => {
+                return match &value[..] {
+                    [] => Ok(Query::Serve(None)),
+                    [addr] => Ok(Query::Serve(Some(addr.clone()))),
+                    _ => Err(anyhow!("serve takes at most an address")),
+                }
+            }
+            "t" => return Ok(Query::Browse),
+            "q" => return Ok(Query::Review),
+            "list" => {
+                let mut list = WordList {
+                    gender: None,
+                    prefix: None,
+                    suffix: None,
+                    class: None,
+                    limit: 50,
+                };
+                let mut args = value.iter();
+                while let Some(arg) = args.next() {
+                    let flag_value = args
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for \"{}\"", arg))?;
+                    match arg.as_str() {
+                        "--gender" => {
+                            list.gender = Some(Gender::from_tag(flag_value).ok_or_else(|| {
+                                anyhow!("invalid gender \"{}\", expected m, f or n", flag_value)
+                            })?)
+                        }
+                        "--prefix" => list.prefix = Some(flag_value.to_lowercase()),
+                        "--suffix" => list.suffix = Some(flag_value.to_lowercase()),
+                        "--class" => list.class = Some(flag_value.as_str().into()),
+                        _ => return Err(anyhow!("unexpected list argument \"{}\"", arg)),
+                    }
+                }
+                return Ok(Query::List(list));
+            }
+            "stats" => {
+                return match value.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                    [] => Ok(Query::Stats { analyses: false }),
+                    ["--linguistics"] => Ok(Query::Stats { analyses: true }),
+                    _ => Err(anyhow!("usage: stats [--linguistics]")),
+                };
+            }
+            "near" => {
+                let [word] = &value[..] else {
+                    return Err(anyhow!("usage: near WORD"));
+                };
+                let options = NormalizeOptions::for_language(Language::German);
+                return Ok(Query::Near(normalize(word, &options)));
+            }
+            "p" => {
+                let options = NormalizeOptions::for_language(Language::German);
+                return Ok(Query::Phrases(normalize(&value.join(" "), &options)));
+            }
+            "r" => {
+                let options = NormalizeOptions::for_language(Language::German);
+                let usage = || anyhow!("usage: r WORD [--count N]");
+                let (word, limit) = match &value[..] {
+                    [word] => (word, 10),
+                    [word, flag, count] if flag == "--count" => (
+                        word,
+                        count
+                            .parse()
+                            .map_err(|_| anyhow!("invalid count \"{}\"", count))?,
+                    ),
+                    _ => return Err(usage()),
+                };
+                return Ok(Query::Related {
+                    word: normalize(word, &options),
+                    limit,
+                });
+            }
+            "compare" => {
+                let [first, second] = &value[..] else {
+                    return Err(anyhow!("usage: compare WORD1 WORD2"));
+                };
+                let options = NormalizeOptions::for_language(Language::German);
+                return Ok(Query::Compare(
+                    normalize(first, &options),
+                    normalize(second, &options),
+                ));
+            }
+            "v" => {
+                return match value[0].as_str() {
+                    "list" => Ok(Query::Vocab(VocabAction::List)),
+                    "clear" => Ok(Query::Vocab(VocabAction::Clear)),
+                    "export" => {
+                        let per_sense = value[1..].iter().any(|v| v == "--per-sense");
+                        let args: Vec<_> =
+                            value[1..].iter().filter(|v| *v != "--per-sense").collect();
+                        match args[..] {
+                            [flag, out] if flag == "--out" => {
+                                Ok(Query::Vocab(VocabAction::Export {
+                                    out: out.into(),
+                                    per_sense,
+                                }))
+                            }
+                            _ => Err(anyhow!(
+                                "usage: v export --out <file.tsv|file.csv> [--per-sense]"
+                            )),
+                        }
+                    }
+                    action => Err(anyhow!("invalid vocab action \"{}\"", action)),
+                };
+            }
+            "daemon" => {
+                return match value.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                    [] => Ok(Query::Daemon(DaemonAction::Run)),
+                    ["stop"] => Ok(Query::Daemon(DaemonAction::Stop)),
+                    _ => Err(anyhow!("usage: daemon [stop]")),
+                };
+            }
+            "index" => {
+                return match &value[..] {
+                    [action] if action == "build" => Ok(Query::BuildIndex),
+                    _ => Err(anyhow!("usage: index build")),
+                };
+            }
+            "update" => {
+                return match &value[..] {
+                    [source] => Ok(Query::Update(source.clone())),
+                    _ => Err(anyhow!("usage: update <path or URL of a dict.cc export>")),
+                };
+            }
+            "completions" => {
+                return match &value[..] {
+                    [shell] => Ok(Query::Completions(shell.as_str().try_into()?)),
+                    _ => Err(anyhow!("usage: completions bash|zsh|fish")),
+                };
+            }
+            "complete" => {
+                return match &value[..] {
+                    [what] if what == "words" => Ok(Query::CompleteWords),
+                    _ => Err(anyhow!("usage: complete words")),
+                };
+            }
+            "explain" => return Ok(Query::Explain(value)),
+            "show" => {
+                return match &value[..] {
+                    [] => Err(anyhow!("usage: show ID|WORD")),
+                    [id] => match id.parse() {
+                        Ok(id) => Ok(Query::Show(ShowTarget::Id(id))),
+                        Err(_) => Ok(Query::Show(ShowTarget::Word(id.clone()))),
+                    },
+                    words => Ok(Query::Show(ShowTarget::Word(words.join(" ")))),
+                };
+            }
+            "backup" => {
+                return match &value[..] {
+                    [action, file] if action == "create" => {
+                        Ok(Query::Backup(BackupAction::Create(file.into())))
+                    }
+                    [action, file] if action == "restore" => {
+                        Ok(Query::Backup(BackupAction::Restore(file.into())))
+                    }
+                    _ => Err(anyhow!("usage: backup create|restore FILE")),
+                };
+            }
+            "review" => {
+                match value[0].as_str() {
+                    "due" => {}
+                    "stats" => return Ok(Query::ReviewStats),
+                    action => return Err(anyhow!("invalid review action \"{}\"", action)),
+                }
+                let output = match value.get(1).map(String::as_str) {
+                    None => DueOutput::List,
+                    Some("--count") => DueOutput::Count,
+                    Some("--notify") => DueOutput::Notify,
+                    Some(flag) => return Err(anyhow!("unknown flag \"{}\"", flag)),
+                };
+                return Ok(Query::ReviewDue(output));
+            }
+            "session" => {
+                let mut text = None;
+                let mut minutes = None;
+                let mut args = value.iter();
+                while let Some(arg) = args.next() {
+                    let flag_value = args
+                        .next()
+                        .ok_or_else(|| anyhow!("missing value for \"{}\"", arg))?;
+                    match arg.as_str() {
+                        "--text" => text = Some(flag_value.into()),
+                        "--minutes" => {
+                            minutes = Some(flag_value.parse().map_err(|_| {
+                                anyhow!("invalid number of minutes \"{}\"", flag_value)
+                            })?)
+                        }
+                        _ => return Err(anyhow!("unexpected session argument \"{}\"", arg)),
+                    }
+                }
+                let text =
+                    text.ok_or_else(|| anyhow!("usage: session --text FILE [--minutes N]"))?;
+                return Ok(Query::Session { text, minutes });
+            }
+            "quiz" => {
+                if value[0] != "recent" {
+                    return Err(anyhow!("invalid quiz \"{}\"", value[0]));
+                }
+                let days = match &value[1..] {
+                    [] => 7,
+                    [flag, days] if flag == "--days" => days
+                        .parse()
+                        .map_err(|_| anyhow!("invalid number of days \"{}\"", days))?,
+                    _ => return Err(anyhow!("usage: quiz recent [--days N]")),
+                };
+                return Ok(Query::QuizRecent { days });
+            }
+            "known" => {
+                let words = value[1..].to_vec();
+                return match value[0].as_str() {
+                    "list" => Ok(Query::Known(KnownAction::List)),
+                    "add" if !words.is_empty() => Ok(Query::Known(KnownAction::Add(words))),
+                    "remove" if !words.is_empty() => Ok(Query::Known(KnownAction::Remove(words))),
+                    action => Err(anyhow!("invalid known words action \"{}\"", action)),
+                };
+            }
             _ => return Err(anyhow!("invalid query specifier \"{}\"", maybe_specifier)),
         };
 
-        let components: Vec<_> = value
-            .iter()
-            .flat_map(|v| v.split_whitespace())
-            .map(String::from)
-            .collect();
+        let components: Vec<_> =
+            normalize(&value.join(" "), &NormalizeOptions::for_language(language))
+                .split_whitespace()
+                .map(String::from)
+                .collect();
 
         Ok(Query::Meaning {
             language,
@@ -73,9 +1063,117 @@ impl TryFrom<Vec<String>> for Query {
     }
 }
 
-fn to_upper(s: &str) -> Option<String> {
+pub fn to_upper(s: &str) -> Option<String> {
     let mut chars = s.chars();
     chars
         .next()
         .map(|c| c.to_uppercase().to_string() + chars.as_str())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_query() {
+        let all = NormalizeOptions {
+            nfc: true,
+            case_fold: true,
+            transliterate_umlauts: true,
+            map_placeholders: true,
+            strip_to: true,
+        };
+
+        let data = [
+            ("Ha\u{0308}user", NormalizeOptions::default(), "Häuser"),
+            (
+                "  jemandem   etwas leihen ",
+                NormalizeOptions::for_language(Language::German),
+                "jdm. etw. leihen",
+            ),
+            (
+                "to give sb sth",
+                NormalizeOptions::for_language(Language::English),
+                "give sb. sth.",
+            ),
+            (
+                "to",
+                NormalizeOptions::for_language(Language::English),
+                "to",
+            ),
+            (
+                "etwas",
+                NormalizeOptions::for_language(Language::German),
+                "etwas",
+            ),
+            (
+                "something",
+                NormalizeOptions::for_language(Language::English),
+                "something",
+            ),
+            (
+                "to someone",
+                NormalizeOptions::for_language(Language::English),
+                "someone",
+            ),
+            ("Große Straße", all, "grosse strasse"),
+            ("U\u{0308}BER", all, "ueber"),
+        ];
+
+        for (input, options, expected) in data {
+            assert_eq!(normalize(input, &options), expected);
+        }
+    }
+
+    #[test]
+    fn detect_language_ngrams() {
+        let data = [
+            ("Schmetterling", Language::German),
+            ("Größe", Language::German),
+            ("Zeitung", Language::German),
+            ("ins Bockshorn jagen", Language::German),
+            ("the weather", Language::English),
+            ("take into account", Language::English),
+            ("butterfly", Language::English),
+        ];
+
+        for (input, expected) in data {
+            assert_eq!(detect_language(input).0, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn timeout_flag() {
+        let mut args: Vec<String> = ["--timeout", "500ms", "d", "Hund"].map(String::from).into();
+        let options = Options::extract(&mut args).unwrap();
+        assert_eq!(options.timeout, Some(Duration::from_millis(500)));
+        assert_eq!(args, ["d", "Hund"]);
+
+        assert_eq!(parse_duration("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1m"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("2"), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("2h"), None);
+    }
+
+    #[test]
+    fn paging_flags() {
+        let mut args: Vec<String> = ["d", "gehen", "--limit", "20", "--page"]
+            .map(String::from)
+            .into();
+        let options = Options::extract(&mut args).unwrap();
+        assert_eq!((options.limit, options.page), (Some(20), true));
+        assert_eq!(args, ["d", "gehen"]);
+        assert!(Options::extract(&mut vec!["--limit".into(), "all".into()]).is_err());
+    }
+
+    #[test]
+    fn adaptive_verbosity() {
+        assert_eq!(Verbosity::Auto.resolve(1), Verbosity::Card);
+        assert_eq!(Verbosity::Auto.resolve(5), Verbosity::List);
+        assert_eq!(Verbosity::Auto.resolve(0), Verbosity::List);
+        assert_eq!(Verbosity::Auto.resolve(250), Verbosity::Summary);
+        assert_eq!(Verbosity::List.resolve(250), Verbosity::List);
+        assert_eq!(Verbosity::Summary.resolve(1), Verbosity::Summary);
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+
+use crate::{
+    entry::{AnnotationKind, Entry, Term, WordClass},
+    query::Language,
+    util,
+};
+
+/// Annotations marking a register or usage that is less likely what the user is after.
+const MARKED_REGISTERS: &[&str] = &[
+    "ugs.",
+    "coll.",
+    "vulg.",
+    "pej.",
+    "slang",
+    "derb",
+    "veraltet",
+    "veraltend",
+    "obs.",
+    "archaic",
+    "dated",
+    "selten",
+    "rare",
+    "hum.",
+    "Jugendspr.",
+];
+
+/// How much each component contributes to a match's score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    /// Base score of a term whose only keyword is the query.
+    pub exact: f32,
+    /// Base score of a term which has the query among several keywords.
+    pub keyword: f32,
+    /// Base score of a term with a keyword containing the query, scaled by how much
+    /// of the keyword the query covers.
+    pub substring: f32,
+    /// Subtracted for every keyword beyond the first.
+    pub length_penalty: f32,
+    /// Subtracted for every colloquial, vulgar, dated or rare marker.
+    pub register_penalty: f32,
+    /// Multiplied with the logarithm of how often the translation occurs.
+    pub frequency: f32,
+    /// Subtracted for every annotation and parenthesized group of the entry's terms.
+    pub complexity_penalty: f32,
+    /// Added if the entry's word class is the one the query suggests.
+    pub word_class: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            exact: 1.0,
+            keyword: 0.8,
+            substring: 0.5,
+            length_penalty: 0.05,
+            register_penalty: 0.1,
+            frequency: 0.05,
+            complexity_penalty: 0.02,
+            word_class: 0.1,
+        }
+    }
+}
+
+/// The components a match's score is made of.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    /// Exact versus loose match.
+    pub base: f32,
+    pub length_penalty: f32,
+    pub register_penalty: f32,
+    pub complexity_penalty: f32,
+    pub frequency_boost: f32,
+    pub word_class_boost: f32,
+}
+
+impl Score {
+    pub fn total(&self) -> f32 {
+        self.base - self.length_penalty - self.register_penalty - self.complexity_penalty
+            + self.frequency_boost
+            + self.word_class_boost
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} = match {:.3} - length {:.3} - register {:.3} - complexity {:.3} \
+             + frequency {:.3} + word class {:.3}",
+            self.total(),
+            self.base,
+            self.length_penalty,
+            self.register_penalty,
+            self.complexity_penalty,
+            self.frequency_boost,
+            self.word_class_boost
+        )
+    }
+}
+
+/// The penalty for the keywords of `term` beyond the first.
+pub fn length_penalty(term: &Term, weights: &Weights) -> f32 {
+    term.keywords().count().saturating_sub(1) as f32 * weights.length_penalty
+}
+
+/// The penalty for the register markers of the given terms.
+pub fn register_penalty<'a>(terms: impl IntoIterator<Item = &'a Term>, weights: &Weights) -> f32 {
+    let markers = terms
+        .into_iter()
+        .flat_map(Term::annotations)
+        .filter(|a| a.kind == AnnotationKind::Explanation && is_marked_register(a.value))
+        .count();
+
+    markers as f32 * weights.register_penalty
+}
+
+/// The penalty for the annotations and parenthesized groups of the given terms, so that
+/// plain entries come before heavily qualified ones.
+pub fn complexity_penalty<'a>(terms: impl IntoIterator<Item = &'a Term>, weights: &Weights) -> f32 {
+    let parts: usize = terms
+        .into_iter()
+        .map(|t| t.annotations().len() + t.group_count())
+        .sum();
+
+    parts as f32 * weights.complexity_penalty
+}
+
+/// The boost for an entry of the word class the query suggests: capitalized German
+/// words are nouns, other German words are not. English queries and German ones in all
+/// caps give no hint, so any entry with a word class gets the boost, which puts plain
+/// words before idioms and phrases.
+pub fn word_class_boost(entry: &Entry, word: &str, language: Language, weights: &Weights) -> f32 {
+    let expected = |class: &WordClass| match language {
+        Language::German if util::is_all_caps(word) => true,
+        Language::German if word.starts_with(char::is_uppercase) => *class == WordClass::Noun,
+        Language::German => *class != WordClass::Noun,
+        Language::English => true,
+    };
+
+    if entry.word_classes.iter().any(expected) {
+        weights.word_class
+    } else {
+        0.0
+    }
+}
+
+/// The boost for a translation shared by `occurrences` results. The dictionary has no
+/// frequency data, but common senses tend to be listed in many entries.
+pub fn frequency_boost(occurrences: usize, weights: &Weights) -> f32 {
+    (occurrences as f32).ln_1p() * weights.frequency
+}
+
+/// The annotation of the term marking its register, as in "[ugs.]", if any.
+pub fn register(term: &Term) -> Option<&str> {
+    term.annotations()
+        .into_iter()
+        .find(|a| a.kind == AnnotationKind::Explanation && is_marked_register(a.value))
+        .map(|a| a.value)
+}
+
+fn is_marked_register(value: &str) -> bool {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .any(|v| MARKED_REGISTERS.contains(&v))
+}
@@ -0,0 +1,282 @@
+//! Searches over the keywords of a dictionary other than looking them up: typo
+//! tolerant, by sort order and by components.
+
+use std::collections::HashMap;
+
+use crate::{query::Language, util, Dictionary};
+
+/// A keyword close to what was searched for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub keyword: String,
+    /// The number of single character edits between the query and the keyword.
+    pub distance: usize,
+    /// The number of entries the keyword occurs in.
+    pub frequency: usize,
+}
+
+/// The largest edit distance tolerated for a query, so that short words don't match
+/// half the dictionary.
+pub fn max_distance(query: &str) -> usize {
+    match query.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Finds the keywords within [`max_distance`] edits of the query, ignoring case. The
+/// closest and then the most frequent keywords come first.
+pub fn fuzzy<'a>(
+    query: &str,
+    keywords: impl IntoIterator<Item = (&'a str, usize)>,
+    limit: usize,
+) -> Vec<Suggestion> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let max = max_distance(&query.iter().collect::<String>());
+
+    let mut suggestions: Vec<_> = keywords
+        .into_iter()
+        .filter_map(|(keyword, frequency)| {
+            let lowercase: Vec<char> = keyword.to_lowercase().chars().collect();
+            let distance = edit_distance(&query, &lowercase, max)?;
+            Some(Suggestion {
+                keyword: keyword.to_string(),
+                distance,
+                frequency,
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        a.distance
+            .cmp(&b.distance)
+            .then(b.frequency.cmp(&a.frequency))
+            .then_with(|| a.keyword.cmp(&b.keyword))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Counts in how many entries each keyword of the given language occurs, for use with
+/// [`fuzzy`] when there is no index.
+pub fn keyword_frequencies(dict: &Dictionary, language: Language) -> HashMap<String, usize> {
+    let mut frequencies = HashMap::new();
+    for entry in dict.entries() {
+        for keyword in entry.term(language).keywords() {
+            *frequencies.entry(keyword.to_string()).or_default() += 1;
+        }
+    }
+    frequencies
+}
+
+/// The headwords around where a word sorts, like on the page of a paper dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbors<'a> {
+    pub before: Vec<&'a str>,
+    /// The headword equal to the word, if there is one.
+    pub found: Option<&'a str>,
+    pub after: Vec<&'a str>,
+}
+
+/// Whether the keyword can be a headword. Keywords keep the punctuation of phrases, as in
+/// "Hund!", which headwords don't have.
+pub fn is_headword(keyword: &str) -> bool {
+    keyword.starts_with(char::is_alphabetic)
+        && keyword.chars().all(|c| c.is_alphabetic() || c == '-')
+}
+
+/// Finds up to `count` headwords sorting right before and right after the word, in the
+/// order of a German dictionary (see [`util::collation_key`]).
+pub fn neighbors<'a>(
+    word: &str,
+    headwords: impl IntoIterator<Item = &'a str>,
+    count: usize,
+) -> Neighbors<'a> {
+    let mut sorted: Vec<_> = headwords
+        .into_iter()
+        .map(|h| (util::collation_key(h), h))
+        .collect();
+    sorted.sort_unstable();
+    sorted.dedup_by(|a, b| a.1 == b.1);
+
+    let key = (util::collation_key(word), word);
+    let pos = sorted.partition_point(|(k, h)| (k.as_str(), *h) < (key.0.as_str(), key.1));
+    // Another spelling of the word with the same key, e.g. "hund" for "Hund", sorts
+    // either right before or right after it.
+    let found = [pos.checked_sub(1), Some(pos)]
+        .into_iter()
+        .flatten()
+        .find(|&i| {
+            sorted
+                .get(i)
+                .is_some_and(|(_, h)| util::case_fold_eq(h, word))
+        });
+
+    let (end, start) = match found {
+        Some(i) => (i, i + 1),
+        None => (pos, pos),
+    };
+    Neighbors {
+        before: sorted[end.saturating_sub(count)..end]
+            .iter()
+            .map(|(_, h)| *h)
+            .collect(),
+        found: found.map(|i| sorted[i].1),
+        after: sorted[start..sorted.len().min(start + count)]
+            .iter()
+            .map(|(_, h)| *h)
+            .collect(),
+    }
+}
+
+/// The keywords containing a word as a component, by where in them it is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Related<'a> {
+    /// Like "Hausaufgabe" for "Haus".
+    pub starting: Vec<&'a str>,
+    /// Like "Krankenhaus" for "Haus".
+    pub ending: Vec<&'a str>,
+    /// Like "Gasthausbrauerei" for "Haus".
+    pub inside: Vec<&'a str>,
+}
+
+/// The fewest letters a keyword has to have next to the word for the word to count as a
+/// component of it, so that "Hause" and "Chaussee" aren't related to "Haus".
+const MIN_REST: usize = 2;
+
+/// Finds the keywords which contain the word as a component, ignoring case. The most
+/// frequent keywords of each group come first.
+pub fn related<'a>(
+    word: &str,
+    keywords: impl IntoIterator<Item = (&'a str, usize)>,
+) -> Related<'a> {
+    let word = word.to_lowercase();
+    if word.is_empty() {
+        return Related::default();
+    }
+
+    let mut found = [vec![], vec![], vec![]];
+    for (keyword, frequency) in keywords {
+        let lowercase = keyword.to_lowercase();
+        let Some(i) = lowercase.find(&word) else {
+            continue;
+        };
+        let before = lowercase[..i].chars().count();
+        let after = lowercase[i + word.len()..].chars().count();
+        let group = match (before, after) {
+            (0, n) if n >= MIN_REST => 0,
+            (n, 0) if n >= MIN_REST => 1,
+            (m, n) if m >= MIN_REST && n >= MIN_REST => 2,
+            _ => continue,
+        };
+        found[group].push((frequency, util::collation_key(keyword), keyword));
+    }
+
+    let [starting, ending, inside] = found.map(|mut group| {
+        group.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| (&a.1, a.2).cmp(&(&b.1, b.2))));
+        group.dedup_by(|a, b| a.2 == b.2);
+        group.into_iter().map(|(_, _, k)| k).collect()
+    });
+    Related {
+        starting,
+        ending,
+        inside,
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`, or `None` if it exceeds `max`.
+pub fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        let mut row_min = cur[0];
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+            row_min = row_min.min(cur[j + 1]);
+        }
+
+        // Distances never shrink from one row to the next.
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    Some(prev[b.len()]).filter(|d| *d <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_ranking() {
+        let keywords = [
+            ("schmetterling", 40),
+            ("schmetterlinge", 3),
+            ("schmettern", 12),
+            ("hund", 100),
+        ];
+
+        let suggestions = fuzzy("Schmeterling", keywords, 10);
+        let found: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.keyword.as_str(), s.distance))
+            .collect();
+        assert_eq!(found, [("schmetterling", 1), ("schmetterlinge", 2)]);
+
+        assert!(fuzzy("Hnd", keywords, 10).is_empty());
+        assert_eq!(fuzzy("Hunf", keywords, 10)[0].keyword, "hund");
+    }
+
+    #[test]
+    fn neighbors_sort_like_a_dictionary() {
+        let headwords = [
+            "Zwerg",
+            "Äpfel",
+            "Apfel",
+            "Apfelbaum",
+            "Abend",
+            "apfel",
+            "Affe",
+        ];
+
+        let near = neighbors("Apfel", headwords, 2);
+        assert_eq!(near.before, ["Abend", "Affe"]);
+        assert_eq!(near.found, Some("Apfel"));
+        assert_eq!(near.after, ["apfel", "Äpfel"]);
+
+        let near = neighbors("Apfelsine", headwords, 2);
+        assert_eq!(near.before, ["Äpfel", "Apfelbaum"]);
+        assert_eq!(near.found, None);
+        assert_eq!(near.after, ["Zwerg"]);
+    }
+
+    #[test]
+    fn related_compounds() {
+        let keywords = [
+            ("Krankenhaus", 12),
+            ("Hausaufgabe", 5),
+            ("Hausarzt", 5),
+            ("Gasthausbrauerei", 1),
+            ("Haus", 80),
+            ("Hause", 30),
+            ("Chaussee", 4),
+            ("Hund", 40),
+        ];
+
+        let related = related("haus", keywords);
+        assert_eq!(related.starting, ["Hausarzt", "Hausaufgabe"]);
+        assert_eq!(related.ending, ["Krankenhaus"]);
+        assert_eq!(related.inside, ["Gasthausbrauerei"]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Figures about the dictionary as a whole. Counting entries is cheap, the analyses of
+//! the words themselves are for linguistics tinkering and have to be asked for.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    entry::{Case, Entry, Placeholder, WordClass},
+    json::gender_name,
+    Dictionary,
+};
+
+/// How many of the most common noun endings are listed per gender.
+const TOP_SUFFIXES: usize = 10;
+
+/// The length of the noun endings compared.
+const SUFFIX_LEN: usize = 3;
+
+/// Neither part of a compound is shorter than this, so that words like "Beet" aren't
+/// taken for "B" + "eet".
+const MIN_COMPONENT_LEN: usize = 3;
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub entries: usize,
+    /// The number of entries per gender of the German term.
+    pub genders: BTreeMap<&'static str, usize>,
+    pub word_classes: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analyses: Option<Analyses>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Analyses {
+    /// How often each letter occurs in the German keywords, ignoring case.
+    pub letters: BTreeMap<char, usize>,
+    /// The most common endings of the German nouns of each gender.
+    pub suffixes: BTreeMap<&'static str, Vec<SuffixCount>>,
+    pub compounds: CompoundStats,
+    /// How often the placeholders in German verb phrases require each case.
+    pub verb_cases: BTreeMap<&'static str, usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct SuffixCount {
+    pub suffix: String,
+    pub count: usize,
+}
+
+/// The single word nouns ending in another noun of the dictionary, like "Hundehütte".
+#[derive(Debug, Serialize)]
+pub struct CompoundStats {
+    pub count: usize,
+    /// In letters.
+    pub average_length: f64,
+}
+
+impl Stats {
+    pub fn new(dict: &Dictionary, analyze: bool) -> Self {
+        let mut genders = BTreeMap::new();
+        let mut word_classes = BTreeMap::new();
+        for entry in dict.entries() {
+            if let Some(gender) = entry.german.gender() {
+                *genders.entry(gender_name(gender)).or_default() += 1;
+            }
+            for class in &entry.word_classes {
+                *word_classes.entry(class.as_str().to_string()).or_default() += 1;
+            }
+        }
+
+        Self {
+            entries: dict.entries().len(),
+            genders,
+            word_classes,
+            analyses: analyze.then(|| Analyses::new(dict.entries())),
+        }
+    }
+}
+
+impl Analyses {
+    fn new(entries: &[Entry]) -> Self {
+        let mut letters = BTreeMap::new();
+        let mut suffixes = HashMap::<_, HashMap<String, usize>>::new();
+        let mut verb_cases = BTreeMap::new();
+        // Lowercase, to find the components of compounds regardless of their position.
+        let mut nouns = HashSet::new();
+
+        for entry in entries {
+            for keyword in entry.german.keywords() {
+                for c in keyword.chars().filter(|c| c.is_alphabetic()) {
+                    for c in c.to_lowercase() {
+                        *letters.entry(c).or_default() += 1;
+                    }
+                }
+            }
+
+            if let (Some(gender), Some(noun)) = (entry.german.gender(), single_word(entry)) {
+                let noun = noun.to_lowercase();
+                let chars: Vec<_> = noun.chars().collect();
+                if chars.len() > SUFFIX_LEN {
+                    let suffix = chars[chars.len() - SUFFIX_LEN..].iter().collect();
+                    *suffixes
+                        .entry(gender_name(gender))
+                        .or_default()
+                        .entry(suffix)
+                        .or_default() += 1;
+                }
+                nouns.insert(noun);
+            }
+
+            if entry.word_classes.contains(&WordClass::Verb) {
+                for placeholder in entry.german.placeholders() {
+                    let case = match placeholder {
+                        Placeholder::Person(case) => Some(*case),
+                        Placeholder::Thing(case) | Placeholder::Reflexive(case) => *case,
+                    };
+                    *verb_cases.entry(case_name(case)).or_default() += 1;
+                }
+            }
+        }
+
+        let suffixes = suffixes
+            .into_iter()
+            .map(|(gender, counts)| {
+                let mut counts: Vec<_> = counts
+                    .into_iter()
+                    .map(|(suffix, count)| SuffixCount { suffix, count })
+                    .collect();
+                counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.suffix.cmp(&b.suffix)));
+                counts.truncate(TOP_SUFFIXES);
+                (gender, counts)
+            })
+            .collect();
+
+        let compound_lengths: Vec<_> = nouns
+            .iter()
+            .filter(|noun| is_compound(noun, &nouns))
+            .map(|noun| noun.chars().count())
+            .collect();
+        let compounds = CompoundStats {
+            count: compound_lengths.len(),
+            average_length: match compound_lengths.len() {
+                0 => 0.0,
+                n => compound_lengths.iter().sum::<usize>() as f64 / n as f64,
+            },
+        };
+
+        Self {
+            letters,
+            suffixes,
+            compounds,
+            verb_cases,
+        }
+    }
+}
+
+/// The German term of the entry, if it is a single word.
+fn single_word(entry: &Entry) -> Option<&str> {
+    let mut keywords = entry.german.keywords();
+    match (keywords.next(), keywords.next()) {
+        (Some(keyword), None) if keyword.chars().all(char::is_alphabetic) => Some(keyword),
+        _ => None,
+    }
+}
+
+/// Whether the lowercased noun ends in another of the nouns.
+fn is_compound(noun: &str, nouns: &HashSet<String>) -> bool {
+    noun.char_indices().skip(MIN_COMPONENT_LEN).any(|(i, _)| {
+        let tail = &noun[i..];
+        tail.chars().count() >= MIN_COMPONENT_LEN && nouns.contains(tail)
+    })
+}
+
+fn case_name(case: Option<Case>) -> &'static str {
+    match case {
+        Some(Case::Nominative) => "nominative",
+        Some(Case::Accusative) => "accusative",
+        Some(Case::Dative) => "dative",
+        Some(Case::Genitive) => "genitive",
+        None => "unspecified",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stats, SuffixCount};
+    use crate::Dictionary;
+
+    #[test]
+    fn analyses() {
+        let dict = Dictionary::load(
+            "Hund {m}\tdog\tnoun\t\n\
+             Hütte {f}\thut\tnoun\t\n\
+             Hundehütte {f}\tkennel\tnoun\t\n\
+             Zeitung {f}\tnewspaper\tnoun\t\n\
+             jdm. etw. geben\tto give sb. sth.\tverb\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let stats = Stats::new(&dict, true);
+        assert_eq!(stats.entries, 5);
+        assert_eq!(stats.genders["feminine"], 3);
+        assert_eq!(stats.word_classes["verb"], 1);
+
+        let analyses = stats.analyses.unwrap();
+        assert_eq!(analyses.letters[&'h'], 4);
+        assert_eq!(
+            analyses.suffixes["feminine"][0],
+            SuffixCount {
+                suffix: "tte".into(),
+                count: 2
+            }
+        );
+        assert_eq!(analyses.compounds.count, 1);
+        assert_eq!(analyses.compounds.average_length, 10.0);
+        assert_eq!(analyses.verb_cases["dative"], 1);
+        assert_eq!(analyses.verb_cases["unspecified"], 1);
+
+        assert!(Stats::new(&dict, false).analyses.is_none());
+    }
+}
@@ -0,0 +1,38 @@
+use std::{collections::HashSet, fs, io, path::Path};
+
+const DEFAULT: &str = include_str!("stopwords.txt");
+
+/// Words that are not worth looking up when processing running text or word lists,
+/// such as articles, pronouns and prepositions.
+#[derive(Debug, Clone)]
+pub struct Stopwords(HashSet<String>);
+
+impl Stopwords {
+    /// Parses a stopword list with one word per line. Empty lines and lines starting
+    /// with `#` are ignored.
+    pub fn parse(input: &str) -> Self {
+        Self(
+            input
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_lowercase)
+                .collect(),
+        )
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        fs::read_to_string(path).map(|s| Self::parse(&s))
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(&word.to_lowercase())
+    }
+}
+
+impl Default for Stopwords {
+    /// The bundled German stopword list.
+    fn default() -> Self {
+        Self::parse(DEFAULT)
+    }
+}
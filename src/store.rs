@@ -0,0 +1,76 @@
+//! Writing the user's files safely. Files are replaced atomically, so that a crash or a
+//! full disk never leaves them half written, and logs are appended to a line at a time.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::lookups;
+
+/// What was done about a file which couldn't be read anymore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recovery {
+    /// The file was moved to the given path and its backup restored.
+    Restored { corrupt: PathBuf },
+    /// The file was moved to the given path and, without a usable backup, started over.
+    Reset { corrupt: PathBuf },
+}
+
+/// Replaces the file's contents by writing them to a temporary file next to it, which
+/// is then renamed over it.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let tmp = with_suffix(path, ".tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, path)
+}
+
+/// Like [`write_atomic`], keeping the previous contents at [`backup_path`].
+pub fn write_with_backup(path: &Path, contents: &[u8]) -> io::Result<()> {
+    match fs::copy(path, backup_path(path)) {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    write_atomic(path, contents)
+}
+
+/// Where [`write_with_backup`] keeps the previous contents of the file.
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Moves a corrupt file out of the way, keeping it for inspection. Returns where it went.
+pub fn set_aside(path: &Path) -> io::Result<PathBuf> {
+    let corrupt = with_suffix(path, &format!(".corrupt-{}", lookups::now()));
+    fs::rename(path, &corrupt)?;
+    Ok(corrupt)
+}
+
+/// Appends the line with a single write, so that concurrent writers don't interleave
+/// and a crash loses at most the line being written.
+pub fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", line).as_bytes())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
@@ -0,0 +1,74 @@
+//! Splitting running text into sentences and words, for reading it along with the
+//! dictionary.
+
+use crate::util;
+
+/// The closing quotes and brackets which may follow the end of a sentence.
+const CLOSING: [char; 7] = ['"', '\'', '“', '”', '«', '»', ')'];
+
+/// The sentences of the text, with their whitespace collapsed. A sentence ends with a
+/// word ending in ".", "!" or "?", or at a blank line, so that headings stand alone.
+/// Abbreviations like "z.B." don't end a sentence, ordinals like "3." and ones like
+/// "usw." do.
+pub fn sentences(text: &str) -> Vec<String> {
+    // Soft hyphens would split words.
+    let text = util::strip_invisible(text);
+    let mut sentences = vec![];
+    let mut current: Vec<&str> = vec![];
+
+    for line in text.lines() {
+        if line.trim().is_empty() && !current.is_empty() {
+            sentences.push(current.join(" "));
+            current.clear();
+        }
+
+        for word in line.split_whitespace() {
+            current.push(word);
+            let word = word.trim_end_matches(CLOSING);
+            let inner = word.trim_end_matches(['.', '!', '?']);
+            if inner.len() < word.len() && !inner.contains('.') {
+                sentences.push(current.join(" "));
+                current.clear();
+            }
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(current.join(" "));
+    }
+
+    sentences
+}
+
+/// The words of a sentence, without punctuation and numbers. Hyphenated compounds like
+/// "E-Mail" stay one word.
+pub fn words(sentence: &str) -> impl Iterator<Item = &str> {
+    sentence
+        .split(|c: char| !c.is_alphabetic() && c != '-')
+        .map(|w| w.trim_matches('-'))
+        .filter(|w| !w.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sentences, words};
+
+    #[test]
+    fn split() {
+        let text = "Kapitel 1\n\n\
+                    Der Hund bellt, z.B. nachts. Warum?\n\
+                    „Ich weiß es nicht!“ sagte sie.\n";
+        assert_eq!(
+            sentences(text),
+            [
+                "Kapitel 1",
+                "Der Hund bellt, z.B. nachts.",
+                "Warum?",
+                "„Ich weiß es nicht!“",
+                "sagte sie."
+            ]
+        );
+
+        let words: Vec<_> = words("„Schick mir 2 E-Mails!“ -- sagte er.").collect();
+        assert_eq!(words, ["Schick", "mir", "E-Mails", "sagte", "er"]);
+    }
+}
@@ -0,0 +1,333 @@
+//! A full screen browser over the dictionary: a search box searching as you type, a
+//! scrollable list of results and a pane with the details of the selected one.
+
+use std::io::{self, Write};
+
+use crate::{
+    console::{self, RawMode},
+    dictionary::Match,
+    editor::{self, Key},
+    entry::{CaseStyle, Entry, PlaceholderStyle, RenderOptions, Term},
+    query::Language,
+    rank::Weights,
+    Dictionary,
+};
+
+/// Queries shorter than this match too much to be useful.
+const MIN_QUERY_LEN: usize = 2;
+const MAX_RESULTS: usize = 500;
+const SEARCH_LABEL: &str = "search: ";
+
+struct Browser<'a> {
+    dict: &'a Dictionary,
+    weights: &'a Weights,
+    query: Vec<char>,
+    cursor: usize,
+    results: Vec<Match<'a>>,
+    selected: usize,
+    scroll: usize,
+    saved: Vec<Entry>,
+    /// The entries review cards were added for.
+    carded: Vec<Entry>,
+    /// What happened on the last key press, shown in the status line until the next.
+    notice: Option<String>,
+}
+
+/// Switches to the alternate screen for as long as the value lives.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        print!("\x1b[?1049h");
+        io::stdout().flush()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Runs the browser until the user quits and returns the entries they saved. Ctrl-S
+/// calls `add_card` with the selected entry, which returns what to tell the user.
+pub fn run(
+    dict: &Dictionary,
+    weights: &Weights,
+    mut add_card: impl FnMut(&Entry) -> String,
+) -> io::Result<Vec<Entry>> {
+    let _raw = RawMode::enable()
+        .ok_or_else(|| io::Error::other("the browser needs an interactive terminal"))?;
+    let _screen = Screen::enter()?;
+
+    let mut browser = Browser {
+        dict,
+        weights,
+        query: vec![],
+        cursor: 0,
+        results: vec![],
+        selected: 0,
+        scroll: 0,
+        saved: vec![],
+        carded: vec![],
+        notice: None,
+    };
+
+    let mut stdin = io::stdin().lock();
+    loop {
+        let (width, height) = console::size().unwrap_or((80, 24));
+        browser.draw(io::stdout().lock(), width, height)?;
+
+        let page = Browser::list_height(height);
+        let key = editor::read_key(&mut stdin)?;
+        browser.notice = None;
+        match key {
+            Key::Interrupt | Key::Eof | Key::Closed => return Ok(browser.saved),
+            Key::Char(c) => {
+                browser.query.insert(browser.cursor, c);
+                browser.cursor += 1;
+                browser.search();
+            }
+            Key::Backspace if browser.cursor > 0 => {
+                browser.cursor -= 1;
+                browser.query.remove(browser.cursor);
+                browser.search();
+            }
+            Key::Delete if browser.cursor < browser.query.len() => {
+                browser.query.remove(browser.cursor);
+                browser.search();
+            }
+            Key::KillToStart | Key::KillWord => {
+                browser.query.clear();
+                browser.cursor = 0;
+                browser.search();
+            }
+            Key::Left => browser.cursor = browser.cursor.saturating_sub(1),
+            Key::Right => browser.cursor = (browser.cursor + 1).min(browser.query.len()),
+            Key::Home => browser.cursor = 0,
+            Key::End => browser.cursor = browser.query.len(),
+            Key::Up => browser.select(-1, page),
+            Key::Down => browser.select(1, page),
+            Key::PageUp => browser.select(-(page as isize), page),
+            Key::PageDown => browser.select(page as isize, page),
+            Key::Enter => browser.toggle_saved(),
+            Key::Save => {
+                if let Some(m) = browser.results.get(browser.selected) {
+                    browser.notice = Some(add_card(m.entry));
+                    if !browser.carded.contains(m.entry) {
+                        browser.carded.push(m.entry.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Browser<'_> {
+    fn search(&mut self) {
+        let query: String = self.query.iter().collect();
+        let query = query.trim();
+
+        self.results = if query.chars().count() < MIN_QUERY_LEN {
+            vec![]
+        } else {
+            let mut results = self.dict.search_with(query, Language::German, self.weights);
+            if results.is_empty() {
+                results = self
+                    .dict
+                    .search_with(query, Language::English, self.weights);
+            }
+            results.truncate(MAX_RESULTS);
+            results
+        };
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn select(&mut self, delta: isize, page: usize) {
+        if self.results.is_empty() {
+            return;
+        }
+
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.results.len() - 1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + page {
+            self.scroll = self.selected + 1 - page;
+        }
+    }
+
+    fn toggle_saved(&mut self) {
+        let Some(m) = self.results.get(self.selected) else {
+            return;
+        };
+
+        match self.saved.iter().position(|e| e == m.entry) {
+            Some(i) => {
+                self.saved.remove(i);
+            }
+            None => self.saved.push(m.entry.clone()),
+        }
+    }
+
+    /// The rows of the result list: the screen without the search box, the status line,
+    /// two separators and the detail pane, which gets about a third.
+    fn list_height(height: usize) -> usize {
+        let available = height.saturating_sub(4).max(2);
+        available - available / 3
+    }
+
+    fn draw(&self, mut wr: impl Write, width: usize, height: usize) -> io::Result<()> {
+        let list_height = Self::list_height(height);
+        let detail_height = height.saturating_sub(4 + list_height);
+        let separator = "─".repeat(width);
+
+        let mut lines = vec![];
+        lines.push(format!(
+            "{}{}",
+            SEARCH_LABEL,
+            self.query.iter().collect::<String>()
+        ));
+        lines.push(separator.clone());
+
+        for row in 0..list_height {
+            let i = self.scroll + row;
+            let line = match self.results.get(i) {
+                Some(m) => {
+                    let marker = if self.saved.contains(m.entry) {
+                        '*'
+                    } else if self.carded.contains(m.entry) {
+                        '+'
+                    } else {
+                        ' '
+                    };
+                    let text = truncate(&format!("{} {}", marker, m.entry), width);
+                    if i == self.selected {
+                        format!("\x1b[7m{}\x1b[0m", text)
+                    } else {
+                        text
+                    }
+                }
+                None => String::new(),
+            };
+            lines.push(line);
+        }
+
+        lines.push(separator);
+        let details = self
+            .results
+            .get(self.selected)
+            .map(|m| details(m.entry))
+            .unwrap_or_default();
+        lines.extend((0..detail_height).map(|i| {
+            details
+                .get(i)
+                .map(|d| d.as_str())
+                .unwrap_or_default()
+                .to_string()
+        }));
+
+        let status = match &self.notice {
+            Some(notice) => notice.clone(),
+            None => format!(
+                "{} results, {} saved | ↑↓ PgUp PgDn select, Enter save, Ctrl-S review card, Ctrl-C quit",
+                self.results.len(),
+                self.saved.len()
+            ),
+        };
+        lines.push(format!(
+            "\x1b[7m{}\x1b[0m",
+            pad(&truncate(&status, width), width)
+        ));
+
+        write!(wr, "\x1b[?25l")?;
+        for (row, line) in lines.iter().take(height).enumerate() {
+            let line = if line.starts_with('\x1b') {
+                line.clone()
+            } else {
+                truncate(line, width)
+            };
+            write!(wr, "\x1b[{};1H{}\x1b[K", row + 1, line)?;
+        }
+        write!(
+            wr,
+            "\x1b[1;{}H\x1b[?25h",
+            SEARCH_LABEL.len() + self.cursor + 1
+        )?;
+        wr.flush()
+    }
+}
+
+/// The lines of the detail pane for an entry.
+fn details(entry: &Entry) -> Vec<String> {
+    let options = RenderOptions {
+        case_style: CaseStyle::Full,
+        ..Default::default()
+    };
+    let join = |values: Vec<String>| {
+        if values.is_empty() {
+            "-".to_string()
+        } else {
+            values.join(", ")
+        }
+    };
+    let annotations =
+        |term: &Term| join(term.annotations().iter().map(|a| a.to_string()).collect());
+    let cases = |term: &Term| {
+        join(
+            term.placeholders()
+                .iter()
+                .map(|p| p.render(CaseStyle::Full, PlaceholderStyle::Expanded))
+                .collect(),
+        )
+    };
+
+    vec![
+        format!(
+            "{} = {}",
+            entry.german.render(options),
+            entry.english.render(options)
+        ),
+        format!(
+            "gender:       {}",
+            entry
+                .german
+                .gender()
+                .map_or("-".to_string(), |g| g.to_string())
+        ),
+        format!(
+            "word class:   {}",
+            join(entry.word_classes.iter().map(|c| c.to_string()).collect())
+        ),
+        format!(
+            "subjects:     {}",
+            join(entry.subjects.iter().map(|s| s.to_string()).collect())
+        ),
+        format!("cases:        {}", cases(&entry.german)),
+        format!("German:       {}", annotations(&entry.german)),
+        format!("English:      {}", annotations(&entry.english)),
+    ]
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(width.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+fn pad(s: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        s,
+        " ".repeat(width.saturating_sub(s.chars().count()))
+    )
+}
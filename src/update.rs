@@ -0,0 +1,166 @@
+//! Replacing the embedded dictionary with a newer dict.cc export. dict.cc only hands out
+//! exports after registering, so the user downloads one and gives its path, or a URL
+//! to it which is fetched with curl.
+
+use std::{
+    io::{self, BufRead, Read},
+    path::Path,
+    process::Command,
+};
+
+use anyhow::anyhow;
+
+use crate::{dictionary::LineReader, entry::Entry, loader::Compression, store};
+
+/// The share of entry lines which have to parse for a file to count as a dict.cc
+/// export. Real exports have a few lines the parser doesn't understand.
+const MIN_PARSED_SHARE: f64 = 0.9;
+
+/// What is known about a dictionary after validating it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub entries: usize,
+    /// The lines which aren't comments but couldn't be parsed.
+    pub skipped: usize,
+}
+
+/// Reads the export from a file or, for "http://" and "https://" URLs, downloads it.
+pub fn fetch(source: &str) -> anyhow::Result<Vec<u8>> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return std::fs::read(source).map_err(|e| anyhow!("failed to read \"{}\": {}", source, e));
+    }
+
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", source])
+        .output()
+        .map_err(|e| anyhow!("failed to run curl for the download: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "failed to download \"{}\": {}",
+            source,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// The dictionary text of an export, which may be plain text, compressed with gzip or
+/// zstd, or the first file of a zip archive as dict.cc hands them out.
+pub fn extract(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut text = vec![];
+    match Compression::detect(data) {
+        Compression::None if data.starts_with(ZIP_MAGIC) => {
+            flate2::read::DeflateDecoder::new(zip_entry(data)?).read_to_end(&mut text)?;
+        }
+        Compression::None => text.extend_from_slice(data),
+        Compression::Gzip => {
+            flate2::read::MultiGzDecoder::new(data).read_to_end(&mut text)?;
+        }
+        Compression::Zstd => {
+            zstd::stream::read::Decoder::new(data)?.read_to_end(&mut text)?;
+        }
+    }
+    Ok(text)
+}
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// The compressed data of the first file in a zip archive, which has to be deflated.
+fn zip_entry(data: &[u8]) -> anyhow::Result<&[u8]> {
+    let field = |at: usize| -> anyhow::Result<usize> {
+        let bytes = data
+            .get(at..at + 2)
+            .ok_or_else(|| anyhow!("the zip archive is truncated"))?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+
+    const DEFLATE: usize = 8;
+    if field(8)? != DEFLATE {
+        return Err(anyhow!(
+            "the zip archive isn't deflated, extract the dictionary from it first"
+        ));
+    }
+    // The local file header is 30 bytes, followed by the file name and extra field.
+    let start = 30 + field(26)? + field(28)?;
+    data.get(start..)
+        .ok_or_else(|| anyhow!("the zip archive is truncated"))
+}
+
+/// Checks that the text is a dict.cc export: almost all of its lines have to parse as
+/// entries.
+pub fn validate(text: &[u8]) -> anyhow::Result<Summary> {
+    let mut rd = LineReader::new(text);
+    let mut buf = String::with_capacity(512);
+    let mut summary = Summary {
+        entries: 0,
+        skipped: 0,
+    };
+
+    loop {
+        buf.clear();
+        let n = rd.read_line(&mut buf).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => anyhow!("the dictionary isn't UTF-8 text"),
+            _ => e.into(),
+        })?;
+        if n == 0 {
+            break;
+        }
+        if buf.starts_with('#') || buf.trim().is_empty() {
+            continue;
+        }
+
+        match Entry::parse(&buf, rd.source()) {
+            Ok(_) => summary.entries += 1,
+            Err(_) => summary.skipped += 1,
+        }
+    }
+
+    let lines = summary.entries + summary.skipped;
+    if lines == 0 || (summary.entries as f64) < lines as f64 * MIN_PARSED_SHARE {
+        return Err(anyhow!(
+            "this doesn't look like a dict.cc export: {} of {} lines are entries",
+            summary.entries,
+            lines
+        ));
+    }
+    Ok(summary)
+}
+
+/// Compresses the dictionary text with zstd and writes it to the path, replacing the
+/// previous dictionary only once the new one is complete.
+pub fn install(text: impl BufRead, path: &Path) -> io::Result<()> {
+    let compressed = zstd::stream::encode_all(text, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+    store::write_atomic(path, &compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{extract, validate, Summary};
+
+    #[test]
+    fn extract_and_validate() {
+        let text = "# de-en vocabulary database compiled by dict.cc\n\
+                    Hund {m}\tdog\tnoun\t\n\
+                    Katze {f}\tcat\tnoun\t\n";
+
+        let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::stream::encode_all(text.as_bytes(), 0).unwrap();
+        for data in [text.as_bytes(), &gzip, &zstd] {
+            assert_eq!(extract(data).unwrap(), text.as_bytes());
+        }
+
+        assert_eq!(
+            validate(text.as_bytes()).unwrap(),
+            Summary {
+                entries: 2,
+                skipped: 0
+            }
+        );
+        assert!(validate(b"<html>not found</html>\n").is_err());
+        assert!(validate(b"").is_err());
+    }
+}
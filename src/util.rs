@@ -1,30 +1,264 @@
-use std::cmp::Ordering;
+//! Case insensitive comparison and search with full Unicode case folding, so that
+//! "STRASSE" matches "straße" and "ﬁne" matches "fine". Matches may differ in length
+//! from the text they match.
+//!
+//! Matching can also be told to ignore diacritics, for users without a German keyboard:
+//! then "uber" and "ueber" both match "über", and "Grosse" matches "Große".
+//!
+//! Invisible characters like soft hyphens never matter.
 
-pub fn case_fold_contains(haystack: &str, needle: &str) -> bool {
-    let nlen = needle.len();
-    let hlen = haystack.len();
-
-    match hlen.cmp(&nlen) {
-        Ordering::Less => false,
-        Ordering::Equal => case_fold_eq(haystack, needle),
-        Ordering::Greater => {
-            for i in 0..hlen - nlen {
-                if haystack.is_char_boundary(i)
-                    && haystack.is_char_boundary(i + nlen)
-                    && case_fold_eq(&haystack[i..i + nlen], needle)
-                {
-                    return true;
-                }
+use std::{
+    borrow::Cow,
+    char::ToLowercase,
+    ops::Range,
+    slice,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use unicode_normalization::{char as unicode, UnicodeNormalization};
+
+static IGNORE_DIACRITICS: AtomicBool = AtomicBool::new(false);
+
+/// Makes all comparisons of this module ignore diacritics, from now on.
+pub fn set_ignore_diacritics(ignore: bool) {
+    IGNORE_DIACRITICS.store(ignore, Ordering::Relaxed);
+}
+
+pub fn ignores_diacritics() -> bool {
+    IGNORE_DIACRITICS.load(Ordering::Relaxed)
+}
+
+/// Whether the character is invisible but would still break matching: soft hyphens,
+/// zero-width spaces and joiners, and byte order marks. They occur in real exports and
+/// in text copied from web pages.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{ad}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}'
+    )
+}
+
+/// The text without invisible characters, borrowed if it has none.
+pub fn strip_invisible(s: &str) -> Cow<'_, str> {
+    if s.contains(is_invisible) {
+        Cow::Owned(s.chars().filter(|&c| !is_invisible(c)).collect())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// The case folding of a character, one or more characters.
+enum Fold {
+    Lower(ToLowercase),
+    Special(slice::Iter<'static, char>),
+}
+
+impl Iterator for Fold {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            Self::Lower(it) => it.next(),
+            Self::Special(it) => it.next().copied(),
+        }
+    }
+}
+
+/// Folds a character. Lowercasing covers almost everything, including "İ", which folds
+/// to "i" with a combining dot. The exceptions are the characters which fold to several
+/// characters, like "ß" to "ss", and the variant forms of letters, like the final sigma.
+fn fold(c: char) -> Fold {
+    let special: &'static [char] = match c {
+        'ß' | 'ẞ' => &['s', 's'],
+        'ſ' => &['s'],
+        'ς' => &['σ'],
+        'ŉ' => &['ʼ', 'n'],
+        'ǰ' => &['j', '\u{30c}'],
+        'ﬀ' => &['f', 'f'],
+        'ﬁ' => &['f', 'i'],
+        'ﬂ' => &['f', 'l'],
+        'ﬃ' => &['f', 'f', 'i'],
+        'ﬄ' => &['f', 'f', 'l'],
+        'ﬅ' | 'ﬆ' => &['s', 't'],
+        _ => return Fold::Lower(c.to_lowercase()),
+    };
+    Fold::Special(special.iter())
+}
+
+/// Folds text character by character, on top of case folding also dropping diacritics
+/// if they are ignored. The "e" of the ASCII spellings "ae", "oe" and "ue" is dropped
+/// then as well, which is why the folder has to remember the previous character.
+struct Folder {
+    ignore_diacritics: bool,
+    prev: Option<char>,
+}
+
+/// The up to three characters a single character folds to.
+#[derive(Default)]
+struct Folded {
+    chars: [char; 3],
+    len: usize,
+    pos: usize,
+}
+
+impl Iterator for Folded {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars[..self.len].get(self.pos).copied();
+        self.pos += 1;
+        c
+    }
+}
+
+impl Folder {
+    fn new() -> Self {
+        Self {
+            ignore_diacritics: ignores_diacritics(),
+            prev: None,
+        }
+    }
+
+    fn fold(&mut self, c: char) -> Folded {
+        let mut out = Folded::default();
+        // Most text is ASCII, which folds to a single character.
+        if c.is_ascii() {
+            self.push(&mut out, c.to_ascii_lowercase());
+        } else if is_invisible(c) {
+            // Skipped, as if it weren't there.
+        } else if self.ignore_diacritics {
+            for f in fold(c) {
+                unicode::decompose_canonical(f, |d| {
+                    if !unicode::is_combining_mark(d) {
+                        self.push(&mut out, d);
+                    }
+                });
             }
-            false
+        } else {
+            fold(c).for_each(|f| self.push(&mut out, f));
+        }
+        out
+    }
+
+    fn push(&mut self, out: &mut Folded, c: char) {
+        let skip = self.ignore_diacritics && c == 'e' && matches!(self.prev, Some('a' | 'o' | 'u'));
+        self.prev = Some(c);
+        if !skip {
+            out.chars[out.len] = c;
+            out.len += 1;
         }
     }
 }
 
+fn folded(s: &str) -> impl Iterator<Item = char> + '_ {
+    let mut folder = Folder::new();
+    s.chars().flat_map(move |c| folder.fold(c))
+}
+
+/// The case folded string, for use as a key.
+pub fn case_fold(s: &str) -> String {
+    folded(s).collect()
+}
+
+/// Like [`case_fold`], but canonically equivalent strings get the same key too, e.g.
+/// "é" written as one character or as "e" with a combining accent.
+pub fn case_fold_normalized(s: &str) -> String {
+    let nfd: String = s.nfd().collect();
+    folded(&nfd).nfc().collect()
+}
+
+/// The key German dictionaries sort words by: case and diacritics don't matter, so that
+/// "Äpfel" comes right after "Apfel" instead of after "Zwerg". Unlike ignoring
+/// diacritics for matching, "ae" stays two letters.
+pub fn collation_key(s: &str) -> String {
+    let mut key = String::with_capacity(s.len());
+    for f in s.chars().filter(|&c| !is_invisible(c)).flat_map(fold) {
+        unicode::decompose_canonical(f, |d| {
+            if !unicode::is_combining_mark(d) {
+                key.push(d);
+            }
+        });
+    }
+    key
+}
+
 pub fn case_fold_eq(a: &str, b: &str) -> bool {
-    a.chars()
-        .zip(b.chars())
-        .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+    folded(a).eq(folded(b))
+}
+
+pub fn case_fold_contains(haystack: &str, needle: &str) -> bool {
+    haystack
+        .char_indices()
+        .any(|(i, _)| match_len(&haystack[i..], needle).is_some())
+        || needle.is_empty()
+}
+
+/// The byte ranges of all non-overlapping occurrences of `needle` in `haystack`,
+/// ignoring case.
+pub fn case_fold_find_all(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    if needle.is_empty() {
+        return ranges;
+    }
+
+    let mut next = 0;
+    for (i, _) in haystack.char_indices() {
+        if i < next {
+            continue;
+        }
+        if let Some(len) = match_len(&haystack[i..], needle) {
+            ranges.push(i..i + len);
+            next = i + len;
+        }
+    }
+
+    ranges
+}
+
+/// The length in bytes of the match if `haystack` starts with `needle`, ignoring case.
+/// A match ending inside the folding of a character, like "s" in "ß", extends to the
+/// end of that character.
+fn match_len(haystack: &str, needle: &str) -> Option<usize> {
+    let mut needle = folded(needle).peekable();
+    needle.peek()?;
+
+    let mut folder = Folder::new();
+    for (i, c) in haystack.char_indices() {
+        for f in folder.fold(c) {
+            match needle.next() {
+                Some(n) if n == f => {}
+                Some(_) => return None,
+                None => return Some(i + c.len_utf8()),
+            }
+        }
+        if needle.peek().is_none() {
+            return Some(i + c.len_utf8());
+        }
+    }
+
+    None
+}
+
+/// Whether the text is written in capitals only, as headlines sometimes are. A single
+/// capital like "A" doesn't count.
+pub fn is_all_caps(s: &str) -> bool {
+    s.chars().filter(|c| c.is_alphabetic()).nth(1).is_some() && !s.chars().any(char::is_lowercase)
+}
+
+/// The casings the text may stand for: as given and, if it is all caps, lowercase and
+/// capitalized. "WOHNUNG GESUCHT" may be "wohnung gesucht" or "Wohnung gesucht".
+pub fn casing_variants(s: &str) -> Vec<String> {
+    let mut variants = vec![s.to_string()];
+    if is_all_caps(s) {
+        let lower = s.to_lowercase();
+        let mut chars = lower.chars();
+        let capitalized = chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect())
+            .unwrap_or_default();
+        variants.extend([lower, capitalized]);
+    }
+    variants
 }
 
 pub fn reuse_vec<T, U>(mut v: Vec<T>) -> Vec<U> {
@@ -33,3 +267,103 @@ pub fn reuse_vec<T, U>(mut v: Vec<T>) -> Vec<U> {
     v.clear();
     v.into_iter().map(|_| unreachable!()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eq() {
+        assert!(case_fold_eq("STRASSE", "straße"));
+        assert!(case_fold_eq("STRAẞE", "strasse"));
+        assert!(case_fold_eq("Äpfel", "äPFEL"));
+        assert!(case_fold_eq("İstanbul", "i\u{307}stanbul"));
+        assert!(case_fold_eq("ﬁnden", "FINDEN"));
+        assert!(case_fold_eq("ὈΔΥΣΣΕΎΣ", "ὀδυσσεύς"));
+
+        assert!(!case_fold_eq("Hund", "Hunde"));
+        assert!(!case_fold_eq("Hunde", "Hund"));
+        assert!(!case_fold_eq("Masse", "Maße ") && !case_fold_eq("", "a"));
+        assert!(!case_fold_eq("é", "e\u{301}"));
+    }
+
+    #[test]
+    fn invisible() {
+        assert_eq!(
+            strip_invisible("\u{feff}Wör\u{ad}ter\u{200b}buch"),
+            "Wörterbuch"
+        );
+        assert!(matches!(strip_invisible("Hund"), Cow::Borrowed(_)));
+        assert!(case_fold_eq("Hun\u{ad}d", "HUND"));
+        assert!(case_fold_contains("Wörter\u{200b}buch", "terbu"));
+        assert_eq!(collation_key("\u{feff}Abend"), collation_key("Abend"));
+    }
+
+    #[test]
+    fn normalized() {
+        assert_eq!(case_fold_normalized("É"), case_fold_normalized("e\u{301}"));
+        assert_eq!(case_fold_normalized("Fuß"), "fuss");
+        assert_eq!(case_fold("GRÜẞE"), "grüsse");
+    }
+
+    #[test]
+    fn contains() {
+        assert!(case_fold_contains("Hauptstraße", "STRASSE"));
+        assert!(case_fold_contains("HAUPTSTRASSE", "straße"));
+        assert!(case_fold_contains("Fuß", "fus"));
+        assert!(case_fold_contains("Hund", ""));
+        assert!(!case_fold_contains("Hund", "hunde"));
+        assert!(!case_fold_contains("", "a"));
+    }
+
+    #[test]
+    fn contains_at_end() {
+        // The last position of the haystack used to be skipped.
+        assert!(case_fold_contains("dog house", "HOUSE"));
+        assert!(case_fold_contains("Haustür", "tür"));
+        assert!(case_fold_contains("ab", "b"));
+    }
+
+    #[test]
+    fn find_all() {
+        assert_eq!(
+            case_fold_find_all("Straße, STRASSE", "strasse"),
+            [0..7, 9..16]
+        );
+        assert_eq!(case_fold_find_all("Maß und Masse", "ss"), [2..4, 11..13]);
+        assert_eq!(case_fold_find_all("aaaaa", "aa"), [0..2, 2..4]);
+        assert!(case_fold_find_all("Hund", "").is_empty());
+    }
+
+    #[test]
+    fn casing() {
+        assert_eq!(
+            casing_variants("WOHNUNG GESUCHT"),
+            ["WOHNUNG GESUCHT", "wohnung gesucht", "Wohnung gesucht"]
+        );
+        assert_eq!(casing_variants("ÄRGER"), ["ÄRGER", "ärger", "Ärger"]);
+        assert_eq!(casing_variants("Wohnung"), ["Wohnung"]);
+        assert_eq!(casing_variants("A"), ["A"]);
+        assert!(is_all_caps("U-BAHN"));
+        assert!(!is_all_caps("GmbH"));
+    }
+
+    #[test]
+    fn ignore_diacritics() {
+        // The switch is global, so it isn't flipped here while other tests run.
+        let fold = |s: &str| -> String {
+            let mut folder = Folder {
+                ignore_diacritics: true,
+                prev: None,
+            };
+            s.chars().flat_map(|c| folder.fold(c)).collect()
+        };
+
+        assert_eq!(fold("über"), fold("uber"));
+        assert_eq!(fold("Über"), fold("UEBER"));
+        assert_eq!(fold("Große"), fold("grosse"));
+        assert_eq!(fold("Café"), fold("cafe"));
+        assert_eq!(fold("Mauer"), fold("Maür"));
+        assert_ne!(fold("Bär"), fold("Bier"));
+    }
+}
@@ -0,0 +1,182 @@
+//! The valency of German verbs: the cases of their objects, whether they are reflexive
+//! and which prepositions they take, gathered from the placeholders of their entries.
+//!
+//! dict.cc only marks the case of "etw." and "sich" where it isn't obvious, so unmarked
+//! ones count as accusative.
+
+use std::cmp::Reverse;
+
+use crate::{
+    entry::{is_placeholder, Case, Entry, Placeholder, WordClass},
+    util,
+};
+
+/// Prepositions with the case they govern, or none for the ones taking either the
+/// accusative or the dative.
+const PREPOSITIONS: &[(&str, Option<Case>)] = &[
+    ("an", None),
+    ("auf", None),
+    ("aus", Some(Case::Dative)),
+    ("bei", Some(Case::Dative)),
+    ("durch", Some(Case::Accusative)),
+    ("für", Some(Case::Accusative)),
+    ("gegen", Some(Case::Accusative)),
+    ("hinter", None),
+    ("in", None),
+    ("mit", Some(Case::Dative)),
+    ("nach", Some(Case::Dative)),
+    ("neben", None),
+    ("ohne", Some(Case::Accusative)),
+    ("um", Some(Case::Accusative)),
+    ("unter", None),
+    ("von", Some(Case::Dative)),
+    ("vor", None),
+    ("wegen", Some(Case::Genitive)),
+    ("zu", Some(Case::Dative)),
+    ("zwischen", None),
+    ("über", None),
+];
+
+/// What the entries of a verb say about the objects it takes. Each count is the number
+/// of entries, most common first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Valency {
+    /// The number of entries the valency is gathered from.
+    pub entries: usize,
+    /// The cases of the objects, without the reflexive pronoun and prepositional objects.
+    pub cases: Vec<(Case, usize)>,
+    /// The cases of the reflexive pronoun.
+    pub reflexive: Vec<(Case, usize)>,
+    /// The prepositions with the case of their object, unless it can't be told.
+    pub prepositions: Vec<((String, Option<Case>), usize)>,
+}
+
+/// Whether the entry is the verb with nothing but its objects, like "sich [Akk.] auf
+/// etw. [Akk.] freuen" for "freuen". Idioms and entries with adverbs say little about
+/// the verb itself.
+pub fn is_frame(entry: &Entry, verb: &str) -> bool {
+    if !entry.word_classes.contains(&WordClass::Verb) {
+        return false;
+    }
+
+    let keywords: Vec<_> = entry
+        .german
+        .keywords()
+        .filter(|k| !is_placeholder(k))
+        .collect();
+    match keywords.split_last() {
+        Some((last, rest)) => {
+            util::case_fold_eq(last, verb) && rest.iter().all(|k| preposition(k).is_some())
+        }
+        None => false,
+    }
+}
+
+/// The valency of the verb, gathered from those of the entries which are frames of it,
+/// see [`is_frame`]. None if there are no such entries.
+pub fn valency<'a>(verb: &str, entries: impl IntoIterator<Item = &'a Entry>) -> Option<Valency> {
+    let mut valency = Valency::default();
+
+    for entry in entries.into_iter().filter(|e| is_frame(e, verb)) {
+        let mut cases = vec![];
+        let mut reflexive = vec![];
+        let mut prepositions = vec![];
+
+        for (before, alternatives) in entry.german.placeholder_slots() {
+            // "jdm./etw." is one object, with the case of "jdm.".
+            let case = alternatives.iter().find_map(|p| match **p {
+                Placeholder::Thing(case) | Placeholder::Reflexive(case) => case,
+                Placeholder::Person(case) => Some(case),
+            });
+            let is_reflexive = matches!(alternatives[..], [Placeholder::Reflexive(_)]);
+            match before.and_then(preposition) {
+                Some(&(word, governed)) => {
+                    insert(&mut prepositions, (word.to_string(), case.or(governed)))
+                }
+                None if is_reflexive => insert(&mut reflexive, case.unwrap_or(Case::Accusative)),
+                None => insert(&mut cases, case.unwrap_or(Case::Accusative)),
+            }
+        }
+
+        valency.entries += 1;
+        cases.into_iter().for_each(|c| count(&mut valency.cases, c));
+        reflexive
+            .into_iter()
+            .for_each(|c| count(&mut valency.reflexive, c));
+        prepositions
+            .into_iter()
+            .for_each(|p| count(&mut valency.prepositions, p));
+    }
+
+    if valency.entries == 0 {
+        return None;
+    }
+    valency.cases.sort_by_key(|(_, n)| Reverse(*n));
+    valency.reflexive.sort_by_key(|(_, n)| Reverse(*n));
+    valency.prepositions.sort_by_key(|(_, n)| Reverse(*n));
+    Some(valency)
+}
+
+fn preposition(word: &str) -> Option<&'static (&'static str, Option<Case>)> {
+    PREPOSITIONS.iter().find(|(p, _)| *p == word)
+}
+
+fn insert<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+fn count<T: PartialEq>(counts: &mut Vec<(T, usize)>, value: T) {
+    match counts.iter_mut().find(|(v, _)| *v == value) {
+        Some((_, n)) => *n += 1,
+        None => counts.push((value, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::valency;
+    use crate::{entry::Case, Dictionary};
+
+    #[test]
+    fn gather_valency() {
+        let dict = Dictionary::load(
+            "jdm. helfen\tto help sb.\tverb\t\n\
+             jdm./etw. helfen\tto aid sb./sth.\tverb\t\n\
+             jdm. bei etw. helfen\tto assist sb. with sth.\tverb\t\n\
+             jdm. (in einer Notsituation) helfen\tto get sb. off the hook\tverb\t\n\
+             jdm. über die Straße helfen\tto see sb. across the road\tverb\t\n\
+             sich freuen\tto be glad\tverb\t\n\
+             jdn. freuen\tto please sb.\tverb\t\n\
+             sich [Akk.] (an jdm./etw.) freuen\tto delight in sb./sth.\tverb\t\n\
+             sich [Akk.] auf etw. [Akk.] freuen\tto look forward to sth.\tverb\t\n\
+             Freuen {n}\tjoy\tnoun\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let helfen = valency("helfen", dict.entries()).unwrap();
+        assert_eq!(helfen.entries, 4);
+        assert_eq!(helfen.cases, [(Case::Dative, 4)]);
+        assert!(helfen.reflexive.is_empty());
+        assert_eq!(
+            helfen.prepositions,
+            [(("bei".to_string(), Some(Case::Dative)), 1)]
+        );
+
+        let freuen = valency("freuen", dict.entries()).unwrap();
+        assert_eq!(freuen.entries, 4);
+        assert_eq!(freuen.cases, [(Case::Accusative, 1)]);
+        assert_eq!(freuen.reflexive, [(Case::Accusative, 3)]);
+        assert_eq!(
+            freuen.prepositions,
+            [
+                (("an".to_string(), Some(Case::Dative)), 1),
+                (("auf".to_string(), Some(Case::Accusative)), 1)
+            ]
+        );
+
+        assert!(valency("laufen", dict.entries()).is_none());
+    }
+}
@@ -0,0 +1,742 @@
+//! The vocabulary list: the translations the user saved, kept across sessions as JSON.
+
+use std::{
+    fs,
+    io::{self, ErrorKind, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dictionary,
+    entry::{AnnotationKind, CaseStyle, Entry, PlaceholderStyle, RenderOptions, Term, WordClass},
+    group, lookups,
+    query::Language,
+    store::{self, Recovery},
+};
+
+/// The days until a card is due again, by the box it is in. Cards answered correctly
+/// move up a box, wrong answers send them back to the first.
+const INTERVALS: [u64; 6] = [0, 1, 3, 7, 14, 30];
+const DAY: u64 = 24 * 60 * 60;
+
+/// A saved German term with its saved translations. The terms are stored in dict.cc
+/// notation, so that they can be parsed again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VocabEntry {
+    pub german: String,
+    pub english: Vec<String>,
+    /// The word classes of the saved entries, as dict.cc abbreviates them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub word_classes: Vec<String>,
+    /// The dictionary entries the translations were saved from, one per sense.
+    /// Translations saved before senses were kept track of have none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub senses: Vec<Sense>,
+    /// When the term was first saved, in seconds since the Unix epoch.
+    pub added: u64,
+    /// The spaced repetition box the card is in, 0 for cards never reviewed.
+    #[serde(default)]
+    pub level: u32,
+    /// When the card is due for review next, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub due: u64,
+    /// How often the card was answered correctly and wrong in quizzes.
+    #[serde(default)]
+    pub correct: u32,
+    #[serde(default)]
+    pub wrong: u32,
+}
+
+/// A saved sense of a German term: the dictionary entry that was picked, so that the
+/// senses saved in different sessions stay apart instead of merging into one list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sense {
+    /// The [`EntryId`](crate::entry::EntryId) of the entry.
+    pub id: String,
+    pub english: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub word_classes: Vec<String>,
+}
+
+/// The file formats flashcards can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardFormat {
+    /// Tab separated, with the header lines Anki uses to configure the import.
+    Tsv,
+    Csv,
+}
+
+impl CardFormat {
+    /// CSV for paths ending in ".csv", TSV otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(e) if e.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Tsv,
+        }
+    }
+}
+
+impl VocabEntry {
+    /// The front and back of the flashcard for the entry: the German term with its
+    /// article and the translations, both with placeholders in dict.cc notation or
+    /// written out. Translations differing only in annotations are listed once.
+    pub fn card(&self, placeholders: PlaceholderStyle) -> (String, String) {
+        let front = render_cards(&[&self.german], placeholders).remove(0);
+        let english: Vec<_> = self.english.iter().map(String::as_str).collect();
+        (front, render_cards(&english, placeholders).join("; "))
+    }
+
+    /// Whether the answer is the German term. Nouns have to be given with their
+    /// article.
+    pub fn check(&self, answer: &str) -> bool {
+        let Ok(term) = Term::parse(&self.german) else {
+            return false;
+        };
+
+        let article = match term.gender() {
+            Some(gender) => Some(gender.to_string()),
+            None => term.is_plural().then(|| "die".to_string()),
+        };
+        let answer = match article {
+            Some(article) => match answer.trim().split_once(char::is_whitespace) {
+                Some((a, rest)) if a.to_lowercase() == article => rest,
+                _ => return false,
+            },
+            None => answer,
+        };
+
+        dictionary::is_exact(&term, answer, Language::German)
+    }
+
+    /// Moves the card to its next box after a quiz answer and schedules the next
+    /// review.
+    pub fn record(&mut self, correct: bool, now: u64) {
+        if correct {
+            self.correct += 1;
+            self.level = (self.level + 1).min(INTERVALS.len() as u32 - 1);
+        } else {
+            self.wrong += 1;
+            self.level = 0;
+        }
+        self.due = now + INTERVALS[self.level as usize] * DAY;
+    }
+}
+
+/// The quiz answers given on a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewDay {
+    /// Days since the Unix epoch, in UTC.
+    pub day: u64,
+    pub reviewed: u32,
+    pub correct: u32,
+}
+
+/// The progress over all quizzes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// The reviews of each of the given number of days up to today, oldest first.
+    pub recent: Vec<u32>,
+    pub reviewed: u32,
+    pub correct: u32,
+    /// Consecutive days with reviews up to today, or up to yesterday if there were none
+    /// today yet.
+    pub streak: u32,
+    pub longest_streak: u32,
+    /// The number of cards in each box.
+    pub boxes: Vec<usize>,
+}
+
+/// The version of the file format. Files without a version are from before it was
+/// introduced and compatible with the first. The second added senses, which older
+/// versions would drop when saving.
+const VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vocab {
+    #[serde(default)]
+    version: u32,
+    entries: Vec<VocabEntry>,
+    #[serde(default)]
+    days: Vec<ReviewDay>,
+}
+
+impl Vocab {
+    /// Reads the vocabulary from the given file. A missing file yields an empty list.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let vocab: Self = match fs::read_to_string(path) {
+            Ok(s) => {
+                serde_json::from_str(&s).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        if vocab.version > VERSION {
+            return Err(io::Error::other(format!(
+                "{} was written by a newer version of this program",
+                path.display()
+            )));
+        }
+        Ok(vocab)
+    }
+
+    /// Like [`Vocab::load`], but a file which can't be parsed anymore is set aside and
+    /// replaced by its backup, or by an empty list if there is no usable backup.
+    pub fn load_or_recover(path: &Path) -> io::Result<(Self, Option<Recovery>)> {
+        match Self::load(path) {
+            Ok(vocab) => Ok((vocab, None)),
+            Err(e) if e.kind() == ErrorKind::InvalidData => {
+                let corrupt = store::set_aside(path)?;
+                match Self::load(&store::backup_path(path)) {
+                    Ok(vocab) if !vocab.entries.is_empty() => {
+                        vocab.save(path)?;
+                        Ok((vocab, Some(Recovery::Restored { corrupt })))
+                    }
+                    _ => Ok((Self::default(), Some(Recovery::Reset { corrupt }))),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the vocabulary atomically, keeping the previous file as a backup.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let vocab = Self {
+            version: VERSION,
+            ..self.clone()
+        };
+        store::write_with_backup(path, serde_json::to_string_pretty(&vocab)?.as_bytes())
+    }
+
+    /// Adds the translation, merging it with the entry for the German term if there is
+    /// one. Returns whether the translation was not saved before.
+    pub fn add(&mut self, german: &Term, english: &Term) -> bool {
+        let english = english.to_dictcc_string();
+        let entry = self.entry_mut(german.to_dictcc_string());
+        if entry.english.contains(&english) {
+            return false;
+        }
+        entry.english.push(english);
+        true
+    }
+
+    /// Adds the dictionary entry as a sense of its German term, along with its
+    /// translation and word classes. Returns whether the sense was not saved before.
+    pub fn add_entry(&mut self, entry: &Entry) -> bool {
+        self.add(&entry.german, &entry.english);
+        self.add_word_classes(&entry.german, &entry.word_classes);
+
+        let id = entry.id().to_string();
+        let saved = self.entry_mut(entry.german.to_dictcc_string());
+        if saved.senses.iter().any(|s| s.id == id) {
+            return false;
+        }
+        saved.senses.push(Sense {
+            id,
+            english: entry.english.to_dictcc_string(),
+            word_classes: entry.word_classes.iter().map(|c| c.to_string()).collect(),
+        });
+        true
+    }
+
+    /// The entry for the German term, which is created if there is none yet.
+    fn entry_mut(&mut self, german: String) -> &mut VocabEntry {
+        let i = match self.entries.iter().position(|e| e.german == german) {
+            Some(i) => i,
+            None => {
+                let now = lookups::now();
+                self.entries.push(VocabEntry {
+                    german,
+                    english: vec![],
+                    word_classes: vec![],
+                    senses: vec![],
+                    added: now,
+                    level: 0,
+                    due: now,
+                    correct: 0,
+                    wrong: 0,
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[i]
+    }
+
+    /// Adds the word classes to the entry for the German term, if there is one.
+    pub fn add_word_classes(&mut self, german: &Term, classes: &[WordClass]) {
+        let german = german.to_dictcc_string();
+        let Some(entry) = self.entries.iter_mut().find(|e| e.german == german) else {
+            return;
+        };
+        for class in classes {
+            if !entry.word_classes.iter().any(|c| c == class.as_str()) {
+                entry.word_classes.push(class.to_string());
+            }
+        }
+    }
+
+    pub fn entries(&self) -> &[VocabEntry] {
+        &self.entries
+    }
+
+    /// The cards due for review at the given time.
+    pub fn due(&self, now: u64) -> impl Iterator<Item = &VocabEntry> {
+        self.entries.iter().filter(move |e| e.due <= now)
+    }
+
+    /// Whether a saved German term is the word, as in "hund" for "Hund {m}".
+    pub fn contains_word(&self, word: &str) -> bool {
+        self.entries.iter().any(|e| {
+            Term::parse(&e.german).is_ok_and(|t| dictionary::is_exact(&t, word, Language::German))
+        })
+    }
+
+    pub fn get(&self, german: &str) -> Option<&VocabEntry> {
+        self.entries.iter().find(|e| e.german == german)
+    }
+
+    /// Records a quiz answer for the card with the given German term, rescheduling it
+    /// and counting it for the day.
+    pub fn answer(&mut self, german: &str, correct: bool, now: u64) {
+        let Some(entry) = self.entries.iter_mut().find(|e| e.german == german) else {
+            return;
+        };
+        entry.record(correct, now);
+
+        let today = now / DAY;
+        let day = match self.days.last_mut() {
+            Some(day) if day.day == today => day,
+            _ => {
+                self.days.push(ReviewDay {
+                    day: today,
+                    reviewed: 0,
+                    correct: 0,
+                });
+                self.days.last_mut().unwrap()
+            }
+        };
+        day.reviewed += 1;
+        day.correct += u32::from(correct);
+    }
+
+    /// The progress up to the given time, with the reviews of the last `days` days.
+    pub fn stats(&self, now: u64, days: u64) -> Stats {
+        let today = now / DAY;
+        let reviews_on = |day: u64| {
+            self.days
+                .iter()
+                .find(|d| d.day == day)
+                .map_or(0, |d| d.reviewed)
+        };
+
+        let mut streak = 0;
+        let mut day = if reviews_on(today) > 0 {
+            Some(today)
+        } else {
+            today.checked_sub(1)
+        };
+        while let Some(d) = day.filter(|&d| reviews_on(d) > 0) {
+            streak += 1;
+            day = d.checked_sub(1);
+        }
+
+        let mut longest_streak = 0;
+        let mut run = 0;
+        let mut previous = None;
+        for d in &self.days {
+            run = if previous.is_some_and(|p| p + 1 == d.day) {
+                run + 1
+            } else {
+                1
+            };
+            longest_streak = longest_streak.max(run);
+            previous = Some(d.day);
+        }
+
+        let mut boxes = vec![0; INTERVALS.len()];
+        for entry in &self.entries {
+            boxes[(entry.level as usize).min(INTERVALS.len() - 1)] += 1;
+        }
+
+        Stats {
+            recent: (0..days)
+                .rev()
+                .map(|i| reviews_on(today.saturating_sub(i)))
+                .collect(),
+            reviewed: self.days.iter().map(|d| d.reviewed).sum(),
+            correct: self.days.iter().map(|d| d.correct).sum(),
+            streak,
+            longest_streak,
+            boxes,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Writes the flashcards, ready for importing into Anki. Entries whose German terms
+    /// differ only in annotations make a single card, with the union of the annotations
+    /// and the translations, unless `per_sense` asks for a card for every saved sense.
+    /// With `grammar`, the word classes follow the German term. Returns the number of
+    /// cards written.
+    pub fn write_cards(
+        &self,
+        mut wr: impl Write,
+        format: CardFormat,
+        grammar: bool,
+        per_sense: bool,
+        placeholders: PlaceholderStyle,
+    ) -> io::Result<usize> {
+        if format == CardFormat::Tsv {
+            writeln!(wr, "#separator:tab")?;
+            writeln!(wr, "#html:false")?;
+            writeln!(wr, "#columns:German\tEnglish")?;
+        }
+
+        let cards = if per_sense {
+            self.sense_cards(placeholders)
+        } else {
+            self.merged_cards(placeholders)
+        };
+        for (front, back, classes) in &cards {
+            let front = if grammar && !classes.is_empty() {
+                format!("{} [{}]", front, classes.join(", "))
+            } else {
+                front.clone()
+            };
+            match format {
+                CardFormat::Tsv => writeln!(wr, "{}\t{}", front, back)?,
+                CardFormat::Csv => writeln!(wr, "{},{}", csv_field(&front), csv_field(back))?,
+            }
+        }
+
+        wr.flush()?;
+        Ok(cards.len())
+    }
+
+    /// A card for every saved sense, with the word classes of its entry. Entries
+    /// without senses make a card of their own. Senses whose translations are rendered
+    /// the same are told apart by their annotations, as in "bank [128 MIDI patches]".
+    fn sense_cards(&self, placeholders: PlaceholderStyle) -> Vec<(String, String, Vec<&str>)> {
+        let mut cards = vec![];
+        for entry in &self.entries {
+            if entry.senses.is_empty() {
+                let (front, back) = entry.card(placeholders);
+                let classes = entry.word_classes.iter().map(String::as_str).collect();
+                cards.push((front, back, classes));
+                continue;
+            }
+
+            let front = render_cards(&[&entry.german], placeholders).remove(0);
+            let backs: Vec<_> = entry
+                .senses
+                .iter()
+                .map(|s| render_cards(&[&s.english], placeholders).remove(0))
+                .collect();
+            for (sense, back) in entry.senses.iter().zip(&backs) {
+                let mut back = back.clone();
+                let term = Term::parse(&sense.english).ok();
+                if backs.iter().filter(|b| **b == back).count() > 1 {
+                    let annotations = term.iter().flat_map(Term::annotations);
+                    for annotation in annotations.filter(|a| a.kind != AnnotationKind::Number) {
+                        back = format!("{} {}", back, annotation);
+                    }
+                }
+                let classes = sense.word_classes.iter().map(String::as_str).collect();
+                cards.push((front.clone(), back, classes));
+            }
+        }
+        cards
+    }
+
+    /// A card for every German term, merging the ones differing only in annotations.
+    fn merged_cards(&self, placeholders: PlaceholderStyle) -> Vec<(String, String, Vec<&str>)> {
+        let mut groups: Vec<(String, Vec<&VocabEntry>)> = vec![];
+        for entry in &self.entries {
+            let (key, _) = entry.card(placeholders);
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, group)) => group.push(entry),
+                None => groups.push((key, vec![entry])),
+            }
+        }
+
+        let mut cards = vec![];
+        for (_, group) in &groups {
+            let german: Vec<_> = group.iter().map(|e| e.german.as_str()).collect();
+            let english: Vec<_> = group
+                .iter()
+                .flat_map(|e| &e.english)
+                .map(String::as_str)
+                .collect();
+            let mut classes: Vec<&str> = vec![];
+            for class in group.iter().flat_map(|e| &e.word_classes) {
+                if !classes.contains(&class.as_str()) {
+                    classes.push(class);
+                }
+            }
+
+            cards.push((
+                render_cards(&german, placeholders).join(", "),
+                render_cards(&english, placeholders).join("; "),
+                classes,
+            ));
+        }
+        cards
+    }
+}
+
+/// Renders terms in dict.cc notation for flashcards, collapsing the ones differing only
+/// in annotations. Terms which can't be parsed are kept as they are.
+fn render_cards(terms: &[&str], placeholders: PlaceholderStyle) -> Vec<String> {
+    let options = RenderOptions {
+        case_style: CaseStyle::Short,
+        placeholders,
+        ..Default::default()
+    };
+
+    let mut parsed = vec![];
+    let mut unparsed = vec![];
+    for &term in terms {
+        match Term::parse(term) {
+            Ok(t) => parsed.push(t),
+            Err(_) => unparsed.push(term.to_string()),
+        }
+    }
+
+    let mut rendered = group::collapse(&parsed, options);
+    rendered.append(&mut unparsed);
+    rendered
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CardFormat, Vocab, DAY};
+    use crate::entry::{PlaceholderStyle, Term, WordClass};
+    use crate::store::Recovery;
+    use crate::Dictionary;
+
+    #[test]
+    fn add_merges_translations() {
+        let term = |s| Term::parse(s).unwrap();
+        let mut vocab = Vocab::default();
+
+        assert!(vocab.add(&term("Hund {m}"), &term("dog")));
+        assert!(vocab.add(&term("Hund {m}"), &term("hound")));
+        assert!(!vocab.add(&term("Hund {m}"), &term("dog")));
+        assert!(vocab.add(&term("Katze {f}"), &term("cat")));
+
+        let json = serde_json::to_string(&vocab).unwrap();
+        let vocab: Vocab = serde_json::from_str(&json).unwrap();
+        assert_eq!(vocab.entries().len(), 2);
+        assert_eq!(vocab.entries()[0].german, "Hund {m}");
+        assert_eq!(vocab.entries()[0].english, ["dog", "hound"]);
+        assert!(vocab.contains_word("hund"));
+        assert!(!vocab.contains_word("Hunde"));
+    }
+
+    #[test]
+    fn write_cards() {
+        let term = |s| Term::parse(s).unwrap();
+        let mut vocab = Vocab::default();
+        vocab.add(&term("Hund {m}"), &term("dog"));
+        vocab.add(&term("Hund {m}"), &term("hound"));
+        vocab.add(&term("jdm. etw. [Akk.] leihen"), &term("to lend sb. sth."));
+
+        let mut out = vec![];
+        assert_eq!(
+            vocab
+                .write_cards(
+                    &mut out,
+                    CardFormat::Csv,
+                    false,
+                    false,
+                    PlaceholderStyle::Compact
+                )
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "der Hund,dog; hound\n\
+             jdm. etw. [Akk.] leihen,to lend sb. sth.\n"
+        );
+
+        vocab.add_word_classes(&term("Hund {m}"), &[WordClass::Noun]);
+        let mut out = vec![];
+        vocab
+            .write_cards(
+                &mut out,
+                CardFormat::Csv,
+                true,
+                false,
+                PlaceholderStyle::Expanded,
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "der Hund [noun],dog; hound\n\
+             jemandem etwas [Akk.] leihen,to lend sb. sth.\n"
+        );
+    }
+
+    #[test]
+    fn write_collapsed_cards() {
+        let term = |s| Term::parse(s).unwrap();
+        let mut vocab = Vocab::default();
+        vocab.add(&term("stark [ugs.]"), &term("strong [coll.]"));
+        vocab.add(&term("stark [fig.]"), &term("strong [fig.]"));
+        vocab.add(&term("stark [fig.]"), &term("powerful"));
+
+        let mut out = vec![];
+        let count = vocab
+            .write_cards(
+                &mut out,
+                CardFormat::Tsv,
+                false,
+                false,
+                PlaceholderStyle::Compact,
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with("\nstark [ugs.], [fig.]\tstrong [coll.], [fig.]; powerful\n"));
+    }
+
+    #[test]
+    fn cards_per_sense() {
+        let dict = Dictionary::load(
+            "Bank {f}\tbench\tnoun\t\n\
+             Bank {f}\tbank\tnoun\t[fin.]\n\
+             Bank {f}\tbank [128 MIDI patches]\tnoun\t[mus.]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let entries = dict.entries();
+        let mut vocab = Vocab::default();
+
+        assert!(vocab.add_entry(&entries[0]));
+        assert!(vocab.add_entry(&entries[1]));
+        assert!(!vocab.add_entry(&entries[0]));
+        assert!(vocab.add_entry(&entries[2]));
+
+        let json = serde_json::to_string(&vocab).unwrap();
+        let vocab: Vocab = serde_json::from_str(&json).unwrap();
+        let bank = &vocab.entries()[0];
+        assert_eq!(bank.english, ["bench", "bank", "bank [128 MIDI patches]"]);
+        assert_eq!(bank.senses.len(), 3);
+        assert_eq!(bank.senses[1].id, entries[1].id().to_string());
+
+        let export = |per_sense| {
+            let mut out = vec![];
+            vocab
+                .write_cards(
+                    &mut out,
+                    CardFormat::Csv,
+                    true,
+                    per_sense,
+                    PlaceholderStyle::Compact,
+                )
+                .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            export(false),
+            "die Bank [noun],bench; bank [128 MIDI patches]\n"
+        );
+        assert_eq!(
+            export(true),
+            "die Bank [noun],bench\n\
+             die Bank [noun],bank\n\
+             die Bank [noun],bank [128 MIDI patches]\n"
+        );
+    }
+
+    #[test]
+    fn quiz_answers() {
+        let term = |s| Term::parse(s).unwrap();
+        let mut vocab = Vocab::default();
+        vocab.add(&term("Hund {m}"), &term("dog"));
+        vocab.add(&term("laufen"), &term("to run"));
+
+        let dog = &vocab.entries()[0];
+        assert!(dog.check("der Hund"));
+        assert!(dog.check("Der hund"));
+        assert!(!dog.check("Hund"));
+        assert!(!dog.check("die Hund"));
+        assert!(vocab.entries()[1].check("laufen"));
+
+        vocab.answer("Hund {m}", true, 0);
+        vocab.answer("Hund {m}", true, 0);
+        let dog = vocab.get("Hund {m}").unwrap();
+        assert_eq!((dog.level, dog.due), (2, 3 * DAY));
+        vocab.answer("Hund {m}", false, 10);
+        let dog = vocab.get("Hund {m}").unwrap();
+        assert_eq!((dog.level, dog.due, dog.correct, dog.wrong), (0, 10, 2, 1));
+        assert_eq!(vocab.due(10).count(), 1);
+    }
+
+    #[test]
+    fn recover_from_corruption() {
+        let dir = std::env::temp_dir().join(format!("vocab-test-{}", std::process::id()));
+        let path = dir.join("vocab.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut vocab = Vocab::default();
+        vocab.add(
+            &Term::parse("Hund {m}").unwrap(),
+            &Term::parse("dog").unwrap(),
+        );
+        vocab.save(&path).unwrap();
+        vocab.save(&path).unwrap();
+        std::fs::write(&path, "{\"entries\": [").unwrap();
+
+        let (recovered, recovery) = Vocab::load_or_recover(&path).unwrap();
+        assert_eq!(recovered.entries(), vocab.entries());
+        assert!(matches!(recovery, Some(Recovery::Restored { .. })));
+        assert_eq!(Vocab::load(&path).unwrap().entries(), vocab.entries());
+
+        std::fs::write(&path, "{\"version\": 3, \"entries\": []}").unwrap();
+        assert!(Vocab::load_or_recover(&path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stats() {
+        let mut vocab = Vocab::default();
+        vocab.add(
+            &Term::parse("Hund {m}").unwrap(),
+            &Term::parse("dog").unwrap(),
+        );
+        for (day, correct) in [
+            (1, true),
+            (2, false),
+            (2, true),
+            (3, true),
+            (5, true),
+            (6, true),
+        ] {
+            vocab.answer("Hund {m}", correct, day * DAY);
+        }
+
+        let stats = vocab.stats(7 * DAY + 1, 4);
+        assert_eq!(stats.recent, [0, 1, 1, 0]);
+        assert_eq!((stats.reviewed, stats.correct), (6, 5));
+        assert_eq!((stats.streak, stats.longest_streak), (2, 3));
+        assert_eq!(stats.boxes, [0, 0, 0, 0, 1, 0]);
+
+        assert_eq!(vocab.stats(8 * DAY, 1).streak, 0);
+    }
+}
@@ -0,0 +1,56 @@
+//! Lookups from JavaScript, for embedding the dictionary into web pages. The manifest
+//! only builds an rlib, so the WebAssembly module is built as a cdylib explicitly:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/dict_cc_lookup.wasm
+//! ```
+//!
+//! The package exports a `Dictionary` class:
+//!
+//! ```js
+//! const dict = new Dictionary(new Uint8Array(await (await fetch("dict.txt")).arrayBuffer()));
+//! const entries = dict.lookup("Hund", "de");
+//! ```
+//!
+//! The page hands over the dictionary data, as there are no files to read it from.
+//! Entries have the shape of [`EntryJson`](crate::json::EntryJson).
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    dictionary::Deadline, json, loader::DictionarySource, query::Language, rank::Weights,
+    Dictionary,
+};
+
+#[wasm_bindgen(js_name = Dictionary)]
+pub struct WasmDictionary {
+    dict: Dictionary,
+}
+
+#[wasm_bindgen(js_class = Dictionary)]
+impl WasmDictionary {
+    /// Loads a dict.cc export, plain or compressed with gzip or zstd.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8]) -> Result<WasmDictionary, JsError> {
+        let source = DictionarySource::from_bytes(data.to_vec());
+        let dict = Dictionary::load(source.reader()?)?;
+        Ok(Self { dict })
+    }
+
+    /// The entries exactly matching the word, best first, like the HTTP mode answers
+    /// them. `lang` is "de" or "en".
+    pub fn lookup(&self, word: &str, lang: &str) -> Result<JsValue, JsError> {
+        let language = Language::from_code(lang)
+            .ok_or_else(|| JsError::new(&format!("unknown language \"{}\"", lang)))?;
+        let entries = json::exact_matches(
+            &self.dict,
+            word,
+            language,
+            &Weights::default(),
+            &Deadline::default(),
+            |_| true,
+        );
+        Ok(serde_wasm_bindgen::to_value(&entries)?)
+    }
+}
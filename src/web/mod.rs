@@ -0,0 +1,7 @@
+//! The HTTP mode and its assets.
+
+pub mod server;
+
+/// A single page search UI. It expects `GET /api/search?q=<word>&lang=<de|en>` to
+/// answer with a JSON array of [`crate::json::EntryJson`].
+pub const WIDGET_HTML: &str = include_str!("widget.html");
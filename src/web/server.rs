@@ -0,0 +1,550 @@
+//! A minimal HTTP/1.1 server answering lookups from a dictionary loaded once, for
+//! browser extensions and the like. Every connection carries a single request.
+//!
+//! - `GET /` serves the search page.
+//! - `GET /meaning?q=<word>&lang=<de|en>` and `GET /api/search?...` answer with the
+//!   entries exactly matching the word, as an array of
+//!   [`EntryJson`](crate::json::EntryJson).
+//! - `GET /stream?q=<word>&lang=<de|en>` answers with the same entries as newline
+//!   delimited JSON in a chunked body, a chunk per entry as soon as it is found. The
+//!   entries come in dictionary order, unranked.
+//! - `GET /gender?q=<word>` answers with an array of [`GenderJson`].
+//! - `POST /batch` takes a [`BatchRequest`] and answers with an array of
+//!   [`BatchResult`](crate::json::BatchResult).
+//!
+//! Queries are normalized like the CLI does, see [`query::normalize`].
+//!
+//! With a timeout in the options, responses whose lookups were cut short carry an
+//! `X-Truncated: true` header. Streams, whose headers are sent before the lookup, end
+//! with a `{"truncated":true}` line instead.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{
+    dictionary::Deadline,
+    json::{self, BatchRequest, GenderJson},
+    query::{self, Language, NormalizeOptions, Options},
+    Dictionary,
+};
+
+use super::WIDGET_HTML;
+
+/// Only reachable from the local machine.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8547";
+
+/// Requests larger than this are rejected, the largest legitimate ones are batches.
+const MAX_BODY: usize = 1 << 20;
+
+/// Request lines and headers longer than this together are rejected.
+const MAX_HEAD: u64 = 16 << 10;
+
+/// How long a client may take to send its whole request. Connections are answered one
+/// at a time, so a client which stops sending or sends slowly must not block everyone
+/// else.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const NDJSON: &str = "application/x-ndjson";
+
+/// The parts of a request the server looks at.
+#[derive(Debug, Default)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// The decoded query string parameters, in order.
+    pub params: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The value of the query string parameter.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    /// Whether the results are incomplete because the timeout was hit.
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+struct ErrorJson<'a> {
+    error: &'a str,
+}
+
+/// Answers requests until accepting a connection fails. Errors of single connections
+/// are passed to `on_error` and don't stop the server.
+pub fn serve(
+    listener: &TcpListener,
+    dict: &Dictionary,
+    options: &Options,
+    mut on_error: impl FnMut(io::Error),
+) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept()?;
+        if let Err(e) = handle(stream, dict, options) {
+            on_error(e);
+        }
+    }
+}
+
+fn handle(stream: TcpStream, dict: &Dictionary, options: &Options) -> io::Result<()> {
+    // Neither must a client which doesn't read the response.
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let rd = Timed {
+        stream: &stream,
+        deadline: Instant::now() + REQUEST_TIMEOUT,
+    };
+    let response = match read_request(&mut BufReader::new(rd)) {
+        Ok(request) if request.method == "GET" && request.path == "/stream" => {
+            match lookup_params(&request) {
+                Ok((word, language)) => {
+                    return stream_matches(&stream, dict, &word, language, options)
+                }
+                Err(response) => response,
+            }
+        }
+        Ok(request) => respond(&request, dict, options),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => error(400, &e.to_string()),
+        Err(e) => return Err(e),
+    };
+
+    write_response(&stream, &response)
+}
+
+/// Reads from the stream until the deadline, however the reads are spread over time.
+struct Timed<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Timed<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the request took too long",
+            ));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+fn read_request(rd: &mut impl BufRead) -> io::Result<Request> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let mut head = rd.take(MAX_HEAD);
+    let mut read_line = |line: &mut String| match head.read_line(line)? {
+        0 => Ok(0),
+        _ if !line.ends_with('\n') && head.limit() == 0 => {
+            Err(invalid("request headers too large"))
+        }
+        n => Ok(n),
+    };
+
+    let mut line = String::new();
+    read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut request = Request {
+        method: method.to_string(),
+        path: percent_decode(path),
+        params: parse_query(query),
+        body: vec![],
+    };
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        if read_line(&mut line)? == 0 {
+            return Err(invalid("unexpected end of headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("invalid Content-Length"))?;
+            }
+        }
+    }
+
+    if length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+    request.body = vec![0; length];
+    rd.read_exact(&mut request.body)?;
+
+    Ok(request)
+}
+
+fn write_response(mut wr: impl Write, response: &Response) -> io::Result<()> {
+    write_head(&mut wr, response.status, response.content_type)?;
+    write!(wr, "Content-Length: {}\r\n", response.body.len())?;
+    if response.truncated {
+        wr.write_all(b"X-Truncated: true\r\n")?;
+    }
+    wr.write_all(b"\r\n")?;
+    wr.write_all(&response.body)?;
+    wr.flush()
+}
+
+/// Writes the status line and the headers every response has.
+fn write_head(mut wr: impl Write, status: u16, content_type: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+
+    write!(
+        wr,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n",
+        status, reason, content_type,
+    )
+}
+
+/// Answers `GET /stream`, writing every match as soon as it is found. As the headers
+/// are sent before the lookup, a timeout ends the body with a line saying so.
+fn stream_matches(
+    mut wr: impl Write,
+    dict: &Dictionary,
+    word: &str,
+    language: Language,
+    options: &Options,
+) -> io::Result<()> {
+    write_head(&mut wr, 200, NDJSON)?;
+    wr.write_all(b"Transfer-Encoding: chunked\r\n\r\n")?;
+
+    let deadline = Deadline::after(options.timeout);
+    let mut chunked = Chunked::new(wr);
+    let entries = dict.matches(word, language, &deadline).map(Ok);
+    json::write_ndjson(entries, |e| options.keeps(e), usize::MAX, &mut chunked)?;
+    if deadline.hit() {
+        writeln!(chunked, r#"{{"truncated":true}}"#)?;
+    }
+    chunked.finish()
+}
+
+/// Sends what was written between two flushes as one chunk of a chunked body.
+struct Chunked<W: Write> {
+    wr: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Chunked<W> {
+    fn new(wr: W) -> Self {
+        Self { wr, buf: vec![] }
+    }
+
+    /// Sends the last chunk, which ends the body.
+    fn finish(mut self) -> io::Result<()> {
+        self.flush()?;
+        self.wr.write_all(b"0\r\n\r\n")?;
+        self.wr.flush()
+    }
+}
+
+impl<W: Write> Write for Chunked<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            write!(self.wr, "{:x}\r\n", self.buf.len())?;
+            self.wr.write_all(&self.buf)?;
+            self.wr.write_all(b"\r\n")?;
+            self.buf.clear();
+        }
+        self.wr.flush()
+    }
+}
+
+/// The normalized word and the language of lookups, or the response to an invalid
+/// request.
+fn lookup_params(request: &Request) -> Result<(String, Language), Response> {
+    let Some(word) = request.param("q").filter(|q| !q.trim().is_empty()) else {
+        return Err(error(400, "missing query parameter \"q\""));
+    };
+    let lang = request.param("lang").unwrap_or("de");
+    let Some(language) = Language::from_code(lang) else {
+        return Err(error(400, &format!("unknown language \"{}\"", lang)));
+    };
+
+    let word = query::normalize(word, &NormalizeOptions::for_language(language));
+    Ok((word, language))
+}
+
+/// Answers a single request.
+pub fn respond(request: &Request, dict: &Dictionary, options: &Options) -> Response {
+    let deadline = Deadline::after(options.timeout);
+    let mut response = route(request, dict, options, &deadline);
+    response.truncated = deadline.hit();
+    response
+}
+
+fn route(request: &Request, dict: &Dictionary, options: &Options, deadline: &Deadline) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: WIDGET_HTML.as_bytes().to_vec(),
+            truncated: false,
+        },
+        ("GET", "/meaning" | "/api/search") => {
+            let (word, language) = match lookup_params(request) {
+                Ok(params) => params,
+                Err(response) => return response,
+            };
+            let entries =
+                json::exact_matches(dict, &word, language, &options.weights, deadline, |e| {
+                    options.keeps(e)
+                });
+            ok(&entries)
+        }
+        ("GET", "/gender") => {
+            let Some(word) = request.param("q").filter(|q| !q.trim().is_empty()) else {
+                return error(400, "missing query parameter \"q\"");
+            };
+            let word = query::normalize(word, &NormalizeOptions::for_language(Language::German));
+            let genders: Vec<GenderJson> = json::genders(dict, dict, &word);
+            ok(&genders)
+        }
+        // Connections stream, this answers at once for callers of `respond`.
+        ("GET", "/stream") => {
+            let (word, language) = match lookup_params(request) {
+                Ok(params) => params,
+                Err(response) => return response,
+            };
+            let entries = dict.matches(&word, language, deadline).map(Ok);
+            let mut body = vec![];
+            match json::write_ndjson(entries, |e| options.keeps(e), usize::MAX, &mut body) {
+                Ok(_) => Response {
+                    status: 200,
+                    content_type: NDJSON,
+                    body,
+                    truncated: false,
+                },
+                Err(e) => error(500, &e.to_string()),
+            }
+        }
+        ("POST", "/batch") => match serde_json::from_slice::<BatchRequest>(&request.body) {
+            Ok(batch) => ok(&json::batch(dict, &batch, deadline)),
+            Err(e) => error(400, &e.to_string()),
+        },
+        (_, "/" | "/meaning" | "/api/search" | "/stream" | "/gender" | "/batch") => {
+            error(405, "method not allowed")
+        }
+        _ => error(404, "not found"),
+    }
+}
+
+fn ok(value: &impl Serialize) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => Response {
+            status: 200,
+            content_type: "application/json",
+            body,
+            truncated: false,
+        },
+        Err(e) => error(500, &e.to_string()),
+    }
+}
+
+fn error(status: u16, message: &str) -> Response {
+    Response {
+        status,
+        content_type: "application/json",
+        body: serde_json::to_vec(&ErrorJson { error: message }).unwrap_or_default(),
+        truncated: false,
+    }
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes and `+` for spaces. Malformed escapes are kept as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_requests() {
+        let dict = Dictionary::load(
+            "Hund {m}\tdog\tnoun\t\n\
+             Hunde {pl}\tdogs\tnoun\t\n\
+             über\tabove\tprep\t\n\
+             gehen\tto go\tverb\t\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let options = Options::default();
+        let get = |target: &str| {
+            let raw = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target);
+            let request = read_request(&mut raw.as_bytes()).unwrap();
+            let response = respond(&request, &dict, &options);
+            (response.status, String::from_utf8(response.body).unwrap())
+        };
+
+        let (status, body) = get("/meaning?q=%C3%BCber&lang=de");
+        assert_eq!(status, 200);
+        assert!(body.contains("\"above\""), "{}", body);
+
+        let (status, body) = get("/meaning?q=to%20go&lang=en");
+        assert_eq!(status, 200);
+        assert!(body.contains("\"gehen\""), "{}", body);
+
+        let (status, body) = get("/gender?q=Hund");
+        assert_eq!(status, 200);
+        assert!(body.contains("\"plural_form\":\"Hunde\""), "{}", body);
+
+        let (status, body) = get("/stream?q=Hund");
+        assert_eq!(status, 200);
+        assert_eq!(body.lines().count(), 1);
+        assert!(body.contains("\"dog\""), "{}", body);
+
+        assert_eq!(get("/meaning?lang=de").0, 400);
+        assert_eq!(get("/meaning?q=Hund&lang=fr").0, 400);
+        assert_eq!(get("/nothing").0, 404);
+
+        let body = r#"{"queries": ["Hund", "to  go"], "language": "english"}"#;
+        let raw = format!(
+            "POST /batch HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        let response = respond(&request, &dict, &options);
+        assert_eq!(response.status, 200);
+        assert!(String::from_utf8(response.body)
+            .unwrap()
+            .contains("\"gehen\""));
+
+        let impatient = Options {
+            timeout: Some(Duration::ZERO),
+            ..Options::default()
+        };
+        let response = respond(&request, &dict, &impatient);
+        assert!(response.truncated);
+        assert!(!respond(&request, &dict, &options).truncated);
+    }
+
+    #[test]
+    fn rejects_large_headers() {
+        let endless = format!("GET /?q={} HTTP/1.1\r\n", "a".repeat(1 << 20));
+        let many = format!("GET / HTTP/1.1\r\n{}\r\n", "X-A: b\r\n".repeat(1 << 12));
+
+        for raw in [endless, many] {
+            let e = read_request(&mut raw.as_bytes()).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn streams_chunks() {
+        let dict = Dictionary::load("Hund {m}\tdog\tnoun\t\nHund {m}\thound\tnoun\t\n".as_bytes())
+            .unwrap();
+        let mut out = vec![];
+        stream_matches(
+            &mut out,
+            &dict,
+            "Hund",
+            Language::German,
+            &Options::default(),
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let (head, body) = out.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains("Transfer-Encoding: chunked"), "{}", head);
+        let chunks: Vec<_> = body.split("\r\n").collect();
+        assert!(chunks[1].contains("\"dog\"") && chunks[3].contains("\"hound\""));
+        assert_eq!(
+            usize::from_str_radix(chunks[0], 16).unwrap(),
+            chunks[1].len()
+        );
+        assert!(body.ends_with("\r\n0\r\n\r\n"), "{:?}", body);
+        assert!(!body.contains("truncated"), "{:?}", body);
+
+        let impatient = Options {
+            timeout: Some(Duration::ZERO),
+            ..Options::default()
+        };
+        let mut out = vec![];
+        stream_matches(&mut out, &dict, "Hund", Language::German, &impatient).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(
+            out.ends_with("\r\n{\"truncated\":true}\n\r\n0\r\n\r\n"),
+            "{:?}",
+            out
+        );
+    }
+}